@@ -104,7 +104,7 @@ impl<'a, C: TodoActions> Render<'a> for Todo<C> {
 
         let bump = cx.bump;
         dodrio!(bump,
-            <li class={
+            <li key={id as u32} class={
                 let mut class = ClassList::new();
                 if self.completed {
                     class.add("completed");
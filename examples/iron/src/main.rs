@@ -4,7 +4,8 @@ use iron::headers::ContentType;
 use iron::modifier::Modifier;
 use iron::prelude::*;
 use iron::status;
-use typed_html::elements::FlowContent;
+use typed_html::component::Component;
+use typed_html::elements::{self, FlowContent};
 use typed_html::types::LinkType;
 use typed_html::{dom::DOMTree, html, text, OutputType};
 
@@ -17,31 +18,39 @@ impl Modifier<Response> for Html {
     }
 }
 
-// Function that wraps a DOM node in an HTML document, to demonstrate how you'd
-// do this sort of templating.
+// Component that wraps its children in an HTML document, to demonstrate how
+// you'd do this sort of templating.
 //
-// It's a bit more complicated than you'd hope because you need to take an input
-// argument of the type that the element that you're inserting it into expects,
-// which in the case of `<body>` is `FlowContent`, not just `Node`, so you can't
-// pass it a `DOMTree<T>` or you'll get a type error.
-fn doc<T: OutputType + 'static + Send>(tree: Box<dyn FlowContent<T>>) -> DOMTree<T> {
-    html!(
-        <html>
-            <head>
-                <title>"Hello Kitty!"</title>
-                <link rel=LinkType::StyleSheet href="lol.css"/>
-            </head>
-            <body>
-                { tree }
-            </body>
-        </html>
-    )
+// Its children are `FlowContent`, because that's what `<body>` expects, and it
+// renders into an `<html>` element, which can only be used as the root of a
+// tree.
+struct Page {
+    title: &'static str,
+}
+
+impl<T: OutputType + 'static + Send> Component<T> for Page {
+    type Children = dyn FlowContent<T>;
+    type Output = elements::html<T>;
+
+    fn render(self, children: Vec<Box<dyn FlowContent<T>>>) -> Box<elements::html<T>> {
+        html!(
+            <html>
+                <head>
+                    <title>{ text!(self.title) }</title>
+                    <link rel=LinkType::StyleSheet href="lol.css"/>
+                </head>
+                <body>
+                    { children }
+                </body>
+            </html>
+        )
+    }
 }
 
 fn index() -> Html {
     let a = true;
-    Html(doc(html!(
-        <div>
+    Html(html!(
+        <Page title="Hello Kitty!">
             <h1 data-lol="omg">"Hello Kitty!"</h1>
             <p class="official-position-of-sanrio-ltd emphasis">
                 "She is not a "<em><a href="https://en.wikipedia.org/wiki/Cat">"cat"</a></em>". She is a "<em>"human girl"</em>"."
@@ -54,8 +63,8 @@ fn index() -> Html {
             }
             <p>"<img src=\"javascript:alert('pwned lol')\">"</p>
             <button disabled=a onclick="alert('She is not a cat.')">"Click me!"</button>
-        </div>
-    : String)))
+        </Page>
+    : String))
 }
 
 fn main() {
@@ -27,5 +27,5 @@ fn main() {
     let document = web::document();
     let body = document.body().expect("no body element in doc");
     let tree = Stdweb::build(&document, vdom).unwrap();
-    body.append_child(&tree);
+    body.append_child(tree.node());
 }
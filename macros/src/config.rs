@@ -38,6 +38,12 @@ pub fn global_attrs(span: Span) -> StringyMap<Ident, TokenStream> {
     attrs
 }
 
+/// The content categories which `TextNode` belongs to.
+pub static TEXT_CONTENT: &[&str] = &["FlowContent", "PhrasingContent"];
+
+/// Elements whose text content is rendered without escaping.
+pub static RAW_TEXT: &[&str] = &["script", "style"];
+
 pub static SELF_CLOSING: &[&str] = &[
     "area",
     "base",
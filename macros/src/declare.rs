@@ -1,11 +1,12 @@
-use proc_macro2::{Ident, Literal, TokenStream, TokenTree};
+use proc_macro2::{Ident, Literal, Span, TokenStream, TokenTree};
 use quote::quote;
 
-use crate::config::{global_attrs, SELF_CLOSING};
+use crate::config::{global_attrs, RAW_TEXT, SELF_CLOSING, TEXT_CONTENT};
 use crate::error::ParseError;
 use crate::ident;
 use crate::lexer::{Lexer, Token};
 use crate::map::StringyMap;
+use crate::order::ChildOrder;
 use crate::parser;
 
 // State
@@ -15,6 +16,7 @@ pub struct Declare {
     pub attrs: StringyMap<Ident, TokenStream>,
     pub req_children: Vec<Ident>,
     pub opt_children: Option<TokenStream>,
+    pub child_order: Option<ChildOrder>,
     pub traits: Vec<TokenStream>,
 }
 
@@ -24,6 +26,7 @@ impl Declare {
             attrs: global_attrs(name.span()),
             req_children: Vec::new(),
             opt_children: None,
+            child_order: None,
             traits: Vec::new(),
             name,
         }
@@ -34,22 +37,29 @@ impl Declare {
     }
 
     fn attr_type_name(&self) -> TokenTree {
-        Ident::new(
-            &format!("Attrs_{}", self.name),
-            self.name.span(),
-        )
-        .into()
+        Ident::new(&format!("Attrs_{}", self.name), self.name.span()).into()
     }
 
     fn attrs(&self) -> impl Iterator<Item = (TokenTree, TokenStream, TokenTree)> + '_ {
         self.attrs.iter().map(|(key, value)| {
             let attr_name: TokenTree = ident::new_raw(&key.to_string(), key.span()).into();
             let attr_type = value.clone();
-            let attr_str = Literal::string(&key.to_string()).into();
+            let attr_str = Literal::string(&key.to_string().replace('_', "-")).into();
             (attr_name, attr_type, attr_str)
         })
     }
 
+    /// Like `attrs()`, but with the attribute name as it's written in HTML,
+    /// and whether it's a boolean attribute.
+    fn html_attrs(&self) -> impl Iterator<Item = (TokenTree, bool, TokenTree)> + '_ {
+        self.attrs.iter().map(|(key, value)| {
+            let attr_name: TokenTree = ident::new_raw(&key.to_string(), key.span()).into();
+            let is_bool = value.to_string().ends_with("Bool");
+            let html_name = Literal::string(&key.to_string().replace('_', "-")).into();
+            (attr_name, is_bool, html_name)
+        })
+    }
+
     fn req_children(&self) -> impl Iterator<Item = (TokenTree, TokenTree, TokenTree)> + '_ {
         self.req_children.iter().map(|child| {
             let child_name: TokenTree =
@@ -60,6 +70,61 @@ impl Declare {
         })
     }
 
+    /// The fields holding the position of each required child among the
+    /// optional ones, for elements which have both.
+    fn req_positions(&self) -> Vec<(TokenTree, TokenTree)> {
+        if self.opt_children.is_none() {
+            return Vec::new();
+        }
+        self.req_children()
+            .map(|(child_name, _, _)| {
+                let position = Ident::new(&format!("{}_position", child_name), child_name.span());
+                (child_name, position.into())
+            })
+            .collect()
+    }
+
+    /// Generate code which calls `each` on the optional children and
+    /// `each_required` on the required ones, in document order.
+    fn each_child(
+        &self,
+        children: TokenStream,
+        each: TokenStream,
+        each_required: impl Fn(&TokenTree) -> TokenStream,
+    ) -> TokenStream {
+        if self.opt_children.is_none() {
+            return self
+                .req_children()
+                .map(|(child_name, _, _)| each_required(&child_name))
+                .collect();
+        }
+        let positions = self.req_positions();
+        if positions.is_empty() {
+            return quote!(for child in #children {
+                #each
+            });
+        }
+        let mut before = TokenStream::new();
+        let mut after = TokenStream::new();
+        for (child_name, position) in &positions {
+            let required = each_required(child_name);
+            before.extend(quote!(if index == self.#position {
+                #required
+            }));
+            after.extend(quote!(if self.#position >= count {
+                #required
+            }));
+        }
+        quote!(
+            let count = self.children.len();
+            for (index, child) in #children.enumerate() {
+                #before
+                #each
+            }
+            #after
+        )
+    }
+
     pub fn into_token_stream(self) -> TokenStream {
         let mut stream = TokenStream::new();
         stream.extend(self.attr_struct());
@@ -70,6 +135,7 @@ impl Declare {
         stream.extend(self.impl_marker_traits());
         stream.extend(self.impl_display());
         stream.extend(self.impl_into_iter());
+        stream.extend(self.impl_parse());
         stream
     }
 
@@ -79,11 +145,58 @@ impl Declare {
             body.extend(quote!( pub #attr_name: Option<#attr_type>, ));
         }
 
+        let mut merge = TokenStream::new();
+        for (attr_name, _, _) in self.attrs() {
+            merge.extend(quote!(
+                if other.#attr_name.is_some() {
+                    self.#attr_name = other.#attr_name;
+                }
+            ));
+        }
+
+        let mut set_attrs = TokenStream::new();
+        for (attr_name, is_bool, html_name) in self.html_attrs() {
+            let value = if is_bool {
+                // Boolean attributes are true when present, whatever their value.
+                quote!(crate::types::Bool::True)
+            } else {
+                quote!(value
+                    .parse()
+                    .map_err(|err| { crate::dom::AttributeError::Invalid(format!("{}", err)) })?)
+            };
+            set_attrs.extend(quote!(
+                #html_name => self.#attr_name = Some(#value),
+            ));
+        }
+
         let attr_type_name = self.attr_type_name();
         quote!(
+            #[derive(Default)]
             pub struct #attr_type_name {
                 #body
             }
+
+            impl #attr_type_name {
+                /// Set every attribute which is set in `other`, leaving the
+                /// rest as they are.
+                pub fn merge(&mut self, other: Self) {
+                    #merge
+                }
+
+                /// Set an attribute from its HTML name and string value,
+                /// parsing the value into the attribute's type.
+                pub fn set(
+                    &mut self,
+                    name: &str,
+                    value: &str,
+                ) -> Result<(), crate::dom::AttributeError> {
+                    match name {
+                        #set_attrs
+                        _ => return Err(crate::dom::AttributeError::Unknown),
+                    }
+                    Ok(())
+                }
+            }
         )
     }
 
@@ -96,6 +209,17 @@ impl Declare {
         for (child_name, child_type, _) in self.req_children() {
             body.extend(quote!( pub #child_name: Box<#child_type<T>>, ));
         }
+        for (child_name, position) in self.req_positions() {
+            let doc = format!(
+                "Where `{}` goes among `children`: it's rendered before the child at this \
+                 index, or after them all if there are fewer.",
+                child_name
+            );
+            body.extend(quote!(
+                #[doc = #doc]
+                pub #position: usize,
+            ));
+        }
 
         if let Some(child_constraint) = &self.opt_children {
             let child_constraint = child_constraint.clone();
@@ -105,7 +229,10 @@ impl Declare {
         quote!(
             pub struct #elem_name<T> where T: crate::OutputType + Send {
                 pub attrs: #attr_type_name,
-                pub data_attributes: Vec<(&'static str, String)>,
+                pub data_attributes: Vec<(std::borrow::Cow<'static, str>, String)>,
+                /// The key identifying the element among its siblings, as
+                /// given by the `key` pseudo-attribute. It isn't rendered.
+                pub key: Option<String>,
                 pub events: T::Events,
                 #body
             }
@@ -131,10 +258,14 @@ impl Declare {
             attrs: #attr_type_name { #attrs },
         ));
         body.extend(quote!(data_attributes: Vec::new(),));
+        body.extend(quote!(key: None,));
 
         for (child_name, _, _) in self.req_children() {
             body.extend(quote!( #child_name, ));
         }
+        for (_, position) in self.req_positions() {
+            body.extend(quote!( #position: 0, ));
+        }
         if self.opt_children.is_some() {
             body.extend(quote!(children: Vec::new()));
         }
@@ -153,24 +284,24 @@ impl Declare {
 
     fn impl_vnode(&self) -> TokenStream {
         let elem_name = TokenTree::Literal(Literal::string(self.name.to_string().as_str()));
-        let mut req_children = TokenStream::new();
-        for (child_name, _, _) in self.req_children() {
-            req_children.extend(quote!(
-                children.push(self.#child_name.vnode());
-            ));
-        }
-        let mut opt_children = TokenStream::new();
-        if self.opt_children.is_some() {
-            opt_children.extend(quote!(for child in &mut self.children {
-                children.push(child.vnode());
-            }));
-        }
+        // Each required child is taken out of an `Option` when it's reached,
+        // so that the borrow checker can see it's only borrowed once.
+        let req_children: Vec<TokenTree> = self.req_children().map(|(name, _, _)| name).collect();
+        let push_children = self.each_child(
+            quote!(self.children.iter_mut()),
+            quote!(children.push(child.vnode());),
+            |child_name| {
+                quote!(if let Some(child) = #child_name.take() {
+                    children.push(child.vnode());
+                })
+            },
+        );
 
         let mut push_attrs = TokenStream::new();
         for (attr_name, _, attr_str) in self.attrs() {
             push_attrs.extend(quote!(
                 if let Some(ref value) = self.attrs.#attr_name {
-                    attributes.push((#attr_str, value.to_string()));
+                    attributes.push((#attr_str.into(), value.to_string()));
                 }
             ));
         }
@@ -181,12 +312,13 @@ impl Declare {
             attributes.extend(self.data_attributes.clone());
 
             let mut children = Vec::new();
-            #req_children
-            #opt_children
+            #( let mut #req_children = Some(&mut self.#req_children); )*
+            #push_children
 
             crate::dom::VNode::Element(crate::dom::VElement {
                 name: #elem_name,
                 attributes,
+                key: self.key.clone(),
                 events: &mut self.events,
                 children
             })
@@ -196,11 +328,33 @@ impl Declare {
     fn impl_node(&self) -> TokenStream {
         let elem_name = self.elem_name();
         let vnode = self.impl_vnode();
+        let visit_fn = self.visit_fn_name();
+        let visit_mut_fn = self.visit_mut_fn_name();
+        let visit_children = self.each_child(
+            quote!(self.children.iter()),
+            quote!(child.accept(visitor);),
+            |child_name| quote!(self.#child_name.accept(visitor);),
+        );
+        let visit_children_mut = self.each_child(
+            quote!(self.children.iter_mut()),
+            quote!(child.accept_mut(visitor);),
+            |child_name| quote!(self.#child_name.accept_mut(visitor);),
+        );
         quote!(
             impl<T> crate::dom::Node<T> for #elem_name<T> where T: crate::OutputType + Send {
                 fn vnode(&'_ mut self) -> crate::dom::VNode<'_, T> {
                     #vnode
                 }
+
+                fn accept(&self, visitor: &mut dyn crate::elements::Visitor<T>) {
+                    visitor.#visit_fn(self);
+                    #visit_children
+                }
+
+                fn accept_mut(&mut self, visitor: &mut dyn crate::elements::VisitorMut<T>) {
+                    visitor.#visit_mut_fn(self);
+                    #visit_children_mut
+                }
             }
         )
     }
@@ -208,6 +362,7 @@ impl Declare {
     fn impl_element(&self) -> TokenStream {
         let name: TokenTree = Literal::string(&self.name.to_string()).into();
         let elem_name = self.elem_name();
+        let attr_type_name = self.attr_type_name();
 
         let attrs: TokenStream = self.attrs().map(|(_, _, name)| quote!( #name, )).collect();
         let reqs: TokenStream = self
@@ -219,7 +374,7 @@ impl Declare {
         for (attr_name, _, attr_str) in self.attrs() {
             push_attrs.extend(quote!(
                 if let Some(ref value) = self.attrs.#attr_name {
-                    out.push((#attr_str, value.to_string()));
+                    out.push((#attr_str.into(), value.to_string()));
                 }
             ));
         }
@@ -230,6 +385,10 @@ impl Declare {
                     #name
                 }
 
+                fn element_name(&self) -> &'static str {
+                    #name
+                }
+
                 fn attribute_names() -> &'static [&'static str] {
                     &[ #attrs ]
                 }
@@ -238,14 +397,41 @@ impl Declare {
                     &[ #reqs ]
                 }
 
-                fn attributes(&self) -> Vec<(&'static str, String)> {
+                fn child_order() -> Option<&'static crate::elements::ChildOrder> {
+                    crate::elements::child_orders::#elem_name
+                }
+
+                fn attributes(&self) -> Vec<(std::borrow::Cow<'static, str>, String)> {
                     let mut out = Vec::new();
                     #push_attrs
-                    for (key, value) in &self.data_attributes {
-                        out.push((key, value.to_string()));
-                    }
+                    out.extend(self.data_attributes.iter().cloned());
                     out
                 }
+
+                fn set_attribute(
+                    &mut self,
+                    name: &str,
+                    value: &str,
+                ) -> Result<(), crate::dom::AttributeError> {
+                    if name.starts_with("data-") {
+                        self.data_attributes.retain(|(key, _)| key != name);
+                        self.data_attributes.push((name.to_string().into(), value.to_string()));
+                        Ok(())
+                    } else {
+                        self.attrs.set(name, value)
+                    }
+                }
+
+                fn check_attribute(
+                    name: &str,
+                    value: &str,
+                ) -> Result<(), crate::dom::AttributeError> {
+                    if name.starts_with("data-") {
+                        Ok(())
+                    } else {
+                        #attr_type_name::default().set(name, value)
+                    }
+                }
             }
         )
     }
@@ -257,8 +443,27 @@ impl Declare {
             let name = t.clone();
             body.extend(quote!(
                 impl<T> #name<T> for #elem_name<T> where T: crate::OutputType + Send {}
+
+                impl<T> From<Box<#elem_name<T>>> for Box<dyn #name<T>>
+                where
+                    T: crate::OutputType + Send + 'static,
+                {
+                    fn from(node: Box<#elem_name<T>>) -> Self {
+                        node
+                    }
+                }
             ));
         }
+        body.extend(quote!(
+            impl<T> From<Box<#elem_name<T>>> for Box<dyn crate::dom::Node<T>>
+            where
+                T: crate::OutputType + Send + 'static,
+            {
+                fn from(node: Box<#elem_name<T>>) -> Self {
+                    node
+                }
+            }
+        ));
         body
     }
 
@@ -283,31 +488,109 @@ impl Declare {
         )
     }
 
-    fn impl_display(&self) -> TokenStream {
+    fn parse_fn_name(&self) -> TokenTree {
+        Ident::new(&format!("parse_{}", self.name), self.name.span()).into()
+    }
+
+    fn visit_fn_name(&self) -> TokenTree {
+        Ident::new(&format!("visit_{}", self.name), self.name.span()).into()
+    }
+
+    fn visit_mut_fn_name(&self) -> TokenTree {
+        Ident::new(&format!("visit_{}_mut", self.name), self.name.span()).into()
+    }
+
+    /// Generate the function which builds this element out of parsed
+    /// attributes and children, for `typed_html::parse`.
+    fn impl_parse(&self) -> TokenStream {
         let elem_name = self.elem_name();
         let name: TokenTree = Literal::string(&self.name.to_string()).into();
+        let parse_fn = self.parse_fn_name();
 
-        let print_opt_children = if self.opt_children.is_some() {
-            quote!(for child in &self.children {
-                child.fmt(f)?;
-            })
-        } else {
-            TokenStream::new()
+        let accepts_text = match &self.opt_children {
+            Some(constraint) => quote!(
+                <#constraint<T> as crate::parse::FromParsed<T>>::ACCEPTS_TEXT
+            ),
+            None => quote!(false),
         };
 
-        let mut print_req_children = TokenStream::new();
+        let mut take_required = TokenStream::new();
+        let mut args = TokenStream::new();
+        let mut set_positions = TokenStream::new();
+        let positions = self.req_positions();
         for (child_name, _, _) in self.req_children() {
-            print_req_children.extend(quote!(
-                self.#child_name.fmt(f)?;
-            ));
+            match positions
+                .iter()
+                .find(|(name, _)| name.to_string() == child_name.to_string())
+            {
+                Some((_, position)) => {
+                    take_required.extend(quote!(
+                        let (#child_name, #position) = children.required()?;
+                    ));
+                    set_positions.extend(quote!(element.#position = #position;));
+                }
+                None => take_required.extend(quote!(
+                    let (#child_name, _) = children.required()?;
+                )),
+            }
+            args.extend(quote!(#child_name,));
         }
 
-        let print_children = if self.req_children.is_empty() {
+        let push_children = if self.opt_children.is_some() {
+            quote!(
+                for child in children {
+                    element.children.push(child.convert(Some(#name))?);
+                }
+            )
+        } else {
+            quote!(children.finish()?;)
+        };
+
+        quote!(
+            #[allow(unused_mut)]
+            fn #parse_fn<T>(
+                location: crate::parse::Location,
+                attributes: Vec<crate::parse::RawAttribute>,
+                children: Vec<crate::parse::ParsedChild<T>>,
+            ) -> Result<ParsedNode<T>, crate::parse::ParseError>
+            where
+                T: crate::OutputType + Send + 'static,
+            {
+                let mut children =
+                    crate::parse::Children::new::<#elem_name<T>>(#name, location, children, #accepts_text)?;
+                #take_required
+                let mut element: #elem_name<T> = #elem_name::new(#args);
+                #set_positions
+                for attribute in attributes {
+                    attribute.apply(#name, &mut element)?;
+                }
+                #push_children
+                Ok(ParsedNode::#elem_name(Box::new(element)))
+            }
+        )
+    }
+
+    fn impl_display(&self) -> TokenStream {
+        let elem_name = self.elem_name();
+        let name: TokenTree = Literal::string(&self.name.to_string()).into();
+
+        let print_child = if RAW_TEXT.contains(&self.name.to_string().as_str()) {
+            quote!(f.write_str(&crate::escape::raw_text(#name, child.as_str()))?;)
+        } else {
+            quote!(child.fmt(f)?;)
+        };
+        let print_children = self.each_child(
+            quote!(self.children.iter()),
+            print_child,
+            |child_name| quote!(self.#child_name.fmt(f)?;),
+        );
+
+        let print_element = if self.req_children.is_empty() {
             if self.opt_children.is_some() {
                 if !SELF_CLOSING.contains(&elem_name.to_string().as_str()) {
                     quote!(
                         write!(f, ">")?;
-                        #print_opt_children
+                        #print_children
                         write!(f, "</{}>", #name)
                     )
                 } else {
@@ -315,7 +598,7 @@ impl Declare {
                         write!(f, " />")
                     } else {
                         write!(f, ">")?;
-                        #print_opt_children
+                        #print_children
                         write!(f, "</{}>", #name)
                     })
                 }
@@ -327,17 +610,21 @@ impl Declare {
         } else {
             quote!(
                 write!(f, ">")?;
-                #print_req_children
-                #print_opt_children
+                #print_children
                 write!(f, "</{}>", #name)
             )
         };
 
         let mut print_attrs = TokenStream::new();
-        for (attr_name, _, attr_str) in self.attrs() {
+        for (attr_name, attr_type, attr_str) in self.attrs() {
+            let escape = if attr_type.to_string() == "Uri" {
+                quote!(crate::escape::url_attribute)
+            } else {
+                quote!(crate::escape::attribute)
+            };
             print_attrs.extend(quote!(
                 if let Some(ref value) = self.attrs.#attr_name {
-                    let value = crate::escape_html_attribute(value.to_string());
+                    let value = #escape(&value.to_string());
                     if !value.is_empty() {
                         write!(f, " {}=\"{}\"", #attr_str, value)?;
                     }
@@ -354,17 +641,328 @@ impl Declare {
                     write!(f, "<{}", #name)?;
                     #print_attrs
                     for (key, value) in &self.data_attributes {
-                        write!(f, " data-{}=\"{}\"", key,
-                               crate::escape_html_attribute(value.to_string()))?;
+                        write!(f, " {}=\"{}\"", key,
+                               crate::escape::attribute(&value.to_string()))?;
                     }
                     write!(f, "{}", self.events)?;
-                    #print_children
+                    #print_element
                 }
             }
         )
     }
 }
 
+/// Generate a constant holding the child order of each of the declared
+/// elements, which is what `html!` checks its children against.
+pub fn child_orders(decls: &[Declare]) -> TokenStream {
+    let names: Vec<TokenTree> = decls.iter().map(Declare::elem_name).collect();
+    let orders: Vec<TokenStream> = decls
+        .iter()
+        .map(|decl| match &decl.child_order {
+            Some(order) => {
+                let order = order.to_tokens();
+                quote!(Some(&#order))
+            }
+            None => quote!(None),
+        })
+        .collect();
+
+    quote!(
+        /// The child order of each element, as returned by
+        /// `Element::child_order()`, for the `html!` macro to check at
+        /// compile time.
+        #[doc(hidden)]
+        #[allow(non_upper_case_globals)]
+        pub mod child_orders {
+            use crate::elements::ChildOrder;
+
+            #( pub const #names: Option<&ChildOrder> = #orders; )*
+        }
+    )
+}
+
+/// Generate the `Visitor` and `VisitorMut` traits, with a method for each of
+/// the declared elements.
+pub fn visitors(decls: &[Declare]) -> TokenStream {
+    let names: Vec<TokenTree> = decls.iter().map(Declare::elem_name).collect();
+    let visit_fns: Vec<TokenTree> = decls.iter().map(Declare::visit_fn_name).collect();
+    let visit_mut_fns: Vec<TokenTree> = decls.iter().map(Declare::visit_mut_fn_name).collect();
+    let docs: Vec<String> = decls
+        .iter()
+        .map(|decl| format!("Visit a `<{}>` element.", decl.name))
+        .collect();
+
+    quote!(
+        /// Trait for walking a DOM tree.
+        ///
+        /// [`Node::accept()`][accept] passes a node to the method for its
+        /// type, and then does the same for each of its children, so a visitor
+        /// sees every node in the tree in document order. Each element has a
+        /// method, which calls [`visit_element()`][visit_element] unless
+        /// it's overridden, so a visitor can handle specific elements with
+        /// their typed attributes, or every element through
+        /// [`Element`][Element]. All the methods do nothing by default.
+        ///
+        /// See [`VisitorMut`][VisitorMut] for modifying the tree.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use typed_html::html;
+        /// # use typed_html::dom::{DOMTree, Element};
+        /// # use typed_html::elements::{a, Visitor};
+        /// # use typed_html::OutputType;
+        /// #[derive(Default)]
+        /// struct Links(Vec<String>);
+        ///
+        /// impl<T: OutputType + Send> Visitor<T> for Links {
+        ///     fn visit_a(&mut self, element: &a<T>) {
+        ///         if let Some(href) = &element.attrs.href {
+        ///             self.0.push(href.to_string());
+        ///         }
+        ///     }
+        /// }
+        ///
+        /// # fn main() {
+        /// let doc: DOMTree<String> = html!(
+        ///     <ul>
+        ///         <li><a href="/">"Home"</a></li>
+        ///         <li><a href="/about">"About"</a></li>
+        ///     </ul>
+        /// );
+        /// let mut links = Links::default();
+        /// doc.accept(&mut links);
+        /// assert_eq!(vec!["/", "/about"], links.0);
+        /// # }
+        /// ```
+        ///
+        /// [accept]: ../dom/trait.Node.html#method.accept
+        /// [visit_element]: #method.visit_element
+        /// [Element]: ../dom/trait.Element.html
+        /// [VisitorMut]: trait.VisitorMut.html
+        pub trait Visitor<T> where T: crate::OutputType + Send {
+            /// Visit any element. The methods for each element call this by
+            /// default.
+            fn visit_element(&mut self, _element: &dyn crate::dom::Element<T>) {}
+
+            /// Visit a text node.
+            fn visit_text(&mut self, _text: &crate::dom::TextNode<T>) {}
+
+            /// Visit an unescaped text node.
+            fn visit_unsafe_text(&mut self, _text: &crate::dom::UnsafeTextNode<T>) {}
+
+            /// Visit a comment node.
+            fn visit_comment(&mut self, _comment: &crate::dom::CommentNode<T>) {}
+
+            #(
+                #[doc = #docs]
+                fn #visit_fns(&mut self, element: &#names<T>) {
+                    self.visit_element(element);
+                }
+            )*
+        }
+
+        /// Trait for walking a DOM tree and modifying it.
+        ///
+        /// This works like [`Visitor`][Visitor], but with mutable references,
+        /// through [`Node::accept_mut()`][accept_mut]. Children are visited
+        /// after their parent, so any children a visitor adds to an element
+        /// are visited too.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use typed_html::html;
+        /// # use typed_html::dom::DOMTree;
+        /// # use typed_html::elements::{img, VisitorMut};
+        /// # use typed_html::types::ImageLoading;
+        /// # use typed_html::OutputType;
+        /// struct LazyImages;
+        ///
+        /// impl<T: OutputType + Send> VisitorMut<T> for LazyImages {
+        ///     fn visit_img_mut(&mut self, element: &mut img<T>) {
+        ///         element.attrs.loading = Some(ImageLoading::Lazy);
+        ///     }
+        /// }
+        ///
+        /// # fn main() {
+        /// let mut doc: DOMTree<String> = html!(
+        ///     <p><img src="cat.jpg" alt="A cat"/></p>
+        /// );
+        /// doc.accept_mut(&mut LazyImages);
+        /// assert_eq!(
+        ///     "<p><img alt=\"A cat\" loading=\"lazy\" src=\"cat.jpg\"/></p>",
+        ///     doc.to_string()
+        /// );
+        /// # }
+        /// ```
+        ///
+        /// [Visitor]: trait.Visitor.html
+        /// [accept_mut]: ../dom/trait.Node.html#method.accept_mut
+        pub trait VisitorMut<T> where T: crate::OutputType + Send {
+            /// Visit any element. The methods for each element call this by
+            /// default.
+            fn visit_element_mut(&mut self, _element: &mut dyn crate::dom::Element<T>) {}
+
+            /// Visit a text node.
+            fn visit_text_mut(&mut self, _text: &mut crate::dom::TextNode<T>) {}
+
+            /// Visit an unescaped text node.
+            fn visit_unsafe_text_mut(&mut self, _text: &mut crate::dom::UnsafeTextNode<T>) {}
+
+            /// Visit a comment node.
+            fn visit_comment_mut(&mut self, _comment: &mut crate::dom::CommentNode<T>) {}
+
+            #(
+                #[doc = #docs]
+                fn #visit_mut_fns(&mut self, element: &mut #names<T>) {
+                    self.visit_element_mut(element);
+                }
+            )*
+        }
+    )
+}
+
 pub fn expand_declare(input: &[Token]) -> Result<Vec<Declare>, ParseError> {
     parser::grammar::DeclarationsParser::new().parse(Lexer::new(input))
 }
+
+/// Generate the types tying all the declared elements together for
+/// `typed_html::parse`: the `ParsedNode` enum, conversions from it into every
+/// content category, and the function which dispatches on element names.
+pub fn parse_support(decls: &[Declare]) -> TokenStream {
+    let names: Vec<TokenTree> = decls.iter().map(Declare::elem_name).collect();
+    let name_strs: Vec<TokenTree> = decls
+        .iter()
+        .map(|decl| Literal::string(&decl.name.to_string()).into())
+        .collect();
+    let parse_fns: Vec<TokenTree> = decls.iter().map(Declare::parse_fn_name).collect();
+    let attr_lists: Vec<TokenStream> = decls
+        .iter()
+        .map(|decl| {
+            let attrs = decl
+                .html_attrs()
+                .map(|(_, is_bool, html_name)| quote!( (#html_name, #is_bool) ));
+            quote!( &[ #(#attrs),* ] )
+        })
+        .collect();
+
+    let mut stream = quote!(
+        /// A node of any type, as produced by [`typed_html::parse`][parse].
+        ///
+        /// [parse]: ../parse/index.html
+        pub enum ParsedNode<T> where T: crate::OutputType + Send + 'static {
+            Text(Box<crate::dom::TextNode<T>>),
+            #( #names(Box<#names<T>>), )*
+        }
+
+        impl<T> ParsedNode<T> where T: crate::OutputType + Send + 'static {
+            /// Get the element name of the node, or
+            /// [`TEXT_NODE`][TEXT_NODE] for text nodes.
+            ///
+            /// [TEXT_NODE]: constant.TEXT_NODE.html
+            pub fn name(&self) -> &'static str {
+                match self {
+                    ParsedNode::Text(_) => TEXT_NODE,
+                    #( ParsedNode::#names(_) => #name_strs, )*
+                }
+            }
+
+            /// Convert the node into a [`DOMTree`][DOMTree].
+            ///
+            /// [DOMTree]: ../dom/type.DOMTree.html
+            pub fn into_node(self) -> Box<dyn crate::dom::Node<T>> {
+                match self {
+                    ParsedNode::Text(node) => node,
+                    #( ParsedNode::#names(node) => node, )*
+                }
+            }
+        }
+
+        /// Look up the attributes of an element by the element's name, as
+        /// pairs of their HTML names and whether they're boolean attributes.
+        ///
+        /// Returns `None` if there's no element with this name.
+        pub fn element_attributes(name: &str) -> Option<&'static [(&'static str, bool)]> {
+            match name {
+                #( #name_strs => Some(#attr_lists), )*
+                _ => None,
+            }
+        }
+
+        /// Find the `'static` name of the element with this name, if there
+        /// is one.
+        pub(crate) fn element_name(name: &str) -> Option<&'static str> {
+            match name {
+                #( #name_strs => Some(#name_strs), )*
+                _ => None,
+            }
+        }
+
+        pub(crate) fn parse_element<T>(
+            name: &str,
+            location: crate::parse::Location,
+            attributes: Vec<crate::parse::RawAttribute>,
+            children: Vec<crate::parse::ParsedChild<T>>,
+        ) -> Option<Result<ParsedNode<T>, crate::parse::ParseError>>
+        where
+            T: crate::OutputType + Send + 'static,
+        {
+            match name {
+                #( #name_strs => Some(#parse_fns(location, attributes, children)), )*
+                _ => None,
+            }
+        }
+    );
+
+    for name in &names {
+        stream.extend(quote!(
+            impl<T> crate::parse::FromParsed<T> for #name<T> where T: crate::OutputType + Send + 'static {
+                const ACCEPTS_TEXT: bool = false;
+
+                fn from_parsed(node: ParsedNode<T>) -> Result<Box<Self>, ParsedNode<T>> {
+                    match node {
+                        ParsedNode::#name(node) => Ok(node),
+                        node => Err(node),
+                    }
+                }
+            }
+        ));
+    }
+
+    let mut traits: Vec<String> = decls
+        .iter()
+        .flat_map(|decl| decl.traits.iter().map(TokenStream::to_string))
+        .collect();
+    traits.sort();
+    traits.dedup();
+    for trait_name in traits {
+        let members: Vec<TokenTree> = decls
+            .iter()
+            .filter(|decl| decl.traits.iter().any(|t| t.to_string() == trait_name))
+            .map(Declare::elem_name)
+            .collect();
+        let accepts_text = TEXT_CONTENT.contains(&trait_name.as_str());
+        let text = if accepts_text {
+            quote!( ParsedNode::Text(node) => Ok(node), )
+        } else {
+            TokenStream::new()
+        };
+        let trait_name = Ident::new(&trait_name, Span::call_site());
+        stream.extend(quote!(
+            impl<T> crate::parse::FromParsed<T> for dyn #trait_name<T> where T: crate::OutputType + Send + 'static {
+                const ACCEPTS_TEXT: bool = #accepts_text;
+
+                fn from_parsed(node: ParsedNode<T>) -> Result<Box<Self>, ParsedNode<T>> {
+                    match node {
+                        #text
+                        #( ParsedNode::#members(node) => Ok(node), )*
+                        node => Err(node),
+                    }
+                }
+            }
+        ));
+    }
+
+    stream
+}
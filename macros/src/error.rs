@@ -1,7 +1,7 @@
 use crate::lexer::Token;
 use ansi_term::Style;
 use lalrpop_util::ParseError::*;
-use proc_macro2::{Ident, TokenStream};
+use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, quote_spanned};
 
 pub type ParseError = lalrpop_util::ParseError<usize, Token, HtmlParseError>;
@@ -9,6 +9,11 @@ pub type ParseError = lalrpop_util::ParseError<usize, Token, HtmlParseError>;
 #[derive(Debug)]
 pub enum HtmlParseError {
     TagMismatch { open: Ident, close: Ident },
+    MultipleRoots { span: Span },
+    InvalidSpread { span: Span },
+    InvalidClassToggle { span: Span, message: String },
+    InvalidFormatString { span: Span },
+    InvalidComment { span: Span },
 }
 
 fn pprint_token(token: &str) -> &str {
@@ -16,6 +21,7 @@ fn pprint_token(token: &str) -> &str {
         "BraceGroupToken" => "code block",
         "LiteralToken" => "literal",
         "IdentToken" => "identifier",
+        "\"f\"" => "format string",
         a => a,
     }
 }
@@ -34,10 +40,18 @@ fn pprint_tokens(tokens: &[String]) -> String {
 fn is_in_node_position(tokens: &[String]) -> bool {
     use std::collections::HashSet;
     let input: HashSet<&str> = tokens.iter().map(String::as_str).collect();
-    let output: HashSet<&str> = ["\"<\"", "BraceGroupToken", "LiteralToken"]
-        .iter()
-        .cloned()
-        .collect();
+    let output: HashSet<&str> = [
+        "\"<\"",
+        "\"f\"",
+        "\"for\"",
+        "\"if\"",
+        "\"match\"",
+        "BraceGroupToken",
+        "LiteralToken",
+    ]
+    .iter()
+    .cloned()
+    .collect();
     input == output
 }
 
@@ -109,5 +123,55 @@ pub fn parse_error(input: &[Token], error: &ParseError) -> TokenStream {
                 #open_error
             }}
         }
+        User {
+            error: HtmlParseError::MultipleRoots { span },
+        } => {
+            let help_msg = format!(
+                "wrap them in a fragment to return more than one node, eg. {}",
+                Style::new().bold().paint("<><li>\"Hello\"</li><li>\"Joe\"</li></>")
+            );
+            let error = quote_spanned! {*span=>
+                compile_error! { "html! can only have one top level node" }
+            };
+            let help = quote_spanned! {*span=>
+                compile_error! { #help_msg }
+            };
+            quote! {{
+                #error
+                #help
+            }}
+        }
+        User {
+            error: HtmlParseError::InvalidSpread { span },
+        } => {
+            let msg = format!(
+                "expected an attribute, or `..` followed by attributes to spread, eg. {}",
+                Style::new().bold().paint("<input {..attrs}/>")
+            );
+            quote_spanned! {*span=>
+                compile_error! { #msg }
+            }
+        }
+        User {
+            error: HtmlParseError::InvalidFormatString { span },
+        } => {
+            quote_spanned! {*span=>
+                compile_error! { "expected a string literal after f!" }
+            }
+        }
+        User {
+            error: HtmlParseError::InvalidComment { span },
+        } => {
+            quote_spanned! {*span=>
+                compile_error! { "a comment must be a string literal which doesn't contain `--`, start with `>` or `->`, or end with `-`" }
+            }
+        }
+        User {
+            error: HtmlParseError::InvalidClassToggle { span, message },
+        } => {
+            quote_spanned! {*span=>
+                compile_error! { #message }
+            }
+        }
     }
 }
@@ -1,23 +1,235 @@
-use proc_macro2::{Delimiter, Group, Ident, Literal, Span, TokenStream, TokenTree};
+use proc_macro2::{Delimiter, Group, Ident, Literal, Spacing, Span, TokenStream, TokenTree};
 use quote::{quote, quote_spanned};
 
 use crate::config::required_children;
-use crate::error::ParseError;
+use crate::error::{HtmlParseError, ParseError};
 use crate::ident;
-use crate::lexer::{to_stream, Lexer, Token};
+use crate::lexer::{keywordise_html, to_stream, unroll_stream, Lexer, Token};
 use crate::map::StringyMap;
 use crate::parser::grammar;
 
+use std::collections::BTreeSet;
 use std::iter::FromIterator;
 
 #[derive(Clone)]
 pub enum Node {
     Element(Element),
     Text(Literal),
+    /// A `f!"..."` text node.
+    Format(Literal),
+    /// A `<!-- ... -->` comment, with the expression for its text.
+    Comment(Span, TokenStream),
     Block(Group),
+    Fragment(Vec<Node>),
+    Control(Control),
+}
+
+/// Control flow in a list of children, which is expanded into the same
+/// control flow around the code adding each child to its parent.
+#[derive(Clone)]
+pub enum Control {
+    /// An `if` or `if let`, where an `else if` is an `else` branch holding
+    /// only the next `if`.
+    If {
+        span: Span,
+        condition: Vec<Token>,
+        body: Vec<Node>,
+        otherwise: Option<Vec<Node>>,
+    },
+    For {
+        span: Span,
+        pattern: Vec<Token>,
+        iterator: Vec<Token>,
+        body: Vec<Node>,
+    },
+    /// A `match`, with a pattern (including any guard) and a list of nodes
+    /// for each arm.
+    Match {
+        span: Span,
+        expression: Vec<Token>,
+        arms: Vec<(Vec<Token>, Vec<Node>)>,
+    },
+}
+
+/// Parse the contents of a control flow body, like the block after an `if`,
+/// as a list of nodes.
+pub fn parse_body(group: Group) -> Result<Vec<Node>, ParseError> {
+    let tokens = keywordise_html(unroll_stream(group.stream(), false));
+    parse_nodes(&tokens, &group)
+}
+
+fn parse_nodes(tokens: &[Token], group: &Group) -> Result<Vec<Node>, ParseError> {
+    grammar::NodesParser::new()
+        .parse(Lexer::new(tokens))
+        .map_err(|error| match error {
+            // Point at the end of the block rather than the end of the macro.
+            lalrpop_util::ParseError::UnrecognizedEOF { location, expected } => {
+                lalrpop_util::ParseError::UnrecognizedToken {
+                    token: (
+                        location,
+                        Token::GroupClose(Delimiter::Brace, group.span_close()),
+                        location,
+                    ),
+                    expected,
+                }
+            }
+            error => error,
+        })
+}
+
+/// Parse the arms of a `match`, each of which is a pattern followed by `=>`
+/// and either a block of nodes or a single node ending with a comma.
+pub fn parse_match_arms(group: Group) -> Result<Vec<(Vec<Token>, Vec<Node>)>, ParseError> {
+    let tokens = keywordise_html(unroll_stream(group.stream(), false));
+    let expected = |expected: &str| lalrpop_util::ParseError::UnrecognizedToken {
+        token: (
+            tokens.len(),
+            Token::GroupClose(Delimiter::Brace, group.span_close()),
+            tokens.len(),
+        ),
+        expected: vec![expected.to_string()],
+    };
+    let mut arms = Vec::new();
+    let mut pos = 0;
+    while pos < tokens.len() {
+        let start = pos;
+        loop {
+            match (tokens.get(pos), tokens.get(pos + 1)) {
+                (Some(Token::Punct('=', eq)), Some(Token::Punct('>', _)))
+                    if eq.spacing() == Spacing::Joint =>
+                {
+                    break
+                }
+                (Some(_), _) => pos += 1,
+                (None, _) => return Err(expected("\"=>\"")),
+            }
+        }
+        let pattern = tokens[start..pos].to_vec();
+        pos += 2;
+        let body = match tokens.get(pos) {
+            Some(Token::Group(Delimiter::Brace, body)) => {
+                pos += 1;
+                parse_body(body.clone())?
+            }
+            _ => {
+                let start = pos;
+                while pos < tokens.len() && !matches!(tokens[pos], Token::Punct(',', _)) {
+                    pos += 1;
+                }
+                if start == pos {
+                    return Err(expected("a node"));
+                }
+                parse_nodes(&tokens[start..pos], &group)?
+            }
+        };
+        if let Some(Token::Punct(',', _)) = tokens.get(pos) {
+            pos += 1;
+        }
+        arms.push((pattern, body));
+    }
+    Ok(arms)
+}
+
+impl Control {
+    fn span(&self) -> Span {
+        match self {
+            Control::If { span, .. } | Control::For { span, .. } | Control::Match { span, .. } => {
+                *span
+            }
+        }
+    }
+
+    /// Generate the control flow, using `child` to generate the code adding
+    /// each node in its bodies to the parent.
+    fn into_stream(
+        self,
+        child: &mut dyn FnMut(Node) -> Result<TokenStream, TokenStream>,
+    ) -> Result<TokenStream, TokenStream> {
+        let mut body = |nodes: Vec<Node>| -> Result<TokenStream, TokenStream> {
+            let mut out = TokenStream::new();
+            for node in nodes {
+                out.extend(child(node)?);
+            }
+            Ok(out)
+        };
+        match self {
+            Control::If {
+                condition,
+                body: then,
+                otherwise,
+                ..
+            } => {
+                let condition = to_stream(condition);
+                let then = body(then)?;
+                let otherwise = match otherwise {
+                    Some(otherwise) => {
+                        let otherwise = body(otherwise)?;
+                        quote!(else { #otherwise })
+                    }
+                    None => TokenStream::new(),
+                };
+                Ok(quote!(
+                    if #condition { #then } #otherwise
+                ))
+            }
+            Control::For {
+                pattern,
+                iterator,
+                body: nodes,
+                ..
+            } => {
+                let pattern = to_stream(pattern);
+                let iterator = to_stream(iterator);
+                let nodes = body(nodes)?;
+                Ok(quote!(
+                    for #pattern in #iterator { #nodes }
+                ))
+            }
+            Control::Match {
+                expression, arms, ..
+            } => {
+                let expression = to_stream(expression);
+                let mut out = TokenStream::new();
+                for (pattern, nodes) in arms {
+                    let pattern = to_stream(pattern);
+                    let nodes = body(nodes)?;
+                    out.extend(quote!( #pattern => { #nodes } ));
+                }
+                Ok(quote!(
+                    match #expression { #out }
+                ))
+            }
+        }
+    }
+}
+
+/// Replace any fragments in a list of nodes with their children.
+pub fn flatten_fragments(nodes: Vec<Node>) -> Vec<Node> {
+    let mut out = Vec::new();
+    for node in nodes {
+        match node {
+            Node::Fragment(children) => out.extend(flatten_fragments(children)),
+            node => out.push(node),
+        }
+    }
+    out
 }
 
 impl Node {
+    pub fn span(&self) -> Span {
+        match self {
+            Node::Element(el) => el.name.span(),
+            Node::Text(text) | Node::Format(text) => text.span(),
+            Node::Comment(span, _) => *span,
+            Node::Block(group) => group.span(),
+            Node::Fragment(children) => match children.first() {
+                Some(child) => child.span(),
+                None => Span::call_site(),
+            },
+            Node::Control(control) => control.span(),
+        }
+    }
+
     pub fn into_token_stream(self, ty: &Option<Vec<Token>>) -> Result<TokenStream, TokenStream> {
         match self {
             Node::Element(el) => el.into_token_stream(ty),
@@ -25,6 +237,15 @@ impl Node {
                 let text = TokenTree::Literal(text);
                 Ok(quote!(Box::new(typed_html::dom::TextNode::new(#text.to_string()))))
             }
+            Node::Format(text) => {
+                let text = TokenTree::Literal(text);
+                Ok(quote!(Box::new(typed_html::dom::TextNode::new(
+                    format!(#text)
+                ))))
+            }
+            Node::Comment(_, text) => {
+                Ok(quote!(Box::new(typed_html::dom::CommentNode::new(#text))))
+            }
             Node::Block(group) => {
                 let span = group.span();
                 let error =
@@ -33,31 +254,73 @@ impl Node {
                     compile_error! { #error }
                 })
             }
+            Node::Fragment(children) => {
+                let mut type_annotation = TokenStream::new();
+                if let Some(ty) = ty {
+                    let type_var = to_stream(ty.clone());
+                    type_annotation.extend(quote!(: typed_html::dom::Fragment<#type_var, _>));
+                }
+                let children = flatten_fragments(children)
+                    .into_iter()
+                    .map(|node| node.into_child_stream(ty, &quote!(fragment)))
+                    .collect::<Result<Vec<TokenStream>, TokenStream>>()?;
+                Ok(quote!(
+                    {
+                        let mut fragment #type_annotation = typed_html::dom::Fragment::new();
+                        #( #children )*
+                        fragment
+                    }
+                ))
+            }
+            Node::Control(control) => {
+                let span = control.span();
+                let error =
+                    "you cannot use control flow as a top level element or a required child element";
+                Err(quote_spanned! { span=>
+                    compile_error! { #error }
+                })
+            }
         }
     }
 
-    fn into_child_stream(self, ty: &Option<Vec<Token>>) -> Result<TokenStream, TokenStream> {
+    /// Generate the code pushing this node onto the list of children
+    /// `target`.
+    fn into_child_stream(
+        self,
+        ty: &Option<Vec<Token>>,
+        target: &TokenStream,
+    ) -> Result<TokenStream, TokenStream> {
         match self {
             Node::Element(el) => {
                 let el = el.into_token_stream(ty)?;
                 Ok(quote!(
-                    element.children.push(#el);
+                    #target.push(#el);
                 ))
             }
-            tx @ Node::Text(_) => {
+            tx @ Node::Text(_) | tx @ Node::Format(_) | tx @ Node::Comment(_, _) => {
                 let tx = tx.into_token_stream(ty)?;
                 Ok(quote!(
-                    element.children.push(#tx);
+                    #target.push(#tx);
                 ))
             }
             Node::Block(group) => {
                 let group: TokenTree = group.into();
                 Ok(quote!(
                     for child in #group.into_iter() {
-                        element.children.push(child);
+                        #target.push(child);
                     }
                 ))
             }
+            Node::Fragment(children) => {
+                let mut out = TokenStream::new();
+                for child in flatten_fragments(children) {
+                    out.extend(child.into_child_stream(ty, target)?);
+                }
+                Ok(out)
+            }
+            Node::Control(control) => {
+                control.into_stream(&mut |child| child.into_child_stream(ty, target))
+            }
         }
     }
 
@@ -70,6 +333,8 @@ impl Node {
         match self {
             Node::Element(el) => el.into_dodrio_token_stream(bump, is_req_child),
             Node::Text(text) => Ok(dodrio_text_node(text)),
+            Node::Format(text) => Ok(dodrio_format_node(text, bump)),
+            Node::Comment(span, _) => Err(dodrio_comment_error(span)),
             Node::Block(group) => {
                 let span = group.span();
                 let error =
@@ -78,6 +343,65 @@ impl Node {
                     compile_error! { #error }
                 })
             }
+            node @ Node::Fragment(_) => {
+                let span = node.span();
+                Err(quote_spanned! { span=>
+                    compile_error! { "the dodrio! macro can't return a fragment" }
+                })
+            }
+            Node::Control(control) => {
+                let span = control.span();
+                let error =
+                    "you cannot use control flow as a top level element or a required child element";
+                Err(quote_spanned! { span=>
+                    compile_error! { #error }
+                })
+            }
+        }
+    }
+
+    /// Generate the code adding this node to the Dodrio element builder
+    /// `node`.
+    #[cfg(feature = "dodrio")]
+    fn into_dodrio_child_stream(self, bump: &Ident) -> Result<TokenStream, TokenStream> {
+        match self {
+            Node::Element(el) => {
+                let el = el.into_dodrio_token_stream(bump, false)?;
+                Ok(quote!(
+                    node = node.child(#el);
+                ))
+            }
+            Node::Text(text) => {
+                let text = dodrio_text_node(text);
+                Ok(quote!(
+                    node = node.child(#text);
+                ))
+            }
+            Node::Format(text) => {
+                let text = dodrio_format_node(text, bump);
+                Ok(quote!(
+                    node = node.child(#text);
+                ))
+            }
+            Node::Comment(span, _) => Err(dodrio_comment_error(span)),
+            Node::Block(group) => {
+                let group: TokenTree = group.into();
+                Ok(quote!(
+                    for child in #group.into_iter() {
+                        node = node.child(child);
+                    }
+                ))
+            }
+            Node::Fragment(children) => {
+                let mut out = TokenStream::new();
+                for child in flatten_fragments(children) {
+                    out.extend(child.into_dodrio_child_stream(bump)?);
+                }
+                Ok(out)
+            }
+            Node::Control(control) => {
+                control.into_stream(&mut |child| child.into_dodrio_child_stream(bump))
+            }
         }
     }
 }
@@ -85,44 +409,291 @@ impl Node {
 #[derive(Clone)]
 pub struct Element {
     pub name: Ident,
-    pub attributes: StringyMap<Ident, TokenTree>,
+    pub attrs: Attributes,
     pub children: Vec<Node>,
 }
 
+/// An item in an element's list of attributes.
+pub enum AttrItem {
+    /// An attribute, and whether it's optional.
+    Attr(Ident, Token, bool),
+    /// The expression in a `{..attrs}`.
+    Spread(TokenStream),
+    /// A `class:name=value` toggle.
+    Class(String, Token),
+    /// A `name=f!"..."` attribute.
+    Format(Ident, Literal),
+}
+
+#[derive(Clone)]
+pub struct Attributes {
+    pub attributes: StringyMap<Ident, TokenTree>,
+    /// The names of the attributes given as `name=?value`, which are only
+    /// set when the value is `Some`.
+    pub optional: BTreeSet<String>,
+    /// The expressions given as `{..attrs}`, in order.
+    pub spread: Vec<TokenStream>,
+    /// The classes given as `class:name=value`, which are added to the
+    /// `class` attribute when their value is true.
+    pub classes: Vec<(String, TokenTree)>,
+    /// The names of the attributes given as `name=f!"..."`, whose values
+    /// are format strings.
+    pub formatted: BTreeSet<String>,
+}
+
+impl Attributes {
+    /// Remove an attribute, returning an expression for its value and
+    /// whether it's optional.
+    fn remove(&mut self, key: &Ident) -> (TokenTree, bool) {
+        let name = key.to_string();
+        let value = self.attributes.remove(key).unwrap();
+        let value = if self.formatted.contains(&name) {
+            format_value(&value)
+        } else {
+            value
+        };
+        (value, self.optional.contains(&name))
+    }
+}
+
+impl Default for Attributes {
+    fn default() -> Self {
+        Attributes {
+            attributes: StringyMap::new(),
+            optional: BTreeSet::new(),
+            spread: Vec::new(),
+            classes: Vec::new(),
+            formatted: BTreeSet::new(),
+        }
+    }
+}
+
+/// Parse a `{..attrs}` in an element's attribute list, returning the
+/// expression after the `..`.
+pub fn parse_spread(group: Group) -> Result<TokenStream, ParseError> {
+    let mut tokens = group.stream().into_iter();
+    match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Punct(first)), Some(TokenTree::Punct(second)))
+            if first.as_char() == '.'
+                && first.spacing() == Spacing::Joint
+                && second.as_char() == '.' =>
+        {
+            let expr: TokenStream = tokens.collect();
+            if !expr.is_empty() {
+                return Ok(expr);
+            }
+        }
+        _ => (),
+    }
+    Err(lalrpop_util::ParseError::User {
+        error: HtmlParseError::InvalidSpread { span: group.span() },
+    })
+}
+
 #[cfg(feature = "dodrio")]
 fn dodrio_text_node(text: Literal) -> TokenStream {
     let text = TokenTree::Literal(text);
     quote!(dodrio::builder::text(#text))
 }
 
-fn extract_data_attrs(attrs: &mut StringyMap<Ident, TokenTree>) -> StringyMap<String, TokenTree> {
+#[cfg(feature = "dodrio")]
+fn dodrio_format_node(text: Literal, bump: &Ident) -> TokenStream {
+    let text = TokenTree::Literal(text);
+    quote!(dodrio::builder::text({
+        let mut text = dodrio::bumpalo::collections::String::new_in(#bump);
+        std::fmt::Write::write_fmt(&mut text, format_args!(#text)).unwrap();
+        text.into_bump_str()
+    }))
+}
+
+#[cfg(feature = "dodrio")]
+fn dodrio_comment_error(span: Span) -> TokenStream {
+    quote_spanned! { span=>
+        compile_error! { "the dodrio! macro can't render comments, as Dodrio has no comment nodes" }
+    }
+}
+
+fn extract_data_attrs(attrs: &mut Attributes) -> StringyMap<String, (TokenTree, bool)> {
     let mut data = StringyMap::new();
-    let keys: Vec<Ident> = attrs.keys().cloned().collect();
+    let keys: Vec<Ident> = attrs.attributes.keys().cloned().collect();
     for key in keys {
         let key_name = key.to_string();
-        if let Some(key_name) = key_name.strip_prefix("data_") {
-            let value = attrs.remove(&key).unwrap();
-            data.insert(key_name.to_string(), value);
+        if let Some(data_name) = key_name.strip_prefix("data_") {
+            data.insert(
+                format!("data-{}", data_name.replace('_', "-")),
+                attrs.remove(&key),
+            );
         }
     }
     data
 }
 
-fn extract_event_handlers(
-    attrs: &mut StringyMap<Ident, TokenTree>,
-) -> StringyMap<Ident, TokenTree> {
+/// Take out the `key` pseudo-attribute, which identifies an element among its
+/// siblings and isn't rendered.
+fn extract_key(attrs: &mut Attributes) -> Option<(TokenTree, bool)> {
+    let key = attrs.attributes.keys().find(|key| *key == "key").cloned()?;
+    Some(attrs.remove(&key))
+}
+
+fn extract_event_handlers(attrs: &mut Attributes) -> StringyMap<Ident, (TokenTree, bool)> {
     let mut events = StringyMap::new();
-    let keys: Vec<Ident> = attrs.keys().cloned().collect();
+    let keys: Vec<Ident> = attrs.attributes.keys().cloned().collect();
     for key in keys {
         let key_name = key.to_string();
         if let Some(event_name) = key_name.strip_prefix("on") {
-            let value = attrs.remove(&key).unwrap();
-            events.insert(ident::new_raw(event_name, key.span()), value);
+            events.insert(ident::new_raw(event_name, key.span()), attrs.remove(&key));
         }
     }
     events
 }
 
+/// Turn the literal from a `name=f!"..."` attribute into an expression
+/// producing the formatted `String`.
+fn format_value(value: &TokenTree) -> TokenTree {
+    let mut group = Group::new(Delimiter::Brace, quote!(format!(#value)));
+    group.set_span(value.span());
+    group.into()
+}
+
+/// Take an element's required children out of its list of children, along
+/// with the number of other children before each, so they can be put back
+/// where they were.
+///
+/// Each is the first element with its name after the one before it, or
+/// failing that the first code block there, which will be checked by its
+/// type.
+fn take_required(
+    parent: &Ident,
+    children: &mut Vec<Node>,
+    names: &[&str],
+) -> Result<Vec<(Node, usize)>, TokenStream> {
+    let mut start = 0;
+    let mut required = Vec::new();
+    for name in names {
+        let index = children[start..]
+            .iter()
+            .position(|child| matches!(child, Node::Element(el) if el.name == *name))
+            .or_else(|| {
+                children[start..]
+                    .iter()
+                    .position(|child| matches!(child, Node::Block(_)))
+            });
+        match index {
+            Some(index) => {
+                start += index;
+                required.push((children.remove(start), start));
+            }
+            None => {
+                let error = format!("<{}> is missing its required <{}> child", parent, name);
+                // Point at the child if it's out of order.
+                let span = children
+                    .iter()
+                    .find_map(|child| match child {
+                        Node::Element(el) if el.name == *name => Some(el.name.span()),
+                        _ => None,
+                    })
+                    .unwrap_or_else(|| parent.span());
+                return Err(quote_spanned! {span=>
+                    compile_error! { #error }
+                });
+            }
+        }
+    }
+    Ok(required)
+}
+
+/// Generate the assignments which put each required child back at its
+/// position among the optional children, given the code adding each of
+/// those, as `take_required` found them.
+fn place_required(
+    required: &[(String, usize)],
+    opt_children: Vec<TokenStream>,
+    element: &TokenStream,
+) -> TokenStream {
+    let count = opt_children.len();
+    let mut body = TokenStream::new();
+    let place = |index: usize, body: &mut TokenStream| {
+        // The positions start out at 0, so only later ones need setting.
+        for (name, _) in required
+            .iter()
+            .filter(|(_, position)| *position == index && index > 0)
+        {
+            let field = Ident::new(&format!("child_{}_position", name), Span::call_site());
+            body.extend(quote!(
+                #element.#field = #element.children.len();
+            ));
+        }
+    };
+    for (index, child) in opt_children.into_iter().enumerate() {
+        place(index, &mut body);
+        body.extend(child);
+    }
+    place(count, &mut body);
+    body
+}
+
+/// Generate a check of the children of an element against its declared
+/// child order, which fails to compile if they don't follow it.
+///
+/// This can only be done when every child is known at compile time, so any
+/// code block or control flow in the child list disables the check.
+fn check_child_order(name: &Ident, children: &[Node]) -> TokenStream {
+    // Comments don't count towards the order.
+    let children: Vec<&Node> = children
+        .iter()
+        .filter(|child| !matches!(child, Node::Comment(_, _)))
+        .collect();
+    let mut names = Vec::new();
+    let mut failures = TokenStream::new();
+    for (index, child) in children.iter().enumerate() {
+        let (span, error) = match child {
+            Node::Element(el) => {
+                let child_name = Literal::string(&el.name.to_string());
+                names.push(quote!(#child_name));
+                (
+                    el.name.span(),
+                    format!(
+                        "<{}> is not allowed here: the children of <{}> must follow the order declared for it",
+                        el.name, name
+                    ),
+                )
+            }
+            Node::Text(text) | Node::Format(text) => {
+                names.push(quote!(typed_html::elements::TEXT_NODE));
+                (
+                    text.span(),
+                    format!(
+                        "text is not allowed here: the children of <{}> must follow the order declared for it",
+                        name
+                    ),
+                )
+            }
+            Node::Comment(_, _) => unreachable!(),
+            Node::Block(_) | Node::Fragment(_) | Node::Control(_) => return TokenStream::new(),
+        };
+        failures.extend(quote_spanned! {span=>
+            Err(#index) => panic!(#error),
+        });
+    }
+    let error = format!(
+        "<{}> is missing children: they must follow the order declared for it",
+        name
+    );
+    let span = name.span();
+    failures.extend(quote_spanned! {span=>
+        Err(_) => panic!(#error),
+    });
+    let typename = Ident::new(&name.to_string(), Span::call_site());
+    quote!(
+        const _: () = if let Some(order) = typed_html::elements::child_orders::#typename {
+            match order.check(&[ #(#names),* ]) {
+                Ok(()) => (),
+                #failures
+            }
+        };
+    )
+}
+
 fn process_value(value: &TokenTree) -> TokenStream {
     match value {
         TokenTree::Group(g) if g.delimiter() == Delimiter::Bracket => {
@@ -137,11 +708,186 @@ fn process_value(value: &TokenTree) -> TokenStream {
     }
 }
 
-fn is_string_literal(literal: &Literal) -> bool {
+/// Generate the code setting an attribute with `assign`, given the code for
+/// its value. An optional attribute's value is an `Option`, and it's only set
+/// when that is `Some`.
+fn set_value<F>(value: &TokenTree, optional: bool, assign: F) -> TokenStream
+where
+    F: FnOnce(TokenStream) -> TokenStream,
+{
+    let value = process_value(value);
+    if optional {
+        let assign = assign(quote!(value));
+        quote!(
+            if let Some(value) = #value {
+                #assign
+            }
+        )
+    } else {
+        assign(value)
+    }
+}
+
+/// Check a class name against the rules in `typed_html::types::Class`, so an
+/// invalid `class:name` toggle fails at compile time rather than at runtime.
+pub fn validate_class(name: &str) -> Result<(), &'static str> {
+    if !name.starts_with(char::is_alphabetic) {
+        return Err("class name must start with an alphabetic character");
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+    {
+        return Err("class name can only contain alphanumerics, dash, dot and underscore");
+    }
+    Ok(())
+}
+
+/// Generate the code adding the classes from `class:name=value` toggles to
+/// an element's `class` attribute.
+fn toggle_classes(classes: &[(String, TokenTree)]) -> TokenStream {
+    let mut out = TokenStream::new();
+    for (name, value) in classes {
+        let value = process_value(value);
+        out.extend(quote!(
+            if #value {
+                element
+                    .attrs
+                    .class
+                    .get_or_insert_with(Default::default)
+                    .insert(typed_html::types::Class::new(#name));
+            }
+        ));
+    }
+    out
+}
+
+/// Check that a comment's text is a string literal which is allowed inside a
+/// comment, rather than leaving `CommentNode` to escape it.
+pub fn is_valid_comment(literal: &Literal) -> bool {
+    if !is_string_literal(literal) {
+        return false;
+    }
+    let text = literal.to_string();
+    let text = &text[1..text.len() - 1];
+    !(text.contains("--") || text.starts_with('>') || text.starts_with("->") || text.ends_with('-'))
+}
+
+pub fn is_string_literal(literal: &Literal) -> bool {
     // This is the worst API
     literal.to_string().starts_with('"')
 }
 
+/// Get the value of a string literal, with its escapes resolved.
+fn string_value(literal: &Literal) -> String {
+    let literal = literal.to_string();
+    let mut chars = literal[1..literal.len() - 1].chars().peekable();
+    let mut value = String::new();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => value.push('\n'),
+            Some('r') => value.push('\r'),
+            Some('t') => value.push('\t'),
+            Some('0') => value.push('\0'),
+            Some('x') => {
+                let code: String = chars.by_ref().take(2).collect();
+                value.extend(u8::from_str_radix(&code, 16).ok().map(char::from));
+            }
+            Some('u') => {
+                let code: String = chars
+                    .by_ref()
+                    .skip(1)
+                    .take_while(|c| *c != '}')
+                    .filter(|c| *c != '_')
+                    .collect();
+                value.extend(
+                    u32::from_str_radix(&code, 16)
+                        .ok()
+                        .and_then(std::char::from_u32),
+                );
+            }
+            // A line continuation skips the whitespace at the start of the
+            // next line.
+            Some('\n') => {
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+            }
+            Some(c) => value.push(c),
+            None => (),
+        }
+    }
+    value
+}
+
+/// Split the value of a format string into the literal text around its
+/// `{...}` arguments.
+fn format_parts(format: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' if chars.peek() == Some(&c) => {
+                chars.next();
+                parts.last_mut().unwrap().push(c);
+            }
+            '{' => {
+                chars.by_ref().find(|c| *c == '}');
+                parts.push(String::new());
+            }
+            c => parts.last_mut().unwrap().push(c),
+        }
+    }
+    parts
+}
+
+/// Generate a check that a string literal, or the literal parts of a
+/// `f!"..."` format string, given for an element's attribute could be
+/// parsed into the attribute's type, which fails to compile if not.
+fn check_attribute_value(
+    element: &str,
+    attribute: &str,
+    key: &TokenTree,
+    literal: &Literal,
+    formatted: bool,
+) -> TokenStream {
+    let value = string_value(literal);
+    let parts = if formatted {
+        format_parts(&value)
+    } else {
+        vec![value]
+    };
+    let error = if formatted {
+        format!(
+            "<{} {}=f!{}> can't be parsed into the attribute's type, whatever the arguments are",
+            element, attribute, literal
+        )
+    } else {
+        format!(
+            "<{} {}={}> can't be parsed into the attribute's type",
+            element, attribute, literal
+        )
+    };
+    let attrs = Ident::new(&format!("Attrs_{}", element), Span::call_site());
+    let span = literal.span();
+    let fail = quote_spanned! {span=>
+        panic!("{}", #error)
+    };
+    quote!(
+        const _: () = if !typed_html::types::ValueSyntax::of(
+            |attrs: &typed_html::elements::#attrs| &attrs.#key
+        )
+        .matches(&[ #(#parts),* ])
+        {
+            #fail
+        };
+    )
+}
+
 #[allow(dead_code)]
 fn stringify_ident(ident: &Ident) -> String {
     let s = ident.to_string();
@@ -153,7 +899,68 @@ fn stringify_ident(ident: &Ident) -> String {
 }
 
 impl Element {
+    /// Test whether the element is a component, whose name starts with a
+    /// capital letter, rather than an HTML element.
+    fn is_component(&self) -> bool {
+        self.name
+            .to_string()
+            .starts_with(|c: char| c.is_ascii_uppercase())
+    }
+
+    fn into_component_stream(self, ty: &Option<Vec<Token>>) -> Result<TokenStream, TokenStream> {
+        let name = self.name;
+        let type_var = match ty {
+            Some(ty) => to_stream(ty.clone()),
+            None => quote!(_),
+        };
+        if !self.attrs.classes.is_empty() {
+            let span = name.span();
+            return Err(quote_spanned! {span=>
+                compile_error! { "class:name toggles can't be used on a component" }
+            });
+        }
+        if self.attrs.spread.len() > 1 {
+            let span = name.span();
+            return Err(quote_spanned! {span=>
+                compile_error! { "a component can only take one {..props} spread" }
+            });
+        }
+        let mut props = TokenStream::new();
+        for (key, value) in self.attrs.attributes.iter() {
+            let formatted = self.attrs.formatted.contains(&key.to_string());
+            let key = TokenTree::Ident(ident::new_raw(&key.to_string(), key.span()));
+            let value = match value {
+                TokenTree::Literal(lit) if formatted => quote!(format!(#lit)),
+                TokenTree::Literal(lit) if is_string_literal(lit) => {
+                    quote!(std::convert::Into::into(#lit))
+                }
+                value => process_value(value),
+            };
+            props.extend(quote!( #key: #value, ));
+        }
+        for spread in &self.attrs.spread {
+            props.extend(quote!( ..#spread ));
+        }
+        let children = self
+            .children
+            .into_iter()
+            .map(|node| node.into_child_stream(ty, &quote!(children)))
+            .collect::<Result<Vec<TokenStream>, TokenStream>>()?;
+        Ok(quote!(
+            {
+                let component = #name { #props };
+                #[allow(unused_mut)]
+                let mut children = typed_html::component::children::<#type_var, _>(&component);
+                #( #children )*
+                typed_html::component::Component::<#type_var>::render(component, children)
+            }
+        ))
+    }
+
     fn into_token_stream(mut self, ty: &Option<Vec<Token>>) -> Result<TokenStream, TokenStream> {
+        if self.is_component() {
+            return self.into_component_stream(ty);
+        }
         let name = self.name;
         let name_str = name.to_string();
         let typename: TokenTree = Ident::new(&name_str, name.span()).into();
@@ -170,32 +977,53 @@ impl Element {
                 compile_error! { #error }
             });
         }
-        let events = extract_event_handlers(&mut self.attributes);
-        let data_attrs = extract_data_attrs(&mut self.attributes);
-        let attrs = self.attributes.iter().map(|(key, value)| {
+        let check_order = check_child_order(&name, &self.children);
+        let events = extract_event_handlers(&mut self.attrs);
+        let data_attrs = extract_data_attrs(&mut self.attrs);
+        let key = extract_key(&mut self.attrs);
+        let optional = &self.attrs.optional;
+        let formatted = &self.attrs.formatted;
+        let attrs = self.attrs.attributes.iter().map(|(key, value)| {
             (
                 key.to_string(),
                 TokenTree::Ident(ident::new_raw(&key.to_string(), key.span())),
                 value,
+                optional.contains(&key.to_string()),
+                formatted.contains(&key.to_string()),
             )
         });
+        let req_children = take_required(&name, &mut self.children, req_names)?;
+        let positions: Vec<(String, usize)> = req_names
+            .iter()
+            .zip(&req_children)
+            .map(|(name, (_, position))| (name.to_string(), *position))
+            .collect();
         let opt_children = self
             .children
-            .split_off(req_names.len())
             .into_iter()
-            .map(|node| node.into_child_stream(ty))
+            .map(|node| node.into_child_stream(ty, &quote!(element.children)))
             .collect::<Result<Vec<TokenStream>, TokenStream>>()?;
-        let req_children = self
-            .children
+        let opt_children = place_required(&positions, opt_children, &quote!(element));
+        let req_children = req_children
             .into_iter()
-            .map(|node| node.into_token_stream(ty))
+            .map(|(node, _)| node.into_token_stream(ty))
             .collect::<Result<Vec<TokenStream>, TokenStream>>()?;
 
         let mut body = TokenStream::new();
 
-        for (attr_str, key, value) in attrs {
+        // Spread attributes go first, so the explicit ones override them.
+        for spread in &self.attrs.spread {
+            body.extend(quote!(
+                element.attrs.merge(#spread);
+            ));
+        }
+
+        for (attr_str, key, value, optional, formatted) in attrs {
             match value {
-                TokenTree::Literal(lit) if is_string_literal(lit) => {
+                TokenTree::Literal(lit) if is_string_literal(lit) && !optional => {
+                    body.extend(check_attribute_value(
+                        &name_str, &attr_str, &key, lit, formatted,
+                    ));
                     let mut eprintln_msg = "ERROR: ".to_owned();
                     #[cfg(can_show_location_of_runtime_parse_error)]
                     {
@@ -212,40 +1040,57 @@ impl Element {
                         );
                     }
                     eprintln_msg += &format!(
-                        "<{} {}={}> failed to parse attribute value: {{}}",
-                        name_str, attr_str, lit,
+                        "<{} {}={}{}> failed to parse attribute value: {{}}",
+                        name_str,
+                        attr_str,
+                        if formatted { "f!" } else { "" },
+                        lit,
                     );
                     #[cfg(not(can_show_location_of_runtime_parse_error))]
                     {
                         eprintln_msg += "\nERROR: rebuild with nightly to print source location";
                     }
+                    let source = if formatted {
+                        quote!(format!(#lit))
+                    } else {
+                        quote!(#lit)
+                    };
 
                     body.extend(quote!(
-                        element.attrs.#key = Some(#lit.parse().unwrap_or_else(|err| {
+                        element.attrs.#key = Some(#source.parse().unwrap_or_else(|err| {
                             eprintln!(#eprintln_msg, err);
                             panic!("failed to parse string literal");
                         }));
                     ));
                 }
                 value => {
-                    let value = process_value(value);
-                    body.extend(quote!(
-                        element.attrs.#key = Some(std::convert::TryInto::try_into(#value).unwrap());
-                    ));
+                    body.extend(set_value(value, optional, |value| {
+                        quote!(
+                            element.attrs.#key = Some(std::convert::TryInto::try_into(#value).unwrap());
+                        )
+                    }));
                 }
             }
         }
-        for (key, value) in data_attrs
-            .iter()
-            .map(|(k, v)| (TokenTree::from(Literal::string(k)), v.clone()))
-        {
-            body.extend(quote!(
-                element.data_attributes.push((#key, #value.into()));
-            ));
+        body.extend(toggle_classes(&self.attrs.classes));
+        for (key, (value, optional)) in data_attrs.iter() {
+            let key = TokenTree::from(Literal::string(key));
+            body.extend(set_value(value, *optional, |value| {
+                quote!(
+                    element.data_attributes.push((#key.into(), #value.into()));
+                )
+            }));
+        }
+        if let Some((value, optional)) = &key {
+            body.extend(set_value(value, *optional, |value| {
+                quote!(
+                    element.key = Some(#value.to_string());
+                )
+            }));
         }
         body.extend(opt_children);
 
-        for (key, value) in events.iter() {
+        for (key, (value, optional)) in events.iter() {
             if ty.is_none() {
                 let mut err = quote_spanned! { key.span() =>
                     compile_error! { "when using event handlers, you must declare the output type inside the html! macro" }
@@ -257,10 +1102,11 @@ impl Element {
                 return Err(err);
             }
             let key = TokenTree::Ident(key.clone());
-            let value = process_value(value);
-            body.extend(quote!(
-                element.events.#key = Some(#value.into());
-            ));
+            body.extend(set_value(value, *optional, |value| {
+                quote!(
+                    element.events.#key = Some(#value.into());
+                )
+            }));
         }
 
         let mut args = TokenStream::new();
@@ -276,6 +1122,7 @@ impl Element {
 
         Ok(quote!(
             {
+                #check_order
                 let mut element #type_annotation = typed_html::elements::#typename::new(#args);
                 #body
                 Box::new(element)
@@ -289,6 +1136,12 @@ impl Element {
         bump: &Ident,
         is_req_child: bool,
     ) -> Result<TokenStream, TokenStream> {
+        if self.is_component() {
+            let span = self.name.span();
+            return Err(quote_spanned! {span=>
+                compile_error! { "components can't be used in the dodrio! macro" }
+            });
+        }
         let name = self.name;
         let name_str = stringify_ident(&name);
         let typename: TokenTree = ident::new_raw(&name_str, name.span()).into();
@@ -306,27 +1159,43 @@ impl Element {
                 compile_error! { #error }
             });
         }
-        let events = extract_event_handlers(&mut self.attributes);
-        let data_attrs = extract_data_attrs(&mut self.attributes);
-        let attrs = self.attributes.iter().map(|(key, value)| {
+        let check_order = check_child_order(&name, &self.children);
+        let events = extract_event_handlers(&mut self.attrs);
+        let data_attrs = extract_data_attrs(&mut self.attrs);
+        let key = extract_key(&mut self.attrs);
+        let optional = &self.attrs.optional;
+        let formatted = &self.attrs.formatted;
+        let attrs = self.attrs.attributes.iter().map(|(key, value)| {
             (
                 key.to_string(),
                 TokenTree::Ident(ident::new_raw(&key.to_string(), key.span())),
                 value,
+                optional.contains(&key.to_string()),
+                formatted.contains(&key.to_string()),
             )
         });
-        let opt_children = self.children.split_off(req_names.len());
-        let req_children = self
-            .children
+        let req_children = take_required(&name, &mut self.children, req_names)?;
+        let opt_children = self.children;
+        let req_children = req_children
             .into_iter()
-            .map(|node| node.into_dodrio_token_stream(bump, true))
-            .collect::<Result<Vec<TokenStream>, TokenStream>>()?;
+            .map(|(node, position)| Ok((node.into_dodrio_token_stream(bump, true)?, position)))
+            .collect::<Result<Vec<(TokenStream, usize)>, TokenStream>>()?;
 
         let mut set_attrs = TokenStream::new();
 
-        for (attr_str, key, value) in attrs {
+        // Spread attributes go first, so the explicit ones override them.
+        for spread in &self.attrs.spread {
+            set_attrs.extend(quote!(
+                element.attrs.merge(#spread);
+            ));
+        }
+
+        for (attr_str, key, value, optional, formatted) in attrs {
             match value {
-                TokenTree::Literal(lit) if is_string_literal(lit) => {
+                TokenTree::Literal(lit) if is_string_literal(lit) && !optional => {
+                    set_attrs.extend(check_attribute_value(
+                        &name_str, &attr_str, &key, lit, formatted,
+                    ));
                     let mut eprintln_msg = "ERROR: ".to_owned();
                     #[cfg(can_show_location_of_runtime_parse_error)]
                     {
@@ -343,79 +1212,135 @@ impl Element {
                         );
                     }
                     eprintln_msg += &format!(
-                        "<{} {}={}> failed to parse attribute value: {{}}",
-                        name_str, attr_str, lit,
+                        "<{} {}={}{}> failed to parse attribute value: {{}}",
+                        name_str,
+                        attr_str,
+                        if formatted { "f!" } else { "" },
+                        lit,
                     );
                     #[cfg(not(can_show_location_of_runtime_parse_error))]
                     {
                         eprintln_msg += "\nERROR: rebuild with nightly to print source location";
                     }
+                    let source = if formatted {
+                        quote!(format!(#lit))
+                    } else {
+                        quote!(#lit)
+                    };
 
                     set_attrs.extend(quote!(
-                        element.attrs.#key = Some(#lit.parse().unwrap_or_else(|err| {
+                        element.attrs.#key = Some(#source.parse().unwrap_or_else(|err| {
                             eprintln!(#eprintln_msg, err);
                             panic!("failed to parse string literal");
                         }));
                     ));
                 }
                 value => {
-                    let value = process_value(value);
-                    set_attrs.extend(quote!(
-                        element.attrs.#key = Some(std::convert::TryInto::try_into(#value).unwrap());
-                    ));
+                    set_attrs.extend(set_value(value, optional, |value| {
+                        quote!(
+                            element.attrs.#key = Some(std::convert::TryInto::try_into(#value).unwrap());
+                        )
+                    }));
                 }
             }
         }
 
-        let attr_max_len = self.attributes.len() + data_attrs.len();
+        set_attrs.extend(toggle_classes(&self.attrs.classes));
+
+        // Dodrio's keys are `u32`s, so the key is converted into one and
+        // evaluated once, here, for both the element and the builder.
+        if let Some((value, optional)) = &key {
+            let value = process_value(value);
+            set_attrs.extend(if *optional {
+                quote!(
+                    let node_key: Option<u32> = #value.map(Into::into);
+                    element.key = node_key.map(|key| key.to_string());
+                )
+            } else {
+                quote!(
+                    let node_key: u32 = #value.into();
+                    element.key = Some(node_key.to_string());
+                )
+            });
+        }
+
+        let mut keys: Vec<Ident> = self.attrs.attributes.keys().cloned().collect();
+        if !self.attrs.classes.is_empty() && !keys.iter().any(|key| key == "class") {
+            keys.push(Ident::new("class", Span::call_site()));
+        }
+        let attr_max_len = keys.len() + data_attrs.len();
         let mut builder = quote!(
             let mut attr_list = dodrio::bumpalo::collections::Vec::with_capacity_in(#attr_max_len, #bump);
         );
 
-        // Build the attributes.
-        for (key, _) in self.attributes.iter() {
-            let key_str = stringify_ident(key);
-            let key = ident::new_raw(&key_str, key.span());
-            let key_str = TokenTree::from(Literal::string(&key_str));
+        // Build the attributes. If there are spread attributes, we don't know
+        // which are set until runtime.
+        if self.attrs.spread.is_empty() {
+            for key in &keys {
+                let key_str = stringify_ident(key);
+                let key = ident::new_raw(&key_str, key.span());
+                let key_str = TokenTree::from(Literal::string(&key_str));
+                builder.extend(quote!(
+                    if let Some(attr_value) = &element.attrs.#key {
+                        let attr_value = dodrio::bumpalo::format!(in &#bump, "{}", attr_value);
+                        if !attr_value.is_empty() {
+                            attr_list.push(dodrio::builder::attr(#key_str, attr_value.into_bump_str()));
+                        }
+                    }
+                ));
+            }
+        } else {
             builder.extend(quote!(
-                let attr_value = dodrio::bumpalo::format!(
-                    in &#bump, "{}", element.attrs.#key.unwrap());
-                if !attr_value.is_empty() {
-                    attr_list.push(dodrio::builder::attr(#key_str, attr_value.into_bump_str()));
+                for (key, value) in typed_html::dom::Element::attributes(&element) {
+                    let attr_value = dodrio::bumpalo::format!(in &#bump, "{}", value);
+                    if !attr_value.is_empty() {
+                        attr_list.push(dodrio::builder::attr(key, attr_value.into_bump_str()));
+                    }
                 }
             ));
         }
-        for (key, value) in data_attrs
-            .iter()
-            .map(|(k, v)| (TokenTree::from(Literal::string(&k)), v.clone()))
-        {
-            builder.extend(quote!(
-                attr_list.push(dodrio::builder::attr(
-                    #key,
-                    dodrio::bumpalo::format!(
-                        in &#bump, "{}", #value
-                    ).into_bump_str()
-                ));
-            ));
+        for (key, (value, optional)) in data_attrs.iter() {
+            let key = TokenTree::from(Literal::string(key));
+            builder.extend(set_value(value, *optional, |value| {
+                quote!(
+                    attr_list.push(dodrio::builder::attr(
+                        #key,
+                        dodrio::bumpalo::format!(
+                            in &#bump, "{}", #value
+                        ).into_bump_str()
+                    ));
+                )
+            }));
         }
 
+        // Build a list of event listeners.
+        let listener_max_len = events.len();
         builder.extend(quote!(
-            let mut node = dodrio::builder::ElementBuilder::new(#bump, #tag_name)
-                             .attributes(attr_list)
+            let mut listener_list = dodrio::bumpalo::collections::Vec::with_capacity_in(#listener_max_len, #bump);
         ));
-
-        // Build an array of event listeners.
-        let mut event_array = TokenStream::new();
-        for (key, value) in events.iter() {
+        for (key, (value, optional)) in events.iter() {
             let key = TokenTree::from(Literal::string(&stringify_ident(key)));
-            let value = process_value(value);
-            event_array.extend(quote!(
-                dodrio::builder::on(&#bump, #key, #value),
-            ));
+            builder.extend(set_value(value, *optional, |value| {
+                quote!(
+                    listener_list.push(dodrio::builder::on(&#bump, #key, #value));
+                )
+            }));
         }
+
         builder.extend(quote!(
-            .listeners([#event_array]);
+            let mut node = dodrio::builder::ElementBuilder::new(#bump, #tag_name)
+                             .attributes(attr_list)
+                             .listeners(listener_list);
         ));
+        match key {
+            Some((_, true)) => builder.extend(quote!(if let Some(node_key) = node_key {
+                node = node.key(node_key);
+            })),
+            Some((_, false)) => builder.extend(quote!(
+                node = node.key(node_key);
+            )),
+            None => {}
+        }
 
         // And finally an array of children, or a stream of builder commands
         // if we have a group inside the child list.
@@ -423,10 +1348,12 @@ impl Element {
         let mut child_builder = TokenStream::new();
         let mut static_children = true;
 
-        // Walk through required children and build them inline.
+        // Walk through required children and build them inline, to be put
+        // back among the others where they were.
         let mut make_req_children = TokenStream::new();
         let mut arg_list = Vec::new();
-        for (index, child) in req_children.into_iter().enumerate() {
+        let mut req_nodes = Vec::new();
+        for (index, (child, position)) in req_children.into_iter().enumerate() {
             let req_child = TokenTree::from(ident::new_raw(
                 &format!("req_child_{}", index),
                 Span::call_site(),
@@ -438,28 +1365,39 @@ impl Element {
             make_req_children.extend(quote!(
                 let (#req_child, #child_node) = #child;
             ));
-            child_array.extend(quote!(
-                #child_node,
-            ));
-            child_builder.extend(quote!(
-                node = node.child(#child_node);
-            ));
+            req_nodes.push((child_node, position));
             arg_list.push(req_child);
         }
+        let push_required =
+            |index: usize, child_array: &mut TokenStream, child_builder: &mut TokenStream| {
+                for (child_node, _) in req_nodes.iter().filter(|(_, position)| *position == index) {
+                    child_array.extend(quote!(
+                        #child_node,
+                    ));
+                    child_builder.extend(quote!(
+                        node = node.child(#child_node);
+                    ));
+                }
+            };
 
         // Build optional children, test if we have groups.
-        for child_node in opt_children {
+        let count = opt_children.len();
+        for (index, child_node) in opt_children.into_iter().enumerate() {
+            push_required(index, &mut child_array, &mut child_builder);
             let child = match child_node {
                 Node::Text(text) => dodrio_text_node(text),
+                Node::Format(text) => dodrio_format_node(text, bump),
+                Node::Comment(span, _) => return Err(dodrio_comment_error(span)),
                 Node::Element(el) => el.into_dodrio_token_stream(bump, false)?,
-                Node::Block(group) => {
+                node @ Node::Fragment(_) => {
+                    let span = node.span();
+                    return Err(quote_spanned! { span=>
+                        compile_error! { "unexpected fragment" }
+                    });
+                }
+                node @ Node::Block(_) | node @ Node::Control(_) => {
                     static_children = false;
-                    let group: TokenTree = group.into();
-                    child_builder.extend(quote!(
-                        for child in #group.into_iter() {
-                            node = node.child(child);
-                        }
-                    ));
+                    child_builder.extend(node.into_dodrio_child_stream(bump)?);
                     continue;
                 }
             };
@@ -470,6 +1408,7 @@ impl Element {
                 node = node.child(#child);
             ));
         }
+        push_required(count, &mut child_array, &mut child_builder);
 
         if static_children {
             builder.extend(quote!(
@@ -493,6 +1432,7 @@ impl Element {
 
         Ok(quote!(
             {
+                #check_order
                 #make_req_children
                 let mut element: typed_html::elements::#typename<typed_html::output::dodrio::Dodrio> =
                       typed_html::elements::#typename::new(#args);
@@ -503,7 +1443,6 @@ impl Element {
     }
 }
 
-// FIXME report a decent error when the macro contains multiple top level elements
 pub fn expand_html(input: &[Token]) -> Result<(Node, Option<Vec<Token>>), ParseError> {
     grammar::NodeWithTypeParser::new().parse(Lexer::new(input))
 }
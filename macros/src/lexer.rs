@@ -82,6 +82,12 @@ impl From<Group> for Token {
 pub enum Keyword {
     In,
     With,
+    Ordered,
+    If,
+    Else,
+    For,
+    Match,
+    Format,
 }
 
 pub fn keywordise(tokens: Vec<Token>) -> Vec<Token> {
@@ -94,6 +100,8 @@ pub fn keywordise(tokens: Vec<Token>) -> Vec<Token> {
                     Token::Keyword(Keyword::In, ident)
                 } else if name == "with" {
                     Token::Keyword(Keyword::With, ident)
+                } else if name == "ordered" {
+                    Token::Keyword(Keyword::Ordered, ident)
                 } else {
                     Token::Ident(ident)
                 }
@@ -103,6 +111,41 @@ pub fn keywordise(tokens: Vec<Token>) -> Vec<Token> {
         .collect()
 }
 
+/// Turn the Rust keywords starting control flow in the `html!` macro into
+/// keyword tokens.
+///
+/// An identifier which is part of an attribute name, like the `for` in
+/// `<label for="name">`, the `if` in `data-if="yes"` or the `match` in
+/// `class:match=true`, is left alone. The `f` in a `f!"..."` format string
+/// becomes a keyword too.
+pub fn keywordise_html(mut tokens: Vec<Token>) -> Vec<Token> {
+    for index in 0..tokens.len() {
+        let ident = match &tokens[index] {
+            Token::Ident(ident) => ident.clone(),
+            _ => continue,
+        };
+        let prev = index.checked_sub(1).and_then(|index| tokens.get(index));
+        let next = tokens.get(index + 1);
+        let in_attr_name = matches!(prev, Some(Token::Punct('-', _)))
+            || matches!(prev, Some(Token::Punct(':', _)))
+            || matches!(next, Some(Token::Punct('=', _)));
+        let is_format = matches!(next, Some(Token::Punct('!', _)))
+            && matches!(tokens.get(index + 2), Some(Token::Literal(_)));
+        let keyword = match ident.to_string().as_str() {
+            "f" if is_format => Keyword::Format,
+            _ if in_attr_name => continue,
+            "if" => Keyword::If,
+            "else" => Keyword::Else,
+            "for" => Keyword::For,
+            "in" => Keyword::In,
+            "match" => Keyword::Match,
+            _ => continue,
+        };
+        tokens[index] = Token::Keyword(keyword, ident);
+    }
+    tokens
+}
+
 pub fn to_stream<I: IntoIterator<Item = Token>>(tokens: I) -> TokenStream {
     let mut stream = TokenStream::new();
     stream.extend(tokens.into_iter().map(TokenTree::from));
@@ -12,6 +12,7 @@ mod html;
 mod ident;
 mod lexer;
 mod map;
+mod order;
 mod parser;
 mod span;
 
@@ -22,7 +23,7 @@ mod span;
 /// [typed_html]: ../typed_html/index.html
 #[proc_macro]
 pub fn html(input: TokenStream) -> TokenStream {
-    let stream = lexer::unroll_stream(input.into(), false);
+    let stream = lexer::keywordise_html(lexer::unroll_stream(input.into(), false));
     let result = html::expand_html(&stream);
     TokenStream::from(match result {
         Err(err) => error::parse_error(&stream, &err),
@@ -41,7 +42,7 @@ pub fn html(input: TokenStream) -> TokenStream {
 #[cfg(feature = "dodrio")]
 #[proc_macro]
 pub fn dodrio(input: TokenStream) -> TokenStream {
-    let stream = lexer::unroll_stream(input.into(), false);
+    let stream = lexer::keywordise_html(lexer::unroll_stream(input.into(), false));
     let result = html::expand_dodrio(&stream);
     TokenStream::from(match result {
         Err(err) => error::parse_error(&stream, &err),
@@ -62,7 +63,9 @@ pub fn declare_elements(input: TokenStream) -> TokenStream {
     TokenStream::from(match result {
         Err(err) => error::parse_error(&stream, &err),
         Ok(decls) => {
-            let mut out = proc_macro2::TokenStream::new();
+            let mut out = declare::parse_support(&decls);
+            out.extend(declare::visitors(&decls));
+            out.extend(declare::child_orders(&decls));
             for decl in decls {
                 out.extend(decl.into_token_stream());
             }
@@ -0,0 +1,54 @@
+use proc_macro2::{Ident, Literal, TokenStream, TokenTree};
+use quote::quote;
+
+/// A pattern describing the permitted order of an element's children, as
+/// written in the `ordered` clause of `declare_elements!`.
+///
+/// This only describes the pattern: it's turned into a
+/// `typed_html::elements::ChildOrder`, which does the matching, both at
+/// runtime and for the `html!` macro's compile time checks.
+pub enum ChildOrder {
+    /// Exactly one element with the given name.
+    Element(Ident),
+    /// Any single node whose name isn't mentioned elsewhere in the pattern.
+    Any,
+    Sequence(Vec<ChildOrder>),
+    Choice(Vec<ChildOrder>),
+    Optional(Box<ChildOrder>),
+    ZeroOrMore(Box<ChildOrder>),
+    OneOrMore(Box<ChildOrder>),
+}
+
+impl ChildOrder {
+    /// Generate a `typed_html::elements::ChildOrder` expression from the
+    /// pattern, for use inside the `typed_html` crate.
+    pub fn to_tokens(&self) -> TokenStream {
+        match self {
+            ChildOrder::Element(name) => {
+                let name: TokenTree = Literal::string(&name.to_string()).into();
+                quote!(crate::elements::ChildOrder::Element(#name))
+            }
+            ChildOrder::Any => quote!(crate::elements::ChildOrder::Any),
+            ChildOrder::Sequence(items) => {
+                let items: Vec<TokenStream> = items.iter().map(ChildOrder::to_tokens).collect();
+                quote!(crate::elements::ChildOrder::Sequence(&[ #(#items),* ]))
+            }
+            ChildOrder::Choice(items) => {
+                let items: Vec<TokenStream> = items.iter().map(ChildOrder::to_tokens).collect();
+                quote!(crate::elements::ChildOrder::Choice(&[ #(#items),* ]))
+            }
+            ChildOrder::Optional(item) => {
+                let item = item.to_tokens();
+                quote!(crate::elements::ChildOrder::Optional(&#item))
+            }
+            ChildOrder::ZeroOrMore(item) => {
+                let item = item.to_tokens();
+                quote!(crate::elements::ChildOrder::ZeroOrMore(&#item))
+            }
+            ChildOrder::OneOrMore(item) => {
+                let item = item.to_tokens();
+                quote!(crate::elements::ChildOrder::OneOrMore(&#item))
+            }
+        }
+    }
+}
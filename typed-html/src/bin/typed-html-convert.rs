@@ -0,0 +1,339 @@
+//! Convert an HTML file into the equivalent `html!` macro source.
+//!
+//! Usage: `typed-html-convert [FILE]`, reading from standard input if no file
+//! is given. The macro source is printed to standard output, and problems
+//! with the input to standard error:
+//!
+//! * elements and attributes typed-html doesn't declare, and event handlers,
+//!   which need rewriting as Rust closures, are warnings;
+//! * the first place where `html!` would reject the output, because the
+//!   content model doesn't allow a child or an attribute value doesn't parse,
+//!   is an error.
+//!
+//! The exit status is 1 if the input isn't well formed HTML, 2 if it was
+//! converted but there were problems, and 0 otherwise.
+
+use std::fmt::Write;
+use std::io::Read;
+use std::process::exit;
+
+use typed_html::dom::Node;
+use typed_html::elements::element_attributes;
+use typed_html::parse::{from_raw, parse_raw, Location, RawAttribute, RawElement, RawNode};
+
+/// Elements whose text content is kept exactly as it is.
+static PREFORMATTED: &[&str] = &["pre", "script", "style", "textarea"];
+
+static INDENT: &str = "    ";
+
+#[derive(Default)]
+struct Converter {
+    output: String,
+    diagnostics: Vec<(Location, &'static str, String)>,
+}
+
+/// Test whether an attribute name can be written in `html!`, where it has to
+/// be a list of Rust identifiers separated by dashes.
+fn is_html_ident(name: &str) -> bool {
+    name.split('-').all(|part| {
+        part.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// Collapse the whitespace in a text node the way a browser would display it.
+fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::new();
+    if text.starts_with(char::is_whitespace) {
+        out.push(' ');
+    }
+    out.push_str(&text.split_whitespace().collect::<Vec<_>>().join(" "));
+    if text.ends_with(char::is_whitespace) && out != " " {
+        out.push(' ');
+    }
+    out
+}
+
+impl Converter {
+    fn warn(&mut self, location: Location, message: String) {
+        self.diagnostics.push((location, "warning", message));
+    }
+
+    fn line(&mut self, depth: usize, line: &str) {
+        for _ in 0..depth {
+            self.output.push_str(INDENT);
+        }
+        self.output.push_str(line);
+        self.output.push('\n');
+    }
+
+    /// Turn a text node into a string literal, or `None` if it's only there
+    /// to format the source.
+    fn text(&self, text: &str, preformatted: bool) -> Option<String> {
+        if preformatted {
+            Some(format!("{:?}", text))
+        } else if text.trim().is_empty() && text.contains('\n') {
+            None
+        } else {
+            Some(format!("{:?}", collapse_whitespace(text)))
+        }
+    }
+
+    /// Check an element's attributes, writing out the ones `html!` will
+    /// accept and leaving only those in the element, so the content model
+    /// check afterwards doesn't stop at them.
+    fn attributes(&mut self, element: &mut RawElement) -> String {
+        let declared = element_attributes(&element.name);
+        let mut out = String::new();
+        let mut kept = Vec::new();
+        for attr in element.attributes.drain(..) {
+            let RawAttribute {
+                name,
+                value,
+                location,
+            } = &attr;
+            let is_bool = declared
+                .and_then(|attrs| attrs.iter().find(|attr| attr.0 == name))
+                .map(|attr| attr.1);
+            if name.len() > 2 && name.starts_with("on") {
+                self.warn(
+                    *location,
+                    format!(
+                        "event handler {} was dropped, it needs rewriting as a Rust closure",
+                        name
+                    ),
+                );
+            } else if !is_html_ident(name) {
+                self.warn(
+                    *location,
+                    format!(
+                        "attribute {:?} can't be written in html! and was dropped",
+                        name
+                    ),
+                );
+            } else {
+                if is_bool == Some(true) {
+                    write!(out, " {}=true", name).unwrap();
+                } else {
+                    write!(out, " {}={:?}", name, value).unwrap();
+                }
+                if is_bool.is_some() || name.starts_with("data-") {
+                    kept.push(attr);
+                } else if declared.is_some() {
+                    self.warn(
+                        *location,
+                        format!("<{}> has no attribute {:?}", element.name, name),
+                    );
+                }
+            }
+        }
+        element.attributes = kept;
+        out
+    }
+
+    /// Write out a node, returning the nodes to check the content model with
+    /// in its place.
+    fn node(&mut self, node: RawNode, depth: usize, preformatted: bool) -> Vec<RawNode> {
+        match node {
+            RawNode::Text { text, location } => {
+                if let Some(literal) = self.text(&text, preformatted) {
+                    self.line(depth, &literal);
+                }
+                vec![RawNode::Text { text, location }]
+            }
+            RawNode::Element(element) => self.element(element, depth, preformatted),
+        }
+    }
+
+    fn element(
+        &mut self,
+        mut element: RawElement,
+        depth: usize,
+        preformatted: bool,
+    ) -> Vec<RawNode> {
+        let known = element_attributes(&element.name).is_some();
+        if !known {
+            self.warn(
+                element.location,
+                format!("unknown element <{}>", element.name),
+            );
+        }
+        let name = element.name.clone();
+        let attrs = self.attributes(&mut element);
+        let preformatted = preformatted || PREFORMATTED.contains(&name.as_str());
+        let children = std::mem::take(&mut element.children);
+
+        let texts: Vec<Option<String>> = children
+            .iter()
+            .map(|child| match child {
+                RawNode::Text { text, .. } => self.text(text, preformatted),
+                _ => None,
+            })
+            .collect();
+        let mut checked = Vec::new();
+        if children.iter().all(RawNode::is_whitespace) && texts.iter().all(Option::is_none) {
+            self.line(depth, &format!("<{}{}/>", name, attrs));
+        } else if let ([RawNode::Text { .. }], [Some(literal)]) =
+            (children.as_slice(), texts.as_slice())
+        {
+            self.line(depth, &format!("<{}{}>{}</{}>", name, attrs, literal, name));
+            checked = children;
+        } else {
+            self.line(depth, &format!("<{}{}>", name, attrs));
+            for child in children {
+                checked.extend(self.node(child, depth + 1, preformatted));
+            }
+            self.line(depth, &format!("</{}>", name));
+        }
+
+        if known {
+            element.children = checked;
+            vec![RawNode::Element(element)]
+        } else {
+            checked
+        }
+    }
+
+    fn convert(&mut self, nodes: Vec<RawNode>) {
+        let roots: Vec<RawNode> = nodes
+            .into_iter()
+            .filter(|node| !node.is_whitespace())
+            .collect();
+        // html! takes a single root node, so several go into a fragment.
+        let fragment = roots.len() > 1;
+        self.line(0, "html!(");
+        if fragment {
+            self.line(1, "<>");
+        }
+        let depth = if fragment { 2 } else { 1 };
+        let mut checked = Vec::new();
+        for root in roots {
+            checked.extend(self.node(root, depth, false));
+        }
+        if fragment {
+            self.line(1, "</>");
+        }
+        self.line(0, ")");
+        if let Err(error) = from_raw::<String, dyn Node<String>>(checked) {
+            self.diagnostics
+                .push((error.location, "error", error.kind.to_string()));
+        }
+    }
+}
+
+fn main() {
+    let path = std::env::args().nth(1);
+    let mut input = String::new();
+    let read = match &path {
+        Some(path) => {
+            std::fs::File::open(path).and_then(|mut file| file.read_to_string(&mut input))
+        }
+        None => std::io::stdin().read_to_string(&mut input),
+    };
+    let path = path.unwrap_or_else(|| "<stdin>".to_string());
+    if let Err(error) = read {
+        eprintln!("{}: {}", path, error);
+        exit(1);
+    }
+
+    let nodes = match parse_raw(&input) {
+        Ok(nodes) => nodes,
+        Err(error) => {
+            eprintln!("{}:{}: error: {}", path, error.location, error.kind);
+            exit(1);
+        }
+    };
+    let mut converter = Converter::default();
+    converter.convert(nodes);
+    print!("{}", converter.output);
+    converter.diagnostics.sort_by_key(|diagnostic| diagnostic.0);
+    for (location, level, message) in &converter.diagnostics {
+        eprintln!("{}:{}: {}: {}", path, location, level, message);
+    }
+    if !converter.diagnostics.is_empty() {
+        exit(2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(input: &str) -> (String, Vec<String>) {
+        let mut converter = Converter::default();
+        converter.convert(parse_raw(input).unwrap());
+        let diagnostics = converter
+            .diagnostics
+            .iter()
+            .map(|(location, level, message)| format!("{}: {}: {}", location, level, message))
+            .collect();
+        (converter.output, diagnostics)
+    }
+
+    #[test]
+    fn test_convert() {
+        let (output, diagnostics) = convert(
+            r#"<div class="card" data-id="1">
+                <h1>Hello &amp;   welcome</h1>
+                <p>Hello <b>Joe</b>, <i>how</i> <em>are you</em>?</p>
+                <input type="checkbox" checked>
+                <img src="joe.png" alt="">
+            </div>"#,
+        );
+        assert_eq!(Vec::<String>::new(), diagnostics);
+        assert_eq!(
+            r#"html!(
+    <div class="card" data-id="1">
+        <h1>"Hello & welcome"</h1>
+        <p>
+            "Hello "
+            <b>"Joe"</b>
+            ", "
+            <i>"how"</i>
+            " "
+            <em>"are you"</em>
+            "?"
+        </p>
+        <input type="checkbox" checked=true/>
+        <img src="joe.png" alt=""/>
+    </div>
+)
+"#,
+            output
+        );
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let (output, diagnostics) = convert(
+            "<ul>\n  <li onclick=\"go()\" colour=\"red\">One</li>\n  <center>Two</center>\n  <p>Three</p>\n</ul>",
+        );
+        assert_eq!(
+            vec![
+                "2:7: warning: event handler onclick was dropped, it needs rewriting as a Rust closure",
+                "2:22: warning: <li> has no attribute \"colour\"",
+                "3:3: warning: unknown element <center>",
+                "3:11: error: text is not allowed inside <ul>",
+            ],
+            diagnostics
+        );
+        assert!(output.contains("<li colour=\"red\">\"One\"</li>"));
+        assert!(output.contains("<center>\"Two\"</center>"));
+    }
+
+    #[test]
+    fn test_several_roots() {
+        let (output, diagnostics) = convert("<h1>Title</h1>\n<p>Text</p>\n");
+        assert_eq!(Vec::<String>::new(), diagnostics);
+        assert_eq!(
+            r#"html!(
+    <>
+        <h1>"Title"</h1>
+        <p>"Text"</p>
+    </>
+)
+"#,
+            output
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! Reusable components for the `html!` macro.
+//!
+//! A component is a struct holding its props, which implements
+//! [`Component`][Component] to render itself. Inside `html!`, any tag starting
+//! with a capital letter is a component: its attributes become the fields of
+//! the struct and its children are passed to
+//! [`render()`][render], so `<Card title="Hello" count=3>...</Card>` turns into
+//! `Card { title: "Hello".into(), count: 3 }.render(children)`.
+//!
+//! String literal props are converted with [`Into`][Into], so a `String` prop
+//! can be given as a literal. Other values must have the prop's type. Every
+//! field of the struct has to be given, as with any struct literal.
+//!
+//! The component's [`Children`][Children] and [`Output`][Output] types take
+//! part in content model checking just like an element's do: a component
+//! rendering into `dyn PhrasingContent<T>` can be used anywhere phrasing
+//! content is allowed, and only accepts children its `Children` type
+//! allows.
+//!
+//! # Examples
+//!
+//! ```
+//! # #![recursion_limit = "128"]
+//! # use typed_html::{html, text};
+//! # use typed_html::component::Component;
+//! # use typed_html::dom::DOMTree;
+//! # use typed_html::elements::FlowContent;
+//! # use typed_html::OutputType;
+//! struct Card {
+//!     title: String,
+//!     count: usize,
+//! }
+//!
+//! impl<T: OutputType + Send + 'static> Component<T> for Card {
+//!     type Children = dyn FlowContent<T>;
+//!     type Output = dyn FlowContent<T>;
+//!
+//!     fn render(self, children: Vec<Box<dyn FlowContent<T>>>) -> Box<dyn FlowContent<T>> {
+//!         html!(
+//!             <div class="card">
+//!                 <h2>{ text!("{} ({})", self.title, self.count) }</h2>
+//!                 { children }
+//!             </div>
+//!         )
+//!     }
+//! }
+//!
+//! # fn main() {
+//! let doc: DOMTree<String> = html!(
+//!     <section>
+//!         <Card title="Hello Joe" count=3>
+//!             <p>"Nice to meet you."</p>
+//!         </Card>
+//!     </section>
+//! );
+//! assert_eq!(
+//!     "<section><div class=\"card\"><h2>Hello Joe (3)</h2><p>Nice to meet you.</p></div></section>",
+//!     doc.to_string()
+//! );
+//! # }
+//! ```
+//!
+//! [Component]: trait.Component.html
+//! [render]: trait.Component.html#tymethod.render
+//! [Children]: trait.Component.html#associatedtype.Children
+//! [Output]: trait.Component.html#associatedtype.Output
+//! [Into]: https://doc.rust-lang.org/std/convert/trait.Into.html
+
+use crate::dom::Node;
+use crate::OutputType;
+
+/// Trait for components, which render their props and children into a DOM
+/// tree.
+///
+/// See the [module documentation][component] for how to use them.
+///
+/// [component]: index.html
+pub trait Component<T: OutputType + Send> {
+    /// The type of the component's children, usually a content category
+    /// like `dyn FlowContent<T>`, or [`NoChildren`][NoChildren].
+    ///
+    /// [NoChildren]: enum.NoChildren.html
+    type Children: ?Sized;
+    /// The type the component renders into, which decides where it can be
+    /// used. This is usually a content category, or an element type.
+    type Output: ?Sized + Node<T>;
+
+    /// Render the component.
+    fn render(self, children: Vec<Box<Self::Children>>) -> Box<Self::Output>;
+}
+
+/// The [`Children`][Children] type of a component which doesn't accept any
+/// children.
+///
+/// [Children]: trait.Component.html#associatedtype.Children
+pub enum NoChildren {}
+
+/// Make an empty list for a component's children, which the `html!` macro
+/// uses to find their type.
+#[doc(hidden)]
+pub fn children<T, C>(_component: &C) -> Vec<Box<C::Children>>
+where
+    T: OutputType + Send,
+    C: Component<T>,
+{
+    Vec::new()
+}
@@ -0,0 +1,580 @@
+//! Diffing DOM trees.
+//!
+//! [`diff()`][diff] compares two [`OwnedVNode`][OwnedVNode] trees and
+//! returns the [`Patch`][Patch]es which turn the old one into the new one,
+//! for a patcher which keeps a real DOM tree in step with a virtual one.
+//! [`apply()`][apply] applies them to an `OwnedVNode`, which is useful for
+//! testing a patcher against.
+//!
+//! Nodes are addressed by their paths from the root, as lists of child
+//! indices. Patches are applied in order, and each path refers to the tree as
+//! it is after the patches before it have been applied.
+//!
+//! Children are matched up by key where they have one, as given by the `key`
+//! pseudo-attribute in [`html!`][html], and by position otherwise. When keyed children are
+//! reordered, the ones which keep their relative order stay put and only the
+//! rest are moved, which gives the fewest possible moves.
+//!
+//! # Examples
+//!
+//! ```
+//! # use typed_html::html;
+//! # use typed_html::dom::{DOMTree, Node};
+//! # use typed_html::diff::{apply, diff, Patch};
+//! # fn main() {
+//! let mut old: DOMTree<String> = html!(
+//!     <ul>
+//!         <li key="a">"Apples"</li>
+//!         <li key="b">"Bananas"</li>
+//!         <li key="c">"Cherries"</li>
+//!     </ul>
+//! );
+//! let mut new: DOMTree<String> = html!(
+//!     <ul>
+//!         <li key="b">"Bananas"</li>
+//!         <li key="c">"Cherries"</li>
+//!         <li key="a">"Apricots"</li>
+//!     </ul>
+//! );
+//! let mut tree = old.owned_vnode();
+//! let patches = diff(&tree, &new.owned_vnode());
+//! assert_eq!(
+//!     vec![
+//!         Patch::Move { parent: vec![], from: 0, to: 2 },
+//!         Patch::SetText { path: vec![2, 0], text: "Apricots".to_string() },
+//!     ],
+//!     patches
+//! );
+//!
+//! apply(&mut tree, &patches);
+//! assert_eq!(new.to_string(), tree.to_string());
+//! # }
+//! ```
+//!
+//! [diff]: fn.diff.html
+//! [html]: ../macro.html.html
+//! [apply]: fn.apply.html
+//! [Patch]: enum.Patch.html
+//! [OwnedVNode]: ../dom/enum.OwnedVNode.html
+
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+
+use crate::dom::{OwnedVElement, OwnedVNode};
+
+/// An operation on a tree, as produced by [`diff()`][diff].
+///
+/// [diff]: fn.diff.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Patch {
+    /// Insert a node as the child at `index` of the element at `parent`.
+    Insert {
+        parent: Vec<usize>,
+        index: usize,
+        node: OwnedVNode,
+    },
+    /// Remove the child at `index` of the element at `parent`.
+    Remove { parent: Vec<usize>, index: usize },
+    /// Take the child at `from` out of the element at `parent`, and put it
+    /// back in so it's at `to` among the remaining children.
+    Move {
+        parent: Vec<usize>,
+        from: usize,
+        to: usize,
+    },
+    /// Replace the node at `path`.
+    Replace { path: Vec<usize>, node: OwnedVNode },
+    /// Change the content of the text or comment node at `path`.
+    SetText { path: Vec<usize>, text: String },
+    /// Add or change an attribute of the element at `path`.
+    SetAttribute {
+        path: Vec<usize>,
+        name: Cow<'static, str>,
+        value: String,
+    },
+    /// Remove an attribute from the element at `path`.
+    RemoveAttribute {
+        path: Vec<usize>,
+        name: Cow<'static, str>,
+    },
+    /// Add or change an event handler of the element at `path`. The event
+    /// name is without the `on` prefix.
+    SetEvent {
+        path: Vec<usize>,
+        name: &'static str,
+        handler: String,
+    },
+    /// Remove an event handler from the element at `path`.
+    RemoveEvent {
+        path: Vec<usize>,
+        name: &'static str,
+    },
+}
+
+/// Find the patches which turn `old` into `new`.
+pub fn diff(old: &OwnedVNode, new: &OwnedVNode) -> Vec<Patch> {
+    let mut patches = Vec::new();
+    diff_node(&mut Vec::new(), old, new, &mut patches);
+    patches
+}
+
+/// Apply a list of patches to a tree, in order.
+///
+/// # Panics
+///
+/// Panics if a patch doesn't fit the tree, which won't happen with patches
+/// [`diff()`][diff] made for it.
+///
+/// [diff]: fn.diff.html
+pub fn apply(root: &mut OwnedVNode, patches: &[Patch]) {
+    for patch in patches {
+        patch.apply(root);
+    }
+}
+
+impl Patch {
+    /// Apply the patch to a tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the patch doesn't fit the tree.
+    pub fn apply(&self, root: &mut OwnedVNode) {
+        match self {
+            Patch::Insert {
+                parent,
+                index,
+                node,
+            } => children_at(root, parent).insert(*index, node.clone()),
+            Patch::Remove { parent, index } => {
+                children_at(root, parent).remove(*index);
+            }
+            Patch::Move { parent, from, to } => {
+                let children = children_at(root, parent);
+                let node = children.remove(*from);
+                children.insert(*to, node);
+            }
+            Patch::Replace { path, node } => *node_at(root, path) = node.clone(),
+            Patch::SetText { path, text } => match node_at(root, path) {
+                OwnedVNode::Text(old) | OwnedVNode::Comment(old) => *old = text.clone(),
+                _ => panic!("patch path {:?} doesn't lead to a text node", path),
+            },
+            Patch::SetAttribute { path, name, value } => {
+                set_pair(&mut element_at(root, path).attributes, name, value)
+            }
+            Patch::RemoveAttribute { path, name } => element_at(root, path)
+                .attributes
+                .retain(|(key, _)| key != name),
+            Patch::SetEvent {
+                path,
+                name,
+                handler,
+            } => set_pair(&mut element_at(root, path).events, name, handler),
+            Patch::RemoveEvent { path, name } => {
+                element_at(root, path).events.retain(|(key, _)| key != name)
+            }
+        }
+    }
+}
+
+fn node_at<'a>(root: &'a mut OwnedVNode, path: &[usize]) -> &'a mut OwnedVNode {
+    path.iter().fold(root, |node, index| match node {
+        OwnedVNode::Element(element) if *index < element.children.len() => {
+            &mut element.children[*index]
+        }
+        _ => panic!("patch path {:?} doesn't lead to a node", path),
+    })
+}
+
+fn element_at<'a>(root: &'a mut OwnedVNode, path: &[usize]) -> &'a mut OwnedVElement {
+    match node_at(root, path) {
+        OwnedVNode::Element(element) => element,
+        _ => panic!("patch path {:?} doesn't lead to an element", path),
+    }
+}
+
+fn children_at<'a>(root: &'a mut OwnedVNode, path: &[usize]) -> &'a mut Vec<OwnedVNode> {
+    &mut element_at(root, path).children
+}
+
+fn set_pair<K: Clone + PartialEq>(pairs: &mut Vec<(K, String)>, name: &K, value: &str) {
+    match pairs.iter_mut().find(|(key, _)| key == name) {
+        Some((_, old)) => *old = value.to_string(),
+        None => pairs.push((name.clone(), value.to_string())),
+    }
+}
+
+/// The key identifying a child among its siblings, if it has one.
+fn key(node: &OwnedVNode) -> Option<&str> {
+    match node {
+        OwnedVNode::Element(element) => element.key.as_deref(),
+        _ => None,
+    }
+}
+
+fn diff_node(path: &mut Vec<usize>, old: &OwnedVNode, new: &OwnedVNode, patches: &mut Vec<Patch>) {
+    match (old, new) {
+        (OwnedVNode::Text(old), OwnedVNode::Text(new))
+        | (OwnedVNode::Comment(old), OwnedVNode::Comment(new)) => {
+            if old != new {
+                patches.push(Patch::SetText {
+                    path: path.clone(),
+                    text: new.clone(),
+                });
+            }
+        }
+        (OwnedVNode::Element(old_element), OwnedVNode::Element(new_element))
+            if old_element.name == new_element.name && key(old) == key(new) =>
+        {
+            diff_pairs(
+                &old_element.attributes,
+                &new_element.attributes,
+                |name, value| {
+                    patches.push(match value {
+                        Some(value) => Patch::SetAttribute {
+                            path: path.clone(),
+                            name,
+                            value: value.clone(),
+                        },
+                        None => Patch::RemoveAttribute {
+                            path: path.clone(),
+                            name,
+                        },
+                    })
+                },
+            );
+            diff_pairs(&old_element.events, &new_element.events, |name, handler| {
+                patches.push(match handler {
+                    Some(handler) => Patch::SetEvent {
+                        path: path.clone(),
+                        name,
+                        handler: handler.clone(),
+                    },
+                    None => Patch::RemoveEvent {
+                        path: path.clone(),
+                        name,
+                    },
+                })
+            });
+            diff_children(path, &old_element.children, &new_element.children, patches);
+        }
+        _ => {
+            if old != new {
+                patches.push(Patch::Replace {
+                    path: path.clone(),
+                    node: new.clone(),
+                });
+            }
+        }
+    }
+}
+
+/// Compare two lists of attributes or events, calling `change` with the new
+/// value for each one which is added or changed, and with `None` for each one
+/// which is removed. Empty values count as missing, as they aren't rendered.
+fn diff_pairs<K, F>(old: &[(K, String)], new: &[(K, String)], mut change: F)
+where
+    K: Clone + PartialEq,
+    F: FnMut(K, Option<&String>),
+{
+    fn find<'a, K: PartialEq>(pairs: &'a [(K, String)], name: &K) -> Option<&'a String> {
+        pairs
+            .iter()
+            .find(|(key, value)| key == name && !value.is_empty())
+            .map(|(_, value)| value)
+    }
+    for (name, value) in new.iter().filter(|(_, value)| !value.is_empty()) {
+        if find(old, name) != Some(value) {
+            change(name.clone(), Some(value));
+        }
+    }
+    for (name, _) in old.iter().filter(|(_, value)| !value.is_empty()) {
+        if find(new, name).is_none() {
+            change(name.clone(), None);
+        }
+    }
+}
+
+fn diff_children(
+    path: &mut Vec<usize>,
+    old: &[OwnedVNode],
+    new: &[OwnedVNode],
+    patches: &mut Vec<Patch>,
+) {
+    // Match each new child with an old one: by key if it has one, otherwise
+    // with the next old child without a key.
+    let mut keyed: HashMap<&str, VecDeque<usize>> = HashMap::new();
+    let mut unkeyed = VecDeque::new();
+    for (index, node) in old.iter().enumerate() {
+        match key(node) {
+            Some(key) => keyed.entry(key).or_default().push_back(index),
+            None => unkeyed.push_back(index),
+        }
+    }
+    let matches: Vec<Option<usize>> = new
+        .iter()
+        .map(|node| match key(node) {
+            Some(key) => keyed.get_mut(key).and_then(VecDeque::pop_front),
+            None => unkeyed.pop_front(),
+        })
+        .collect();
+    let mut new_index = vec![None; old.len()];
+    for (index, old_index) in matches.iter().enumerate() {
+        if let Some(old_index) = old_index {
+            new_index[*old_index] = Some(index);
+        }
+    }
+
+    // Remove the old children without a match, last first so the indices of
+    // the others don't change.
+    for (index, _) in new_index
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, n)| n.is_none())
+    {
+        patches.push(Patch::Remove {
+            parent: path.clone(),
+            index,
+        });
+    }
+
+    // What's left is the matched children in their old order, written as
+    // their new indices. The longest run of them which is already in order
+    // stays, and everything else is moved or inserted in front of its new
+    // next sibling, working backwards from the end.
+    let mut current: Vec<usize> = new_index.into_iter().flatten().collect();
+    let mut stays = vec![false; new.len()];
+    for (position, in_order) in longest_increasing(&current).into_iter().enumerate() {
+        if in_order {
+            stays[current[position]] = true;
+        }
+    }
+    let mut next = None;
+    for index in (0..new.len()).rev() {
+        if !stays[index] {
+            let from = current.iter().position(|i| *i == index);
+            if let Some(from) = from {
+                current.remove(from);
+            }
+            let to = match next {
+                Some(next) => current.iter().position(|i| *i == next).unwrap(),
+                None => current.len(),
+            };
+            current.insert(to, index);
+            match from {
+                Some(from) if from != to => patches.push(Patch::Move {
+                    parent: path.clone(),
+                    from,
+                    to,
+                }),
+                Some(_) => {}
+                None => patches.push(Patch::Insert {
+                    parent: path.clone(),
+                    index: to,
+                    node: new[index].clone(),
+                }),
+            }
+        }
+        next = Some(index);
+    }
+
+    for (index, old_index) in matches.into_iter().enumerate() {
+        if let Some(old_index) = old_index {
+            path.push(index);
+            diff_node(path, &old[old_index], &new[index], patches);
+            path.pop();
+        }
+    }
+}
+
+/// Find a longest strictly increasing subsequence of `values`, marking which
+/// positions are in it.
+fn longest_increasing(values: &[usize]) -> Vec<bool> {
+    // `tails[n]` is the position of the smallest value ending an increasing
+    // subsequence of length `n + 1`.
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous = vec![None; values.len()];
+    for (position, value) in values.iter().enumerate() {
+        let length = tails.partition_point(|tail| values[*tail] < *value);
+        if length > 0 {
+            previous[position] = Some(tails[length - 1]);
+        }
+        if length == tails.len() {
+            tails.push(position);
+        } else {
+            tails[length] = position;
+        }
+    }
+    let mut marked = vec![false; values.len()];
+    let mut position = tails.last().copied();
+    while let Some(at) = position {
+        marked[at] = true;
+        position = previous[at];
+    }
+    marked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate as typed_html;
+    use crate::dom::DOMTree;
+    use crate::elements::li;
+    use crate::{html, text};
+
+    /// Sort attributes and events and drop empty ones, as their order and
+    /// empty values don't affect the rendered tree.
+    fn normalise(node: &mut OwnedVNode) {
+        if let OwnedVNode::Element(element) = node {
+            element.attributes.retain(|(_, value)| !value.is_empty());
+            element.attributes.sort();
+            element.events.sort();
+            element.children.iter_mut().for_each(normalise);
+        }
+    }
+
+    /// Diff two trees, and check the patches turn the old one into the new
+    /// one.
+    fn check(mut old: DOMTree<String>, mut new: DOMTree<String>) -> Vec<Patch> {
+        let mut tree = old.owned_vnode();
+        let mut new = new.owned_vnode();
+        let patches = diff(&tree, &new);
+        apply(&mut tree, &patches);
+        normalise(&mut tree);
+        normalise(&mut new);
+        assert_eq!(new, tree);
+        patches
+    }
+
+    fn list(keys: &[&str]) -> DOMTree<String> {
+        let items = keys.iter().map(|key| {
+            let item: Box<li<String>> = html!(<li key=key>{ text!(*key) }</li>);
+            item
+        });
+        html!(<ul>{ items }</ul>)
+    }
+
+    #[test]
+    fn test_identical() {
+        let doc = || html!(<p class="a">"Hello "<b>"Joe"</b><!-- "note" --></p>);
+        assert_eq!(Vec::<Patch>::new(), check(doc(), doc()));
+    }
+
+    #[test]
+    fn test_attributes_and_text() {
+        let patches = check(
+            html!(<p class="a" title="old" onclick="go()">"Hello"</p> : String),
+            html!(<p class="b" lang="en" onclick="stop()">"Goodbye"</p> : String),
+        );
+        assert_eq!(
+            vec![
+                Patch::SetAttribute {
+                    path: vec![],
+                    name: "class".into(),
+                    value: "b".to_string()
+                },
+                Patch::SetAttribute {
+                    path: vec![],
+                    name: "lang".into(),
+                    value: "en".to_string()
+                },
+                Patch::RemoveAttribute {
+                    path: vec![],
+                    name: "title".into()
+                },
+                Patch::SetEvent {
+                    path: vec![],
+                    name: "click",
+                    handler: "stop()".to_string()
+                },
+                Patch::SetText {
+                    path: vec![0],
+                    text: "Goodbye".to_string()
+                },
+            ],
+            patches
+        );
+    }
+
+    #[test]
+    fn test_replace() {
+        let patches = check(
+            html!(<div><p>"Hello"</p>"text"</div>),
+            html!(<div><span>"Hello"</span><b>"text"</b></div>),
+        );
+        assert_eq!(2, patches.len());
+        assert!(patches
+            .iter()
+            .all(|patch| matches!(patch, Patch::Replace { .. })));
+
+        let patches = check(html!(<p>"Hello"</p>), html!(<div>"Hello"</div>));
+        assert_eq!(1, patches.len());
+    }
+
+    #[test]
+    fn test_insert_and_remove() {
+        let patches = check(list(&["a", "b", "c"]), list(&["a", "c", "d"]));
+        assert_eq!(2, patches.len());
+        assert_eq!(
+            Patch::Remove {
+                parent: vec![],
+                index: 1
+            },
+            patches[0]
+        );
+        match &patches[1] {
+            Patch::Insert { index: 2, .. } => {}
+            patch => panic!("unexpected patch {:?}", patch),
+        }
+
+        assert_eq!(3, check(list(&[]), list(&["a", "b", "c"])).len());
+        assert_eq!(3, check(list(&["a", "b", "c"]), list(&[])).len());
+    }
+
+    #[test]
+    fn test_reorder() {
+        let moves = |old: &[&str], new: &[&str]| {
+            let patches = check(list(old), list(new));
+            assert!(patches
+                .iter()
+                .all(|patch| matches!(patch, Patch::Move { .. })));
+            patches.len()
+        };
+        assert_eq!(0, moves(&["a", "b", "c"], &["a", "b", "c"]));
+        assert_eq!(1, moves(&["a", "b", "c"], &["b", "c", "a"]));
+        assert_eq!(1, moves(&["a", "b", "c"], &["c", "a", "b"]));
+        assert_eq!(2, moves(&["a", "b", "c"], &["c", "b", "a"]));
+        assert_eq!(
+            1,
+            moves(&["a", "b", "c", "d", "e"], &["a", "d", "b", "c", "e"])
+        );
+        assert_eq!(
+            2,
+            moves(
+                &["a", "b", "c", "d", "e", "f"],
+                &["b", "a", "c", "d", "f", "e"]
+            )
+        );
+    }
+
+    #[test]
+    fn test_mixed() {
+        check(
+            list(&["a", "b", "c", "d", "e"]),
+            list(&["e", "x", "c", "a", "y", "b"]),
+        );
+        check(
+            html!(<div>"one"<p key="a">"A"</p>"two"<p key="b">"B"</p></div>),
+            html!(<div><p key="b">"B!"</p>"one"<hr/><p key="a">"A"</p></div>),
+        );
+    }
+
+    #[test]
+    fn test_longest_increasing() {
+        assert_eq!(Vec::<bool>::new(), longest_increasing(&[]));
+        assert_eq!(
+            vec![false, true, true, false, true],
+            longest_increasing(&[4, 0, 1, 0, 3])
+        );
+    }
+}
@@ -1,11 +1,18 @@
 //! DOM and virtual DOM types.
 
+use std::borrow::Cow;
 use std::fmt::Display;
 use std::marker::PhantomData;
 
-use crate::elements::{FlowContent, PhrasingContent};
+use crate::elements::{
+    ChildOrder, DescriptionListContent, EmbeddedContent, FlowContent, FormContent, HGroupContent,
+    HeadingContent, InteractiveContent, MapContent, MediaContent, MetadataContent, PhrasingContent,
+    SectioningContent, SelectContent, TableColumnContent, TableContent, Visitor, VisitorMut,
+};
+use crate::escape;
+use crate::parse::{RAW_TEXT_ELEMENTS, VOID_ELEMENTS};
+use crate::select::{self, SelectorError};
 use crate::OutputType;
-use htmlescape::encode_minimal;
 
 /// A boxed DOM tree, as returned from the `html!` macro.
 ///
@@ -43,17 +50,322 @@ pub type DOMTree<T> = Box<dyn Node<T>>;
 pub enum VNode<'a, T: OutputType + 'a> {
     Text(&'a str),
     UnsafeText(&'a str),
+    Comment(&'a str),
     Element(VElement<'a, T>),
 }
 
+impl<'a, T: OutputType + 'a> VNode<'a, T> {
+    /// Find the first element in this tree which matches a CSS selector.
+    ///
+    /// See the [`select`][select] module for the selectors supported.
+    ///
+    /// [select]: ../select/index.html
+    pub fn query_selector(
+        &self,
+        selector: &str,
+    ) -> Result<Option<&VElement<'a, T>>, SelectorError> {
+        select::query_selector(self, selector)
+    }
+
+    /// Find every element in this tree which matches a CSS selector, in
+    /// document order.
+    ///
+    /// See the [`select`][select] module for the selectors supported.
+    ///
+    /// [select]: ../select/index.html
+    pub fn query_selector_all(
+        &self,
+        selector: &str,
+    ) -> Result<Vec<&VElement<'a, T>>, SelectorError> {
+        select::query_selector_all(self, selector)
+    }
+}
+
 /// An untyped representation of an HTML element.
 pub struct VElement<'a, T: OutputType + 'a> {
     pub name: &'static str,
-    pub attributes: Vec<(&'static str, String)>,
+    pub attributes: Vec<(Cow<'static, str>, String)>,
+    /// The key identifying the element among its siblings, if it was given
+    /// one with the `key` pseudo-attribute.
+    pub key: Option<String>,
     pub events: &'a mut T::Events,
     pub children: Vec<VNode<'a, T>>,
 }
 
+/// An owned copy of a [`VNode`][VNode] tree.
+///
+/// A `VNode` borrows from the node it was rendered from, including its event
+/// handlers, so it can't be stored, cloned or compared. An `OwnedVNode` holds
+/// its own copy of everything, minus any event handlers which aren't strings,
+/// and doesn't depend on the output type, which makes it suitable for
+/// caching, diffing and snapshot tests.
+///
+/// It renders to the same HTML as the node it was made from, and
+/// [`into_node()`][into_node] turns it back into a [`Node`][Node].
+///
+/// # Examples
+///
+/// ```
+/// # use typed_html::html;
+/// # use typed_html::dom::{DOMTree, OwnedVNode};
+/// # fn main() {
+/// let mut doc: DOMTree<String> = html!(
+///     <p class="greeting">"Hello "<em>"Joe"</em></p>
+/// );
+/// let owned = doc.owned_vnode();
+/// assert_eq!(doc.to_string(), owned.to_string());
+///
+/// let copy = owned.clone();
+/// let doc: DOMTree<String> = Box::new(copy.into_node());
+/// assert_eq!("<p class=\"greeting\">Hello <em>Joe</em></p>", doc.to_string());
+/// # }
+/// ```
+///
+/// # Serialisation
+///
+/// With the `serde` feature enabled, `OwnedVNode` and [`VNode`][VNode]
+/// implement `Serialize`, and `OwnedVNode` implements `Deserialize`. Each
+/// node is a map with a single key naming its kind:
+///
+/// ```json
+/// {"text": "Hello"}
+/// {"unsafe_text": "<hr/>"}
+/// {"comment": "a comment"}
+/// {"element": {
+///     "name": "p",
+///     "key": "greeting",
+///     "attributes": {"class": "greeting", "http-equiv": "refresh"},
+///     "data": {"data-count": "2"},
+///     "events": {"click": "greet()"},
+///     "children": [{"text": "Hello Joe"}]
+/// }}
+/// ```
+///
+/// Attributes use their HTML names and keep their order. Only `name` is
+/// required: `key` is left out when there isn't one, and `attributes`,
+/// `data`, `events` and `children` when they're empty. Deserialising rejects unknown elements, attributes and
+/// events, and [`parse::from_owned()`][from_owned] turns the result into
+/// typed elements, checking attribute values and content the way
+/// [`parse()`][parse] does.
+///
+/// [VNode]: enum.VNode.html
+/// [Node]: trait.Node.html
+/// [into_node]: #method.into_node
+/// [from_owned]: ../parse/fn.from_owned.html
+/// [parse]: ../parse/fn.parse.html
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum OwnedVNode {
+    Text(String),
+    UnsafeText(String),
+    Comment(String),
+    Element(OwnedVElement),
+}
+
+/// An owned copy of a [`VElement`][VElement].
+///
+/// Event handlers which are strings, as used for string output, are kept
+/// in `events` as pairs of event names, without the `on` prefix, and
+/// handlers. Any others are left out.
+///
+/// [VElement]: struct.VElement.html
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct OwnedVElement {
+    pub name: &'static str,
+    pub attributes: Vec<(Cow<'static, str>, String)>,
+    pub key: Option<String>,
+    pub events: Vec<(&'static str, String)>,
+    pub children: Vec<OwnedVNode>,
+}
+
+impl OwnedVNode {
+    /// Turn the tree back into a [`Node`][Node] for any output type, with
+    /// its string event handlers if the output type supports them.
+    ///
+    /// [Node]: trait.Node.html
+    pub fn into_node<T: OutputType + Send>(self) -> OwnedNode<T> {
+        OwnedNode::new(self)
+    }
+}
+
+impl<'a, 'b, T: OutputType + 'a> From<&'b VNode<'a, T>> for OwnedVNode {
+    fn from(vnode: &'b VNode<'a, T>) -> Self {
+        match vnode {
+            VNode::Text(text) => OwnedVNode::Text(text.to_string()),
+            VNode::UnsafeText(text) => OwnedVNode::UnsafeText(text.to_string()),
+            VNode::Comment(text) => OwnedVNode::Comment(text.to_string()),
+            VNode::Element(element) => OwnedVNode::Element(OwnedVElement {
+                name: element.name,
+                attributes: element.attributes.clone(),
+                key: element.key.clone(),
+                events: T::string_events(element.events),
+                children: element.children.iter().map(OwnedVNode::from).collect(),
+            }),
+        }
+    }
+}
+
+impl Display for OwnedVNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            OwnedVNode::Text(text) => f.write_str(&escape::text(text)),
+            OwnedVNode::UnsafeText(text) => f.write_str(text),
+            OwnedVNode::Comment(text) => write!(f, "<!--{}-->", escape::comment(text)),
+            OwnedVNode::Element(element) => element.fmt(f),
+        }
+    }
+}
+
+impl Display for OwnedVElement {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "<{}", self.name)?;
+        for (key, value) in &self.attributes {
+            let value = if escape::URL_ATTRIBUTES.contains(&key.as_ref()) {
+                escape::url_attribute(value)
+            } else {
+                escape::attribute(value)
+            };
+            if !value.is_empty() {
+                write!(f, " {}=\"{}\"", key, value)?;
+            }
+        }
+        for (name, handler) in &self.events {
+            write!(f, " on{}=\"{}\"", name, escape::attribute(handler))?;
+        }
+        if self.children.is_empty() && VOID_ELEMENTS.contains(&self.name) {
+            return f.write_str("/>");
+        }
+        f.write_str(">")?;
+        for child in &self.children {
+            match child {
+                OwnedVNode::Text(text) if RAW_TEXT_ELEMENTS.contains(&self.name) => {
+                    f.write_str(&escape::raw_text(self.name, text))?
+                }
+                child => child.fmt(f)?,
+            }
+        }
+        write!(f, "</{}>", self.name)
+    }
+}
+
+/// A [`Node`][Node] made from an [`OwnedVNode`][OwnedVNode], with its own
+/// event handlers for each element.
+///
+/// Visitors don't see inside it, as it has no typed elements.
+///
+/// [Node]: trait.Node.html
+/// [OwnedVNode]: enum.OwnedVNode.html
+pub struct OwnedNode<T: OutputType + Send> {
+    vnode: OwnedVNode,
+    /// The events for each element, in document order.
+    events: Vec<T::Events>,
+}
+
+impl<T: OutputType + Send> OwnedNode<T> {
+    /// Construct a node from an owned tree.
+    pub fn new(vnode: OwnedVNode) -> Self {
+        fn collect<T: OutputType + Send>(node: &OwnedVNode, events: &mut Vec<T::Events>) {
+            if let OwnedVNode::Element(element) = node {
+                let mut element_events = T::Events::default();
+                for (name, handler) in &element.events {
+                    T::set_string_event(&mut element_events, name, handler);
+                }
+                events.push(element_events);
+                for child in &element.children {
+                    collect::<T>(child, events);
+                }
+            }
+        }
+        let mut events = Vec::new();
+        collect::<T>(&vnode, &mut events);
+        OwnedNode { vnode, events }
+    }
+
+    /// Get the owned tree this node renders.
+    pub fn owned_vnode(&self) -> &OwnedVNode {
+        &self.vnode
+    }
+}
+
+impl<T: OutputType + Send> From<OwnedVNode> for OwnedNode<T> {
+    fn from(vnode: OwnedVNode) -> Self {
+        OwnedNode::new(vnode)
+    }
+}
+
+impl<T: OutputType + Send> Display for OwnedNode<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        self.vnode.fmt(f)
+    }
+}
+
+impl<T: OutputType + Send> Node<T> for OwnedNode<T> {
+    fn vnode(&'_ mut self) -> VNode<'_, T> {
+        fn borrow<'a, T: OutputType + Send>(
+            node: &'a OwnedVNode,
+            events: &mut std::slice::IterMut<'a, T::Events>,
+        ) -> VNode<'a, T> {
+            match node {
+                OwnedVNode::Text(text) => VNode::Text(text),
+                OwnedVNode::UnsafeText(text) => VNode::UnsafeText(text),
+                OwnedVNode::Comment(text) => VNode::Comment(text),
+                OwnedVNode::Element(element) => VNode::Element(VElement {
+                    name: element.name,
+                    attributes: element.attributes.clone(),
+                    key: element.key.clone(),
+                    events: events
+                        .next()
+                        .expect("OwnedNode has events for every element"),
+                    children: element
+                        .children
+                        .iter()
+                        .map(|child| borrow(child, events))
+                        .collect(),
+                }),
+            }
+        }
+        borrow(&self.vnode, &mut self.events.iter_mut())
+    }
+}
+
+/// The location of an element inside a [`VNode`][VNode] tree.
+///
+/// Each step holds an element name and its position among its parent's
+/// element children, counting from 1 and skipping text and comments as
+/// CSS does. It displays as a CSS selector, eg.
+/// `html > body:nth-child(2) > p:nth-child(1)`.
+///
+/// [VNode]: enum.VNode.html
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ElementPath(pub Vec<(&'static str, usize)>);
+
+impl ElementPath {
+    /// Construct an empty path, pointing at nothing.
+    pub fn new() -> Self {
+        ElementPath(Vec::new())
+    }
+
+    /// Construct the path to the `index`th child of this element, counting
+    /// from 1.
+    pub fn child(&self, name: &'static str, index: usize) -> Self {
+        let mut path = self.clone();
+        path.0.push((name, index));
+        path
+    }
+}
+
+impl Display for ElementPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        for (depth, (name, index)) in self.0.iter().enumerate() {
+            if depth == 0 {
+                f.write_str(name)?;
+            } else {
+                write!(f, " > {}:nth-child({})", name, index)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Trait for rendering a typed HTML node.
 ///
 /// All [HTML elements][elements] implement this, in addition to
@@ -71,6 +383,31 @@ pub trait Node<T: OutputType + Send>: Display + Send {
     ///
     /// [VNode]: enum.VNode.html
     fn vnode(&mut self) -> VNode<T>;
+
+    /// Pass this node to its method on a [`Visitor`][Visitor], and then do
+    /// the same for each of its children.
+    ///
+    /// Nodes which aren't elements, text or comments don't visit anything by
+    /// default.
+    ///
+    /// [Visitor]: ../elements/trait.Visitor.html
+    fn accept(&self, _visitor: &mut dyn Visitor<T>) {}
+
+    /// Pass this node to its method on a [`VisitorMut`][VisitorMut], and then
+    /// do the same for each of its children.
+    ///
+    /// Nodes which aren't elements, text or comments don't visit anything by
+    /// default.
+    ///
+    /// [VisitorMut]: ../elements/trait.VisitorMut.html
+    fn accept_mut(&mut self, _visitor: &mut dyn VisitorMut<T>) {}
+
+    /// Render the node into an [`OwnedVNode`][OwnedVNode] tree.
+    ///
+    /// [OwnedVNode]: enum.OwnedVNode.html
+    fn owned_vnode(&mut self) -> OwnedVNode {
+        OwnedVNode::from(&self.vnode())
+    }
 }
 
 impl<T> IntoIterator for Box<dyn Node<T>>
@@ -87,28 +424,73 @@ where
 
 /// Trait for querying a typed HTML element.
 ///
-/// All [HTML elements][elements] implement this.
+/// All [HTML elements][elements] implement this. The methods which don't
+/// take `self` aren't available on a `dyn Element`, which
+/// [visitors][Visitor] see elements as.
 ///
 /// [elements]: ../elements/index.html
+/// [Visitor]: ../elements/trait.Visitor.html
 pub trait Element<T: OutputType + Send>: Node<T> {
     /// Get the name of the element.
-    fn name() -> &'static str;
+    fn name() -> &'static str
+    where
+        Self: Sized;
+    /// Get the name of the element, for when its type isn't known, as in a
+    /// `dyn Element`.
+    fn element_name(&self) -> &'static str;
     /// Get a list of the attribute names for this element.
     ///
     /// This includes only the typed attributes, not any `data-` attributes
     /// defined on this particular element instance.
     ///
     /// This is probably not useful unless you're the `html!` macro.
-    fn attribute_names() -> &'static [&'static str];
+    fn attribute_names() -> &'static [&'static str]
+    where
+        Self: Sized;
     /// Get a list of the element names of required children for this element.
     ///
     /// This is probably not useful unless you're the `html!` macro.
-    fn required_children() -> &'static [&'static str];
+    fn required_children() -> &'static [&'static str]
+    where
+        Self: Sized;
+    /// Get the pattern the children of this element must follow, if the
+    /// element cares about the order of its children.
+    ///
+    /// See [`ChildOrder`][ChildOrder].
+    ///
+    /// [ChildOrder]: ../elements/enum.ChildOrder.html
+    fn child_order() -> Option<&'static ChildOrder>
+    where
+        Self: Sized;
     /// Get a list of the defined attribute pairs for this element.
     ///
     /// This will convert attribute values into strings and return a vector of
     /// key/value pairs.
-    fn attributes(&self) -> Vec<(&'static str, String)>;
+    fn attributes(&self) -> Vec<(Cow<'static, str>, String)>;
+    /// Set an attribute from its HTML name and string value, parsing the
+    /// value into the attribute's type.
+    ///
+    /// Boolean attributes are set to true whatever their value, as in HTML.
+    /// Any `data-*` attribute is accepted.
+    fn set_attribute(&mut self, name: &str, value: &str) -> Result<(), AttributeError>;
+    /// Check that [`set_attribute()`][set_attribute] would accept an
+    /// attribute, without an element to set it on.
+    ///
+    /// [set_attribute]: #tymethod.set_attribute
+    fn check_attribute(name: &str, value: &str) -> Result<(), AttributeError>
+    where
+        Self: Sized;
+}
+
+/// The reason [`Element::set_attribute()`][set_attribute] failed.
+///
+/// [set_attribute]: trait.Element.html#tymethod.set_attribute
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AttributeError {
+    /// The element has no attribute with this name.
+    Unknown,
+    /// The value couldn't be parsed into the attribute's type.
+    Invalid(String),
 }
 
 /// An HTML text node.
@@ -196,11 +578,15 @@ impl<T: OutputType + Send> TextNode<T> {
     pub fn new<S: Into<String>>(s: S) -> Self {
         TextNode(s.into(), PhantomData)
     }
+
+    pub(crate) fn as_str(&self) -> &str {
+        &self.0
+    }
 }
 
 impl<T: OutputType + Send> Display for TextNode<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-        f.write_str(&encode_minimal(&self.0))
+        f.write_str(&escape::text(&self.0))
     }
 }
 
@@ -208,6 +594,14 @@ impl<T: OutputType + Send> Node<T> for TextNode<T> {
     fn vnode(&'_ mut self) -> VNode<'_, T> {
         VNode::Text(&self.0)
     }
+
+    fn accept(&self, visitor: &mut dyn Visitor<T>) {
+        visitor.visit_text(self);
+    }
+
+    fn accept_mut(&mut self, visitor: &mut dyn VisitorMut<T>) {
+        visitor.visit_text_mut(self);
+    }
 }
 
 impl<T: OutputType + Send> IntoIterator for TextNode<T> {
@@ -253,6 +647,14 @@ impl<T: OutputType + Send> Node<T> for UnsafeTextNode<T> {
     fn vnode(&'_ mut self) -> VNode<'_, T> {
         VNode::UnsafeText(&self.0)
     }
+
+    fn accept(&self, visitor: &mut dyn Visitor<T>) {
+        visitor.visit_unsafe_text(self);
+    }
+
+    fn accept_mut(&mut self, visitor: &mut dyn VisitorMut<T>) {
+        visitor.visit_unsafe_text_mut(self);
+    }
 }
 
 impl<T: OutputType + Send> IntoIterator for UnsafeTextNode<T> {
@@ -275,3 +677,311 @@ impl<T: OutputType + Send> IntoIterator for Box<UnsafeTextNode<T>> {
 
 impl<T: OutputType + Send> FlowContent<T> for UnsafeTextNode<T> {}
 impl<T: OutputType + Send> PhrasingContent<T> for UnsafeTextNode<T> {}
+
+/// An HTML comment node, `<!--...-->`.
+///
+/// The text of a comment can't contain `--` or end with a `-`, as it could
+/// close the comment early, and it can't start with `>` or `->`. Text which
+/// would break these rules is escaped when the node is constructed, by
+/// inserting spaces between the offending dashes, so it's always safe to
+/// render.
+///
+/// # Examples
+///
+/// ```
+/// # use typed_html::dom::CommentNode;
+/// let comment: CommentNode<String> = CommentNode::new("--> <script>");
+/// assert_eq!("<!--- -> <script>-->", comment.to_string());
+/// ```
+pub struct CommentNode<T: OutputType + Send>(String, PhantomData<T>);
+
+/// Macro for creating comment nodes.
+///
+/// Returns a boxed comment node of type `Box<CommentNode>`.
+///
+/// These can be created inside the `html!` macro directly with
+/// `<!-- "..." -->`. This macro is useful for creating comments inside code
+/// blocks.
+///
+/// # Examples
+///
+/// ```no_compile
+/// html!(
+///     <p>{ comment!("Hello Joe!") }</p>
+/// )
+/// ```
+///
+/// ```no_compile
+/// html!(
+///     <p>{ comment!("Hello {}!", "Robert") }</p>
+/// )
+/// ```
+#[macro_export]
+macro_rules! comment {
+    ($t:expr) => {
+        Box::new($crate::dom::CommentNode::new($t))
+    };
+    ($format:tt, $($tail:expr),*) => {
+        Box::new($crate::dom::CommentNode::new(format!($format, $($tail),*)))
+    };
+}
+
+impl<T: OutputType + Send> CommentNode<T> {
+    /// Construct a comment node, escaping its text if it isn't allowed inside
+    /// a comment.
+    ///
+    /// The preferred way to construct a comment node is with the
+    /// [`comment!()`][comment] macro.
+    ///
+    /// [comment]: ../macro.comment.html
+    pub fn new<S: Into<String>>(s: S) -> Self {
+        CommentNode(escape::comment(&s.into()), PhantomData)
+    }
+}
+
+impl<T: OutputType + Send> Display for CommentNode<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "<!--{}-->", self.0)
+    }
+}
+
+impl<T: OutputType + Send> Node<T> for CommentNode<T> {
+    fn vnode(&'_ mut self) -> VNode<'_, T> {
+        VNode::Comment(&self.0)
+    }
+
+    fn accept(&self, visitor: &mut dyn Visitor<T>) {
+        visitor.visit_comment(self);
+    }
+
+    fn accept_mut(&mut self, visitor: &mut dyn VisitorMut<T>) {
+        visitor.visit_comment_mut(self);
+    }
+}
+
+impl<T: OutputType + Send> IntoIterator for CommentNode<T> {
+    type Item = CommentNode<T>;
+    type IntoIter = std::vec::IntoIter<CommentNode<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        vec![self].into_iter()
+    }
+}
+
+impl<T: OutputType + Send> IntoIterator for Box<CommentNode<T>> {
+    type Item = Box<CommentNode<T>>;
+    type IntoIter = std::vec::IntoIter<Box<CommentNode<T>>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        vec![self].into_iter()
+    }
+}
+
+// Comments are allowed anywhere an element's children are, so they belong to
+// every content category.
+impl<T: OutputType + Send> MetadataContent<T> for CommentNode<T> {}
+impl<T: OutputType + Send> FlowContent<T> for CommentNode<T> {}
+impl<T: OutputType + Send> SectioningContent<T> for CommentNode<T> {}
+impl<T: OutputType + Send> HeadingContent<T> for CommentNode<T> {}
+impl<T: OutputType + Send> PhrasingContent<T> for CommentNode<T> {}
+impl<T: OutputType + Send> EmbeddedContent<T> for CommentNode<T> {}
+impl<T: OutputType + Send> InteractiveContent<T> for CommentNode<T> {}
+impl<T: OutputType + Send> FormContent<T> for CommentNode<T> {}
+impl<T: OutputType + Send> DescriptionListContent<T> for CommentNode<T> {}
+impl<T: OutputType + Send> HGroupContent<T> for CommentNode<T> {}
+impl<T: OutputType + Send> MapContent<T> for CommentNode<T> {}
+impl<T: OutputType + Send> MediaContent<T> for CommentNode<T> {}
+impl<T: OutputType + Send> SelectContent<T> for CommentNode<T> {}
+impl<T: OutputType + Send> TableContent<T> for CommentNode<T> {}
+impl<T: OutputType + Send> TableColumnContent<T> for CommentNode<T> {}
+
+// Conversions for pushing text and comment nodes into a `Fragment`.
+macro_rules! impl_into_box_dyn {
+    ($node:ident: $($trait:ident),*) => {
+        $(
+            impl<T: OutputType + Send + 'static> From<Box<$node<T>>> for Box<dyn $trait<T>> {
+                fn from(node: Box<$node<T>>) -> Self {
+                    node
+                }
+            }
+        )*
+    };
+}
+
+impl_into_box_dyn!(TextNode: Node, FlowContent, PhrasingContent);
+impl_into_box_dyn!(UnsafeTextNode: Node, FlowContent, PhrasingContent);
+impl_into_box_dyn!(
+    CommentNode: Node,
+    MetadataContent,
+    FlowContent,
+    SectioningContent,
+    HeadingContent,
+    PhrasingContent,
+    EmbeddedContent,
+    InteractiveContent,
+    FormContent,
+    DescriptionListContent,
+    HGroupContent,
+    MapContent,
+    MediaContent,
+    SelectContent,
+    TableContent,
+    TableColumnContent
+);
+
+/// A list of sibling nodes, as returned from `html!(<>...</>)`.
+///
+/// A fragment isn't a node itself: when it's returned from a code block inside
+/// `html!`, its children are inserted into the parent element in its place.
+/// `C` is the type of the children, which has to be a type the parent accepts,
+/// so a fragment can only be used where each of its children could be.
+///
+/// Nodes are converted into `C` with [`Into`][Into], which is implemented
+/// for boxed elements into each of their content categories, so the type of
+/// `C` can usually be left to inference.
+///
+/// # Examples
+///
+/// ```
+/// # use typed_html::html;
+/// # use typed_html::dom::{DOMTree, Fragment};
+/// # use typed_html::elements::DescriptionListContent;
+/// fn entry(term: &str, description: &str) -> Fragment<String, dyn DescriptionListContent<String>> {
+///     html!(
+///         <>
+///             <dt>{ typed_html::text!(term) }</dt>
+///             <dd>{ typed_html::text!(description) }</dd>
+///         </>
+///     )
+/// }
+///
+/// # fn main() {
+/// let doc: DOMTree<String> = html!(
+///     <dl>{ entry("Joe", "A person") }</dl>
+/// );
+/// assert_eq!("<dl><dt>Joe</dt><dd>A person</dd></dl>", doc.to_string());
+/// # }
+/// ```
+///
+/// [Into]: https://doc.rust-lang.org/std/convert/trait.Into.html
+pub struct Fragment<T: OutputType + Send, C: ?Sized = dyn Node<T>> {
+    pub children: Vec<Box<C>>,
+    output: PhantomData<T>,
+}
+
+impl<T: OutputType + Send, C: ?Sized> Fragment<T, C> {
+    /// Construct an empty fragment.
+    pub fn new() -> Self {
+        Fragment {
+            children: Vec::new(),
+            output: PhantomData,
+        }
+    }
+
+    /// Add a node to the end of the fragment.
+    pub fn push<N: Into<Box<C>>>(&mut self, node: N) {
+        self.children.push(node.into());
+    }
+}
+
+impl<T: OutputType + Send, C: ?Sized> Default for Fragment<T, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: OutputType + Send, C: ?Sized + Display> Display for Fragment<T, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        for child in &self.children {
+            child.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: OutputType + Send, C: ?Sized> IntoIterator for Fragment<T, C> {
+    type Item = Box<C>;
+    type IntoIter = std::vec::IntoIter<Box<C>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.children.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate as typed_html;
+    use crate::html;
+    use std::collections::HashSet;
+
+    fn doc() -> DOMTree<String> {
+        html!(
+            <div id="main" data-count="2">
+                <!-- "list of things" -->
+                <p class="intro" onclick="alert(\"hi\")">"Fish & chips"<br/>"mushy peas"</p>
+                <a href="javascript:alert(1)">"link"</a>
+                <input type="checkbox" checked=true disabled=false/>
+                <script>"if (a < b && c) { x = \"</script>\" }"</script>
+                { unsafe_text!("<hr/>") }
+                <ul></ul>
+            </div>
+            : String
+        )
+    }
+
+    #[test]
+    fn test_owned_renders_the_same() {
+        let mut doc = doc();
+        let owned = doc.owned_vnode();
+        assert_eq!(doc.to_string(), owned.to_string());
+        let node: OwnedNode<String> = owned.clone().into_node();
+        assert_eq!(doc.to_string(), node.to_string());
+    }
+
+    #[test]
+    fn test_owned_round_trip() {
+        let owned = doc().owned_vnode();
+        match &owned {
+            OwnedVNode::Element(div) => match &div.children[1] {
+                OwnedVNode::Element(p) => {
+                    assert_eq!(vec![("click", "alert(\"hi\")".to_string())], p.events)
+                }
+                _ => panic!("expected <p>"),
+            },
+            _ => panic!("expected <div>"),
+        }
+        let mut node: DOMTree<String> = Box::new(owned.clone().into_node());
+        assert_eq!(owned, node.owned_vnode());
+    }
+
+    #[test]
+    fn test_owned_compare() {
+        let first = doc().owned_vnode();
+        let mut second: DOMTree<String> = html!(<div id="main"></div>);
+        let second = second.owned_vnode();
+        assert_eq!(first, first.clone());
+        assert_ne!(first, second);
+        let set: HashSet<OwnedVNode> = vec![first.clone(), second, first].into_iter().collect();
+        assert_eq!(2, set.len());
+
+        fn assert_send_sync<T: Send + Sync + 'static>() {}
+        assert_send_sync::<OwnedVNode>();
+    }
+
+    #[test]
+    fn test_runtime_data_attributes() {
+        let mut element = crate::elements::p::<String>::new();
+        let name = format!("data-{}", "x".repeat(100_000));
+        assert_eq!(Ok(()), element.set_attribute(&name, "1"));
+        assert_eq!(Ok(()), element.set_attribute("data-count", "2"));
+        assert_eq!(Ok(()), element.set_attribute("data-count", "3"));
+        assert_eq!(
+            vec![
+                (Cow::Owned(name), "1".to_string()),
+                ("data-count".into(), "3".to_string())
+            ],
+            element.attributes()
+        );
+    }
+}
@@ -2,11 +2,344 @@
 
 #![allow(non_camel_case_types)]
 
+use std::fmt::{Display, Error, Formatter};
+
 use crate::dom::{Node, TextNode};
 use crate::types::*;
 use crate::OutputType;
 use typed_html_macros::declare_elements;
 
+/// A pattern describing the permitted order of an element's children.
+///
+/// Elements which care about the order of their children declare one of
+/// these, which you can get at through
+/// [`Element::child_order()`][child_order]. The `html!` macro checks it at
+/// compile time whenever an element's children are all known statically.
+///
+/// A pattern matches the element's complete list of children, including any
+/// required children.
+///
+/// # Examples
+///
+/// ```
+/// # use typed_html::dom::Element;
+/// # use typed_html::elements::{table, ChildOrder};
+/// let order = <table<String> as Element<String>>::child_order().unwrap();
+/// assert!(order.check(&["caption", "thead", "tr", "tr"]).is_ok());
+/// assert_eq!(Err(1), order.check(&["tfoot", "caption"]));
+/// ```
+///
+/// [child_order]: ../dom/trait.Element.html#tymethod.child_order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChildOrder {
+    /// Exactly one element with the given name.
+    Element(&'static str),
+    /// Any single node whose name isn't mentioned elsewhere in the pattern.
+    ///
+    /// Text nodes are called [`TEXT_NODE`][TEXT_NODE] for this purpose.
+    ///
+    /// [TEXT_NODE]: constant.TEXT_NODE.html
+    Any,
+    /// Each of the patterns in turn.
+    Sequence(&'static [ChildOrder]),
+    /// Exactly one of the patterns.
+    Choice(&'static [ChildOrder]),
+    /// Zero or one of the pattern.
+    Optional(&'static ChildOrder),
+    /// Zero or more of the pattern.
+    ZeroOrMore(&'static ChildOrder),
+    /// One or more of the pattern.
+    OneOrMore(&'static ChildOrder),
+}
+
+/// The name of text nodes when matching against a [`ChildOrder`][ChildOrder].
+///
+/// [ChildOrder]: enum.ChildOrder.html
+pub const TEXT_NODE: &str = "#text";
+
+// The pattern is matched as a Glushkov automaton: each `Element` and `Any` in
+// it is a state, numbered in the order they're written, and a set of states
+// is a bitset. This is all `const` so that `html!` can check its children at
+// compile time against the same tables `Element::child_order()` returns.
+impl ChildOrder {
+    /// The number of states in the pattern.
+    const fn len(&self) -> usize {
+        match self {
+            ChildOrder::Element(_) | ChildOrder::Any => 1,
+            ChildOrder::Sequence(items) | ChildOrder::Choice(items) => {
+                let mut len = 0;
+                let mut index = 0;
+                while index < items.len() {
+                    len += items[index].len();
+                    index += 1;
+                }
+                len
+            }
+            ChildOrder::Optional(item)
+            | ChildOrder::ZeroOrMore(item)
+            | ChildOrder::OneOrMore(item) => item.len(),
+        }
+    }
+
+    /// Whether the pattern matches an empty list of children.
+    const fn nullable(&self) -> bool {
+        match self {
+            ChildOrder::Element(_) | ChildOrder::Any => false,
+            ChildOrder::Sequence(items) => {
+                let mut index = 0;
+                while index < items.len() {
+                    if !items[index].nullable() {
+                        return false;
+                    }
+                    index += 1;
+                }
+                true
+            }
+            ChildOrder::Choice(items) => {
+                let mut index = 0;
+                while index < items.len() {
+                    if items[index].nullable() {
+                        return true;
+                    }
+                    index += 1;
+                }
+                false
+            }
+            ChildOrder::Optional(_) | ChildOrder::ZeroOrMore(_) => true,
+            ChildOrder::OneOrMore(item) => item.nullable(),
+        }
+    }
+
+    /// The states which can match the first child, for a pattern whose
+    /// states are numbered from `offset`.
+    const fn first(&self, offset: usize) -> u128 {
+        match self {
+            ChildOrder::Element(_) | ChildOrder::Any => 1 << offset,
+            ChildOrder::Sequence(items) | ChildOrder::Choice(items) => {
+                let sequence = matches!(self, ChildOrder::Sequence(_));
+                let mut states = 0;
+                let mut offset = offset;
+                let mut index = 0;
+                while index < items.len() {
+                    states |= items[index].first(offset);
+                    if sequence && !items[index].nullable() {
+                        break;
+                    }
+                    offset += items[index].len();
+                    index += 1;
+                }
+                states
+            }
+            ChildOrder::Optional(item)
+            | ChildOrder::ZeroOrMore(item)
+            | ChildOrder::OneOrMore(item) => item.first(offset),
+        }
+    }
+
+    /// The states which can match the last child.
+    const fn last(&self, offset: usize) -> u128 {
+        match self {
+            ChildOrder::Element(_) | ChildOrder::Any => 1 << offset,
+            ChildOrder::Sequence(items) | ChildOrder::Choice(items) => {
+                let sequence = matches!(self, ChildOrder::Sequence(_));
+                let mut states = 0;
+                let mut offset = offset + self.len();
+                let mut index = items.len();
+                while index > 0 {
+                    index -= 1;
+                    offset -= items[index].len();
+                    states |= items[index].last(offset);
+                    if sequence && !items[index].nullable() {
+                        break;
+                    }
+                }
+                states
+            }
+            ChildOrder::Optional(item)
+            | ChildOrder::ZeroOrMore(item)
+            | ChildOrder::OneOrMore(item) => item.last(offset),
+        }
+    }
+
+    /// The states which can match the child after one matched by `state`.
+    const fn follow(&self, offset: usize, state: usize) -> u128 {
+        match self {
+            ChildOrder::Element(_) | ChildOrder::Any => 0,
+            ChildOrder::Sequence(items) | ChildOrder::Choice(items) => {
+                let mut offset = offset;
+                let mut index = 0;
+                while state >= offset + items[index].len() {
+                    offset += items[index].len();
+                    index += 1;
+                }
+                let mut states = items[index].follow(offset, state);
+                if matches!(self, ChildOrder::Sequence(_))
+                    && items[index].last(offset) & 1 << state != 0
+                {
+                    offset += items[index].len();
+                    index += 1;
+                    while index < items.len() {
+                        states |= items[index].first(offset);
+                        if !items[index].nullable() {
+                            break;
+                        }
+                        offset += items[index].len();
+                        index += 1;
+                    }
+                }
+                states
+            }
+            ChildOrder::Optional(item) => item.follow(offset, state),
+            ChildOrder::ZeroOrMore(item) | ChildOrder::OneOrMore(item) => {
+                let mut states = item.follow(offset, state);
+                if item.last(offset) & 1 << state != 0 {
+                    states |= item.first(offset);
+                }
+                states
+            }
+        }
+    }
+
+    /// The `Element` or `Any` which is `state`.
+    const fn state(&self, offset: usize, state: usize) -> &ChildOrder {
+        match self {
+            ChildOrder::Element(_) | ChildOrder::Any => self,
+            ChildOrder::Sequence(items) | ChildOrder::Choice(items) => {
+                let mut offset = offset;
+                let mut index = 0;
+                while state >= offset + items[index].len() {
+                    offset += items[index].len();
+                    index += 1;
+                }
+                items[index].state(offset, state)
+            }
+            ChildOrder::Optional(item)
+            | ChildOrder::ZeroOrMore(item)
+            | ChildOrder::OneOrMore(item) => item.state(offset, state),
+        }
+    }
+
+    /// Whether the pattern mentions an element called `name`.
+    const fn mentions(&self, name: &str) -> bool {
+        match self {
+            ChildOrder::Element(element) => str_eq(element, name),
+            ChildOrder::Any => false,
+            ChildOrder::Sequence(items) | ChildOrder::Choice(items) => {
+                let mut index = 0;
+                while index < items.len() {
+                    if items[index].mentions(name) {
+                        return true;
+                    }
+                    index += 1;
+                }
+                false
+            }
+            ChildOrder::Optional(item)
+            | ChildOrder::ZeroOrMore(item)
+            | ChildOrder::OneOrMore(item) => item.mentions(name),
+        }
+    }
+
+    /// Match a list of child names against the pattern.
+    ///
+    /// On failure, returns the index of the first child which doesn't fit the
+    /// pattern, or the length of the list if the children are valid as far as
+    /// they go but something required is missing at the end.
+    pub const fn check(&self, children: &[&str]) -> Result<(), usize> {
+        let len = self.len();
+        assert!(len <= 128, "a child order can only name 128 children");
+        // The states which matched the last child.
+        let mut current = 0;
+        let mut index = 0;
+        while index < children.len() {
+            let mut candidates = 0;
+            if index == 0 {
+                candidates = self.first(0);
+            } else {
+                let mut state = 0;
+                while state < len {
+                    if current & 1 << state != 0 {
+                        candidates |= self.follow(0, state);
+                    }
+                    state += 1;
+                }
+            }
+            let mut next = 0;
+            let mut state = 0;
+            while state < len {
+                if candidates & 1 << state != 0 {
+                    let matches = match self.state(0, state) {
+                        ChildOrder::Element(name) => str_eq(name, children[index]),
+                        _ => !self.mentions(children[index]),
+                    };
+                    if matches {
+                        next |= 1 << state;
+                    }
+                }
+                state += 1;
+            }
+            if next == 0 {
+                return Err(index);
+            }
+            current = next;
+            index += 1;
+        }
+        let complete = if children.is_empty() {
+            self.nullable()
+        } else {
+            current & self.last(0) != 0
+        };
+        if complete {
+            Ok(())
+        } else {
+            Err(children.len())
+        }
+    }
+}
+
+const fn str_eq(left: &str, right: &str) -> bool {
+    let (left, right) = (left.as_bytes(), right.as_bytes());
+    if left.len() != right.len() {
+        return false;
+    }
+    let mut index = 0;
+    while index < left.len() {
+        if left[index] != right[index] {
+            return false;
+        }
+        index += 1;
+    }
+    true
+}
+
+fn fmt_list(f: &mut Formatter, items: &[ChildOrder], sep: &str) -> Result<(), Error> {
+    for (index, item) in items.iter().enumerate() {
+        if index > 0 {
+            f.write_str(sep)?;
+        }
+        item.fmt(f)?;
+    }
+    Ok(())
+}
+
+impl Display for ChildOrder {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            ChildOrder::Element(name) => f.write_str(name),
+            ChildOrder::Any => f.write_str("_"),
+            ChildOrder::Sequence(items) => {
+                f.write_str("[")?;
+                fmt_list(f, items, ", ")?;
+                f.write_str("]")
+            }
+            ChildOrder::Choice(items) => fmt_list(f, items, " | "),
+            ChildOrder::Optional(item) => write!(f, "{}?", item),
+            ChildOrder::ZeroOrMore(item) => write!(f, "{}*", item),
+            ChildOrder::OneOrMore(item) => write!(f, "{}+", item),
+        }
+    }
+}
+
 // Marker traits for element content groups
 
 macro_rules! marker_trait {
@@ -54,7 +387,7 @@ declare_elements! {
     html {
         xmlns: Uri,
     } with [head, body];
-    head with [title] MetadataContent;
+    head with [title] MetadataContent ordered [[_*, title, _*, base?, _*] | [_*, base, _*, title, _*]];
     body with FlowContent;
 
     // Metadata
@@ -146,10 +479,10 @@ declare_elements! {
     } in [FlowContent, PhrasingContent] with FlowContent;
     details {
         open: Bool,
-    } in [FlowContent, SectioningContent, InteractiveContent] with [summary] FlowContent;
+    } in [FlowContent, SectioningContent, InteractiveContent] with [summary] FlowContent ordered [summary, _*];
     dfn in [FlowContent, PhrasingContent] with PhrasingContent;
     div in [FlowContent] with FlowContent;
-    dl in [FlowContent] with DescriptionListContent;
+    dl in [FlowContent] with DescriptionListContent ordered [[dt+, dd+]*];
     em in [FlowContent, PhrasingContent] with PhrasingContent;
     embed {
         height: usize,
@@ -187,11 +520,12 @@ declare_elements! {
         allowfullscreen: Bool,
         allowpaymentrequest: Bool,
         height: usize,
+        loading: ImageLoading,
         name: Id,
         referrerpolicy: ReferrerPolicy,
         sandbox: SpacedSet<Sandbox>,
         src: Uri,
-        srcdoc: Uri,
+        srcdoc: String,
         width: usize,
     } in [FlowContent, PhrasingContent, EmbeddedContent, InteractiveContent] with FlowContent;
     img {
@@ -200,6 +534,7 @@ declare_elements! {
         decoding: ImageDecoding,
         height: usize,
         ismap: Bool,
+        loading: ImageLoading,
         sizes: SpacedList<String>, // FIXME it's not really just a string
         src: Uri,
         srcset: String, // FIXME this is much more complicated
@@ -326,7 +661,7 @@ declare_elements! {
     strong in [FlowContent, PhrasingContent] with PhrasingContent;
     sub in [FlowContent, PhrasingContent] with PhrasingContent;
     sup in [FlowContent, PhrasingContent] with PhrasingContent;
-    table in [FlowContent] with TableContent;
+    table in [FlowContent] with TableContent ordered [caption?, colgroup*, thead?, tbody* | tr+, tfoot?];
     template in [MetadataContent, FlowContent, PhrasingContent, TableColumnContent] with Node;
     textarea {
         autocomplete: OnOff,
@@ -459,3 +794,235 @@ fn test_data_attributes() {
 
     assert_eq!("<div data-id=\"1234\">Boo!</div>", frag.to_string());
 }
+
+#[test]
+fn test_attribute_value_syntax() {
+    use crate as typed_html;
+    use crate::dom::DOMTree;
+    use crate::html;
+    use crate::types::ValueSyntax;
+
+    let syntax = ValueSyntax::of(|attrs: &Attrs_input| &attrs.r#type);
+    assert!(syntax.matches(&["checkbox"]));
+    assert!(!syntax.matches(&["check"]));
+    assert!(syntax.matches(&["check", ""]));
+    assert!(syntax.matches(&["", "time", ""]));
+    assert!(!syntax.matches(&["date", "local", "time"]));
+    assert!(!syntax.matches(&["x", ""]));
+
+    let syntax = ValueSyntax::of(|attrs: &Attrs_input| &attrs.tabindex);
+    assert!(syntax.matches(&["1", ""]));
+    assert!(!syntax.matches(&["-", ""]));
+    assert!(!syntax.matches(&["", "px"]));
+
+    let kind = "box";
+    let n = 2;
+    let doc: DOMTree<String> = html!(<input type=f!"check{kind}" tabindex=f!"1{n}"/>);
+    assert_eq!(
+        "<input tabindex=\"12\" type=\"checkbox\"/>",
+        doc.to_string()
+    );
+}
+
+#[test]
+fn test_child_order() {
+    use crate::dom::Element;
+
+    let order = <dl<String> as Element<String>>::child_order().unwrap();
+    assert_eq!(Ok(()), order.check(&[]));
+    assert_eq!(Ok(()), order.check(&["dt", "dt", "dd", "dt", "dd", "dd"]));
+    assert_eq!(Err(0), order.check(&["dd"]));
+    assert_eq!(Err(1), order.check(&["dt"]));
+
+    let order = <head<String> as Element<String>>::child_order().unwrap();
+    assert_eq!(Ok(()), order.check(&["title", "meta", "base", "link"]));
+    assert_eq!(Err(3), order.check(&["title", "base", "meta", "base"]));
+    assert_eq!(Err(2), order.check(&["title", "meta", "title"]));
+    assert_eq!(Ok(()), order.check(&["meta", "title"]));
+    assert_eq!(Ok(()), order.check(&["base", "meta", "title", "link"]));
+    assert_eq!(Err(1), order.check(&["meta"]));
+    assert_eq!(Err(3), order.check(&["meta", "base", "title", "base"]));
+
+    // `html!` checks the same tables at compile time.
+    const CHECKED: Result<(), usize> = match child_orders::table {
+        Some(order) => order.check(&["caption", "tr", "tfoot", "tr"]),
+        None => Ok(()),
+    };
+    assert_eq!(Err(3), CHECKED);
+
+    // The usual way to start a document, with the charset first. The title
+    // is a required child, but it stays where it's written.
+    {
+        use crate as typed_html;
+        use crate::{dom::DOMTree, html};
+
+        let doc: DOMTree<String> = html!(
+            <head>
+                <meta charset="utf-8"/>
+                <title>"Hello"</title>
+                <base href="/"/>
+            </head>
+        );
+        assert_eq!(
+            "<head><meta charset=\"utf-8\"/><title>Hello</title><base href=\"/\"/></head>",
+            doc.to_string()
+        );
+    }
+
+    assert_eq!(None, <div<String> as Element<String>>::child_order());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate as typed_html;
+    use crate::dom::{CommentNode, DOMTree, Element, UnsafeTextNode, VNode};
+    use crate::{html, unsafe_text};
+    use std::borrow::Cow;
+
+    /// Records every node it sees, elements by name.
+    #[derive(Default)]
+    struct Trace(Vec<String>);
+
+    impl<T: OutputType + Send> Visitor<T> for Trace {
+        fn visit_element(&mut self, element: &dyn Element<T>) {
+            self.0.push(element.element_name().to_string());
+        }
+
+        fn visit_text(&mut self, text: &TextNode<T>) {
+            self.0.push(format!("{:?}", text.to_string()));
+        }
+
+        fn visit_unsafe_text(&mut self, text: &UnsafeTextNode<T>) {
+            self.0.push(format!("unsafe {:?}", text.to_string()));
+        }
+
+        fn visit_comment(&mut self, comment: &CommentNode<T>) {
+            self.0.push(comment.to_string());
+        }
+
+        fn visit_title(&mut self, element: &title<T>) {
+            let children = element.children.len();
+            self.0.push(format!("title with {} children", children));
+        }
+    }
+
+    #[test]
+    fn test_visit_order() {
+        let doc: DOMTree<String> = html!(
+            <html>
+                <head><title>"Hello"</title></head>
+                <body>
+                    <p>"One"<!-- "two" --><em>"three"</em></p>
+                    { unsafe_text!("<hr/>") }
+                </body>
+            </html>
+        );
+        let mut trace = Trace::default();
+        doc.accept(&mut trace);
+        assert_eq!(
+            vec![
+                "html",
+                "head",
+                "title with 1 children",
+                "\"Hello\"",
+                "body",
+                "p",
+                "\"One\"",
+                "<!--two-->",
+                "em",
+                "\"three\"",
+                "unsafe \"<hr/>\"",
+            ],
+            trace.0
+        );
+    }
+
+    /// Adds a class to every element, and a `loading` attribute to images.
+    struct Rewrite;
+
+    impl<T: OutputType + Send> VisitorMut<T> for Rewrite {
+        fn visit_element_mut(&mut self, element: &mut dyn Element<T>) {
+            element.set_attribute("class", "seen").unwrap();
+        }
+
+        fn visit_img_mut(&mut self, element: &mut img<T>) {
+            element.attrs.loading = Some(ImageLoading::Lazy);
+            self.visit_element_mut(element);
+        }
+
+        fn visit_text_mut(&mut self, text: &mut TextNode<T>) {
+            *text = TextNode::new(text.to_string().to_uppercase());
+        }
+    }
+
+    #[test]
+    fn test_visit_mut() {
+        let mut doc: DOMTree<String> = html!(
+            <div>
+                <p>"Look:"</p>
+                <img src="cat.jpg" alt="A cat"/>
+            </div>
+        );
+        doc.accept_mut(&mut Rewrite);
+        assert_eq!(
+            concat!(
+                "<div class=\"seen\">",
+                "<p class=\"seen\">LOOK:</p>",
+                "<img alt=\"A cat\" class=\"seen\" loading=\"lazy\" src=\"cat.jpg\"/>",
+                "</div>"
+            ),
+            doc.to_string()
+        );
+    }
+
+    #[test]
+    fn test_dashed_attribute_names() {
+        let mut doc: DOMTree<String> = html!(<meta http-equiv="refresh" content="5"/>);
+        assert_eq!(
+            "<meta content=\"5\" http-equiv=\"refresh\"/>",
+            doc.to_string()
+        );
+        match doc.vnode() {
+            VNode::Element(element) => assert_eq!(
+                vec![
+                    (Cow::from("content"), "5".to_string()),
+                    (Cow::from("http-equiv"), "refresh".to_string())
+                ],
+                element.attributes
+            ),
+            _ => panic!("expected an element"),
+        }
+    }
+
+    #[test]
+    fn test_key() {
+        let missing: Option<u32> = None;
+        let mut doc: DOMTree<String> = html!(
+            <ul>
+                <li key="first">"one"</li>
+                <li key=?missing>"two"</li>
+                <li key=3>"three"</li>
+            </ul>
+        );
+        assert_eq!(
+            "<ul><li>one</li><li>two</li><li>three</li></ul>",
+            doc.to_string()
+        );
+        let keys: Vec<Option<String>> = match doc.vnode() {
+            VNode::Element(list) => list
+                .children
+                .into_iter()
+                .map(|child| match child {
+                    VNode::Element(item) => item.key,
+                    _ => panic!("expected an element"),
+                })
+                .collect(),
+            _ => panic!("expected an element"),
+        };
+        assert_eq!(
+            vec![Some("first".to_string()), None, Some("3".to_string())],
+            keys
+        );
+    }
+}
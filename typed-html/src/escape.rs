@@ -0,0 +1,519 @@
+//! Escaping text for the context it's rendered in.
+//!
+//! Every string which goes into rendered HTML passes through one of the
+//! functions here, chosen by the [`Context`][Context] it's rendered in: the
+//! text of an element, an attribute value, an attribute value which is a URL,
+//! the raw text content of `<script>` and `<style>`, or a comment.
+//!
+//! The content of `<script>` and `<style>` is raw text: the HTML parser
+//! doesn't decode character references inside it, so it can't be escaped
+//! like other text, or `a && b` would turn into `a &amp;&amp; b`. All it
+//! has to avoid is anything which would end the element early, which
+//! [`raw_text()`][raw_text] takes care of, and [`json()`][json] prepares
+//! JSON for embedding in a `<script>` as inline data.
+//!
+//! [Context]: enum.Context.html
+//! [raw_text]: fn.raw_text.html
+//! [json]: fn.json.html
+
+/// The contexts a string can be rendered in, which each need their own
+/// escaping.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Context {
+    /// The text content of an element, escaped with [`text()`][text].
+    ///
+    /// [text]: fn.text.html
+    Text,
+    /// A double quoted attribute value, escaped with
+    /// [`attribute()`][attribute].
+    ///
+    /// [attribute]: fn.attribute.html
+    Attribute,
+    /// A double quoted attribute value which is a URL, escaped with
+    /// [`url_attribute()`][url_attribute].
+    ///
+    /// [url_attribute]: fn.url_attribute.html
+    UrlAttribute,
+    /// The content of the raw text element with this name, `script` or
+    /// `style`, escaped with [`raw_text()`][raw_text].
+    ///
+    /// [raw_text]: fn.raw_text.html
+    RawText(&'static str),
+    /// The text of a comment, escaped with [`comment()`][comment].
+    ///
+    /// [comment]: fn.comment.html
+    Comment,
+}
+
+impl Context {
+    /// Escape a string for this context.
+    pub fn escape(self, input: &str) -> String {
+        match self {
+            Context::Text => text(input),
+            Context::Attribute => attribute(input),
+            Context::UrlAttribute => url_attribute(input),
+            Context::RawText(element) => raw_text(element, input),
+            Context::Comment => comment(input),
+        }
+    }
+}
+
+fn replace_chars(input: &str, entity: fn(char) -> Option<&'static str>) -> String {
+    let mut out = String::with_capacity(input.len());
+    for c in input.chars() {
+        match entity(c) {
+            Some(entity) => out.push_str(entity),
+            None => out.push(c),
+        }
+    }
+    out
+}
+
+fn text_entity(c: char) -> Option<&'static str> {
+    match c {
+        '&' => Some("&amp;"),
+        '<' => Some("&lt;"),
+        '>' => Some("&gt;"),
+        '"' => Some("&quot;"),
+        '\'' => Some("&#39;"),
+        _ => None,
+    }
+}
+
+fn attribute_entity(c: char) -> Option<&'static str> {
+    match c {
+        // Old versions of IE treat a backtick as a quote.
+        '`' => Some("&#96;"),
+        c => text_entity(c),
+    }
+}
+
+/// Escape the text content of an element.
+///
+/// `&`, `<`, `>` and quotes are replaced with character references.
+///
+/// # Examples
+///
+/// ```
+/// # use typed_html::escape::text;
+/// assert_eq!("&lt;b&gt;Fish &amp; chips&lt;/b&gt;", text("<b>Fish & chips</b>"));
+/// ```
+pub fn text(input: &str) -> String {
+    replace_chars(input, text_entity)
+}
+
+/// Escape a double quoted attribute value.
+///
+/// This escapes the same characters as [`text()`][text], and backticks.
+///
+/// [text]: fn.text.html
+pub fn attribute(input: &str) -> String {
+    replace_chars(input, attribute_entity)
+}
+
+/// The URL which [`url_attribute()`][url_attribute] puts in place of a URL
+/// which would run a script.
+///
+/// [url_attribute]: fn.url_attribute.html
+pub const UNSAFE_URL: &str = "about:invalid#unsafe-url";
+
+/// Escape a double quoted attribute value which is a URL.
+///
+/// A `javascript:` or `vbscript:` URL, which would run a script when it's
+/// followed, is replaced with [`UNSAFE_URL`][UNSAFE_URL]. The scheme is
+/// recognised the way browsers do, ignoring case, whitespace and control
+/// characters. Anything else is escaped like [`attribute()`][attribute].
+///
+/// # Examples
+///
+/// ```
+/// # use typed_html::escape::url_attribute;
+/// assert_eq!("/search?q=fish&amp;chips", url_attribute("/search?q=fish&chips"));
+/// assert_eq!("about:invalid#unsafe-url", url_attribute("JavaScript:alert(1)"));
+/// ```
+///
+/// [UNSAFE_URL]: constant.UNSAFE_URL.html
+/// [attribute]: fn.attribute.html
+pub fn url_attribute(input: &str) -> String {
+    match url_scheme(input).as_deref() {
+        Some("javascript") | Some("vbscript") => UNSAFE_URL.to_string(),
+        _ => attribute(input),
+    }
+}
+
+/// Attributes whose values are URLs, which are escaped with
+/// [`url_attribute()`][url_attribute].
+///
+/// [url_attribute]: fn.url_attribute.html
+pub(crate) static URL_ATTRIBUTES: &[&str] = &[
+    "action",
+    "cite",
+    "data",
+    "formaction",
+    "href",
+    "manifest",
+    "poster",
+    "src",
+    "xmlns",
+];
+
+/// Find the scheme of a URL, in lower case, or `None` if it's relative.
+///
+/// Browsers ignore whitespace and control characters inside a scheme, eg.
+/// `java\tscript:`, so they're ignored here too.
+pub(crate) fn url_scheme(url: &str) -> Option<String> {
+    let url: String = url
+        .chars()
+        .filter(|c| !(c.is_ascii_whitespace() || c.is_ascii_control()))
+        .collect();
+    let end = url.find([':', '/', '?', '#'])?;
+    if url[end..].starts_with(':') {
+        Some(url[..end].to_ascii_lowercase())
+    } else {
+        None
+    }
+}
+
+/// Escape the text of a comment.
+///
+/// A comment can't contain `--` or end with a `-`, as it could close the
+/// comment early, and it can't start with `>` or `->`. Any consecutive
+/// dashes are separated with spaces, and the text is padded with spaces
+/// where it would start or end with the others.
+pub fn comment(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    if input.starts_with('>') || input.starts_with("->") {
+        out.push(' ');
+    }
+    for c in input.chars() {
+        if c == '-' && out.ends_with('-') {
+            out.push(' ');
+        }
+        out.push(c);
+    }
+    if out.ends_with('-') {
+        out.push(' ');
+    }
+    out
+}
+
+/// Make text safe to render as the content of the raw text element
+/// `element`, which is `script` or `style`.
+///
+/// Any `</script` or `</style` closing the element, in any case, is written
+/// as `<\/script` or `<\/style`, and inside `<script>`, `<!--` is written as
+/// `<\!--`, as it can hide the closing tag from the parser. Inside a string,
+/// which is the only place either should appear, the backslash escapes mean
+/// the same in JavaScript and CSS. Everything else is left as it is.
+///
+/// # Examples
+///
+/// ```
+/// # use typed_html::escape::raw_text;
+/// assert_eq!("if (a && b) {}", raw_text("script", "if (a && b) {}"));
+/// assert_eq!(
+///     r#"var s = "<\/script><\!-- ";"#,
+///     raw_text("script", r#"var s = "</script><!-- ";"#)
+/// );
+/// ```
+pub fn raw_text(element: &str, text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find('<') {
+        out.push_str(&rest[..=index]);
+        rest = &rest[index + 1..];
+        let closes = rest.starts_with('/')
+            && rest.len() > element.len()
+            && rest.as_bytes()[1..=element.len()].eq_ignore_ascii_case(element.as_bytes());
+        if closes || (element == "script" && rest.starts_with("!--")) {
+            out.push('\\');
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Escape JSON for embedding in a `<script>` element, eg. as data for a
+/// script to read with `JSON.parse()`.
+///
+/// `<`, `>` and `&` are written as `\u003c`, `\u003e` and `\u0026`, which
+/// mean the same inside a JSON string, the only place they can appear, so
+/// the result can't close the element or start a comment. U+2028 and U+2029
+/// are escaped too, as older JavaScript doesn't allow them in strings.
+///
+/// # Examples
+///
+/// ```
+/// # use typed_html::{html, text};
+/// # use typed_html::dom::DOMTree;
+/// # use typed_html::escape::json;
+/// # fn main() {
+/// let data = r#"{"name": "</script><script>alert(1)</script>"}"#;
+/// let doc: DOMTree<String> = html!(
+///     <script type="application/json">{ text!(json(data)) }</script>
+/// );
+/// assert_eq!(
+///     r#"<script type="application/json">{"name": "\u003c/script\u003e\u003cscript\u003ealert(1)\u003c/script\u003e"}</script>"#,
+///     doc.to_string()
+/// );
+/// # }
+/// ```
+pub fn json(json: &str) -> String {
+    let mut out = String::with_capacity(json.len());
+    for c in json.chars() {
+        match c {
+            '<' => out.push_str("\\u003c"),
+            '>' => out.push_str("\\u003e"),
+            '&' => out.push_str("\\u0026"),
+            '\u{2028}' => out.push_str("\\u2028"),
+            '\u{2029}' => out.push_str("\\u2029"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate as typed_html;
+    use crate::dom::{DOMTree, OwnedVNode};
+    use crate::{comment, html, text};
+
+    #[test]
+    fn test_contexts() {
+        let input = "<a href='x' title=\"`y`\">&";
+        assert_eq!(
+            "&lt;a href=&#39;x&#39; title=&quot;`y`&quot;&gt;&amp;",
+            Context::Text.escape(input)
+        );
+        assert_eq!(
+            "&lt;a href=&#39;x&#39; title=&quot;&#96;y&#96;&quot;&gt;&amp;",
+            Context::Attribute.escape(input)
+        );
+        assert_eq!(attribute(input), Context::UrlAttribute.escape(input));
+        assert_eq!(input, Context::RawText("style").escape(input));
+        assert_eq!("- - ->", Context::Comment.escape("--->"));
+        assert_eq!(" ->", Context::Comment.escape("->"));
+        assert_eq!(" > - ", Context::Comment.escape("> -"));
+    }
+
+    #[test]
+    fn test_url_scheme() {
+        assert_eq!(Some("https".to_string()), url_scheme("HTTPS://example.com"));
+        assert_eq!(
+            Some("javascript".to_string()),
+            url_scheme(" java\tscript:x")
+        );
+        assert_eq!(None, url_scheme("/a:b"));
+        assert_eq!(None, url_scheme("?a:b"));
+        assert_eq!(None, url_scheme("#a:b"));
+        assert_eq!(None, url_scheme("no-colon"));
+    }
+
+    #[test]
+    fn test_xss_text() {
+        let vectors = [
+            "<script>alert(1)</script>",
+            "<img src=x onerror=alert(1)>",
+            "</p><script>alert(1)</script>",
+            "&lt;script&gt;",
+        ];
+        for vector in &vectors {
+            let doc: DOMTree<String> = html!(<p>{ text!(*vector) }</p>);
+            let rendered = doc.to_string();
+            assert_eq!(format!("<p>{}</p>", text(vector)), rendered);
+            assert_eq!(2, rendered.matches('<').count(), "{}", rendered);
+        }
+    }
+
+    #[test]
+    fn test_xss_attributes() {
+        let vectors = [
+            "\" onmouseover=\"alert(1)",
+            "' onmouseover='alert(1)",
+            "` onmouseover=`alert(1)",
+            "\"><script>alert(1)</script>",
+            "&quot; onmouseover=&quot;alert(1)",
+        ];
+        for &vector in &vectors {
+            let doc: DOMTree<String> = html!(
+                <div title=vector data-payload=vector onclick=vector></div>
+                : String
+            );
+            let rendered = doc.to_string();
+            let value = attribute(vector);
+            assert!(!value.contains(['"', '\'', '`', '<']));
+            for name in &["title", "data-payload", "onclick"] {
+                assert!(
+                    rendered.contains(&format!(" {}=\"{}\"", name, value)),
+                    "{} not escaped in {}",
+                    name,
+                    rendered
+                );
+            }
+            assert_eq!(6, rendered.matches('"').count(), "{}", rendered);
+        }
+    }
+
+    #[test]
+    fn test_xss_urls() {
+        let vectors = [
+            "javascript:alert(1)",
+            "JaVaScRiPt:alert(1)",
+            "java\tscript:alert(1)",
+            "java\nscript:alert(1)",
+            " javascript:alert(1)",
+            "\u{1}javascript:alert(1)",
+            "vbscript:msgbox(1)",
+        ];
+        for &vector in &vectors {
+            let doc: DOMTree<String> = html!(
+                <div>
+                    <a href=vector>"link"</a>
+                    <img src=vector alt="img"/>
+                    <form action=vector></form>
+                </div>
+            );
+            assert_eq!(
+                format!(
+                    concat!(
+                        "<div>",
+                        "<a href=\"{0}\">link</a>",
+                        "<img alt=\"img\" src=\"{0}\"/>",
+                        "<form action=\"{0}\"></form>",
+                        "</div>"
+                    ),
+                    UNSAFE_URL
+                ),
+                doc.to_string()
+            );
+        }
+        let doc: DOMTree<String> = html!(
+            <div>
+                <a href="https://example.com/?a=1&b=\"2\"">"safe"</a>
+                <a href="/javascript:alert(1)">"relative"</a>
+            </div>
+        );
+        assert_eq!(
+            concat!(
+                "<div>",
+                "<a href=\"https://example.com/?a=1&amp;b=&quot;2&quot;\">safe</a>",
+                "<a href=\"/javascript:alert(1)\">relative</a>",
+                "</div>"
+            ),
+            doc.to_string()
+        );
+    }
+
+    #[test]
+    fn test_xss_format() {
+        let name = "<script>alert(1)</script>";
+        let title = "\" onmouseover=\"alert(1)";
+        let doc: DOMTree<String> = html!(<p title=f!"<{title}>">f!"<b>{name}</b>"</p>);
+        assert_eq!(
+            format!(
+                "<p title=\"{}\">{}</p>",
+                attribute(&format!("<{}>", title)),
+                text(&format!("<b>{}</b>", name))
+            ),
+            doc.to_string()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "failed to parse string literal")]
+    fn test_format_parse_failure() {
+        // Only the literal parts are checked at compile time, and these could
+        // be part of a number.
+        let n = 1;
+        let _: DOMTree<String> = html!(<p tabindex=f!"-{n}-"></p>);
+    }
+
+    #[test]
+    fn test_xss_raw_text() {
+        let vectors = [
+            "</script><script>alert(1)</script>",
+            "</SCRIPT ><script>alert(1)</script>",
+            "<!--<script>",
+        ];
+        for vector in &vectors {
+            let doc: DOMTree<String> = html!(<script>{ text!(*vector) }</script>);
+            let rendered = doc.to_string();
+            let content = &rendered["<script>".len()..rendered.len() - "</script>".len()];
+            assert!(
+                !content.to_ascii_lowercase().contains("</script"),
+                "{}",
+                rendered
+            );
+            assert!(!content.contains("<!--"), "{}", rendered);
+        }
+    }
+
+    #[test]
+    fn test_xss_owned_comment() {
+        let vectors = [
+            "--><script>alert(1)</script><!--",
+            "-><script>alert(1)</script>",
+            "><script>alert(1)</script>",
+            "--!><script>alert(1)</script>",
+        ];
+        for vector in &vectors {
+            let node = OwnedVNode::Comment(vector.to_string());
+            let rendered = node.to_string();
+            let content = &rendered["<!--".len()..rendered.len() - "-->".len()];
+            assert_eq!(comment(vector), content);
+            assert!(!content.contains("--"), "{}", rendered);
+            assert!(!content.starts_with('>'), "{}", rendered);
+        }
+        let mut doc: DOMTree<String> = html!(<p>{ comment!("a--b") }</p>);
+        assert_eq!(doc.to_string(), doc.owned_vnode().to_string());
+    }
+
+    #[test]
+    fn test_raw_text() {
+        assert_eq!("a < b && c > d", raw_text("script", "a < b && c > d"));
+        assert_eq!("<\\/SCRIPT >", raw_text("script", "</SCRIPT >"));
+        assert_eq!("<\\/sCrIpT", raw_text("script", "</sCrIpT"));
+        assert_eq!("</scrip", raw_text("script", "</scrip"));
+        assert_eq!("</style>", raw_text("script", "</style>"));
+        assert_eq!("<\\/style><!--", raw_text("style", "</style><!--"));
+        assert_eq!("<\\!--<script>", raw_text("script", "<!--<script>"));
+        assert_eq!("<<\\/script", raw_text("script", "<</script"));
+        assert_eq!("é</ścript", raw_text("script", "é</ścript"));
+    }
+
+    #[test]
+    fn test_json() {
+        assert_eq!(
+            r#"["\u003c/script\u003e", "\u0026amp;", "\u2028"]"#,
+            json("[\"</script>\", \"&amp;\", \"\u{2028}\"]")
+        );
+    }
+
+    #[test]
+    fn test_render_raw_text() {
+        let doc: DOMTree<String> = html!(
+            <div>
+                <script>"if (a && b < c) { document.write(\"</script>\") }"</script>
+                <p>"a && b"</p>
+            </div>
+        );
+        assert_eq!(
+            concat!(
+                "<div>",
+                "<script>if (a && b < c) { document.write(\"<\\/script>\") }</script>",
+                "<p>a &amp;&amp; b</p>",
+                "</div>"
+            ),
+            doc.to_string()
+        );
+        let doc: DOMTree<String> = html!(
+            <style>"p > a::before { content: \"</style>\" }"</style>
+        );
+        assert_eq!(
+            "<style>p > a::before { content: \"<\\/style>\" }</style>",
+            doc.to_string()
+        );
+    }
+}
@@ -1,11 +1,10 @@
 //! Event handlers.
 
 use crate::OutputType;
-use htmlescape::encode_attribute;
 use std::fmt::{Display, Error, Formatter};
 
 /// Trait for event handlers.
-pub trait EventHandler<T: OutputType + Send, E: Send> {
+pub trait EventHandler<T: OutputType + Send, E> {
     /// Build a callback function from this event handler.
     ///
     /// Returns `None` is this event handler can't be used to build a callback
@@ -25,6 +24,9 @@ pub trait EventHandler<T: OutputType + Send, E: Send> {
 
 macro_rules! declare_events_struct {
     ($($name:ident,)*) => {
+        /// The names of all events, without the `on` prefix.
+        pub static EVENT_NAMES: &[&str] = &[$(stringify!($name),)*];
+
         pub struct Events<T> where T: Send {
             $(
                 pub $name: Option<T>,
@@ -42,6 +44,20 @@ macro_rules! declare_events_struct {
                 vec.into_iter()
             }
 
+            /// Set the handler for an event by its name, without the `on`
+            /// prefix.
+            ///
+            /// Returns false if there's no event with this name.
+            pub fn set(&mut self, name: &str, value: T) -> bool {
+                match name {
+                    $(
+                        stringify!($name) => self.$name = Some(value),
+                    )*
+                    _ => return false,
+                }
+                true
+            }
+
             pub fn iter_mut(&mut self) -> impl Iterator<Item = (&'static str, &mut T)> {
                 let mut vec = Vec::new();
                 $(
@@ -82,7 +98,7 @@ macro_rules! declare_events_struct {
             fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
                 $(
                     if let Some(ref value) = self.$name {
-                        let attribute = encode_attribute(&value.to_string());
+                        let attribute = crate::escape::attribute(&value.to_string());
                         write!(f, " on{}=\"{}\"", stringify!($name), attribute)?;
                     }
                 )*
@@ -0,0 +1,619 @@
+//! Hydration of server rendered DOM trees.
+//!
+//! A page rendered to a string on the server can be brought to life on the
+//! client without throwing its DOM away and building it again.
+//! [`hydrate()`][hydrate] walks an existing DOM tree alongside the
+//! [`VNode`][VNode] tree it should have been rendered from, passes each
+//! element's event handlers to a callback along with the matching DOM node,
+//! and reports every place where the two trees differ as a
+//! [`Mismatch`][Mismatch], rather than replacing anything.
+//!
+//! The output backends wrap this up as `Stdweb::hydrate()` and
+//! `WebSys::hydrate()`, which attach the handlers as listeners. Any other DOM
+//! can be walked by implementing [`HydrateNode`][HydrateNode] for its nodes.
+//! It's implemented for `&OwnedVNode`, which can stand in for the server's
+//! output in tests.
+//!
+//! The trees are compared the way the markup is rendered: adjacent text nodes
+//! are treated as one, empty text and attributes with empty values are left
+//! out, and unsafe URLs are expected to have been replaced. Unsafe text can
+//! render as anything, so it matches every DOM node up to the one matching
+//! the node after it. Attributes starting with `on` in the DOM are taken to
+//! be event handlers rendered on the server, and aren't reported.
+//!
+//! # Examples
+//!
+//! ```
+//! # use typed_html::html;
+//! # use typed_html::dom::{DOMTree, Node};
+//! # use typed_html::hydrate::hydrate;
+//! # fn main() {
+//! let mut server: DOMTree<String> = html!(
+//!     <div class="greeting">
+//!         <p>"Hello Joe"</p>
+//!     </div>
+//! );
+//! let mut client: DOMTree<String> = html!(
+//!     <div class="greeting">
+//!         <p>"Hello Mike"</p>
+//!     </div>
+//! );
+//! let dom = server.owned_vnode();
+//! let mut elements = Vec::new();
+//! let mismatches = hydrate(&&dom, client.vnode(), |node, _events| {
+//!     elements.push(node.to_string())
+//! });
+//! assert_eq!(2, elements.len());
+//! assert_eq!(
+//!     "div > p:nth-child(1): child 1 should be text \"Hello Mike\", found text \"Hello Joe\"",
+//!     mismatches[0].to_string()
+//! );
+//! # }
+//! ```
+//!
+//! [hydrate]: fn.hydrate.html
+//! [VNode]: ../dom/enum.VNode.html
+//! [Mismatch]: struct.Mismatch.html
+//! [HydrateNode]: trait.HydrateNode.html
+
+use std::fmt::{Display, Error, Formatter};
+
+use crate::dom::{ElementPath, OwnedVNode, VElement, VNode};
+use crate::escape;
+use crate::parse::RAW_TEXT_ELEMENTS;
+use crate::OutputType;
+
+/// What a DOM node is, as far as [`hydrate()`][hydrate] is concerned.
+///
+/// [hydrate]: fn.hydrate.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NodeKind {
+    /// An element, with its tag name, in any case.
+    Element(String),
+    Text(String),
+    Comment(String),
+    /// Any other node, such as a processing instruction.
+    Other,
+}
+
+impl NodeKind {
+    /// Whether a node of this kind could be the node `expected`, ignoring
+    /// its text.
+    fn fits(&self, expected: &NodeKind) -> bool {
+        match (self, expected) {
+            (NodeKind::Element(name), NodeKind::Element(expected)) => {
+                name.eq_ignore_ascii_case(expected)
+            }
+            (NodeKind::Text(_), NodeKind::Text(_)) => true,
+            (NodeKind::Comment(_), NodeKind::Comment(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Display for NodeKind {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            NodeKind::Element(name) => write!(f, "<{}>", name.to_ascii_lowercase()),
+            NodeKind::Text(text) => write!(f, "text {:?}", text),
+            NodeKind::Comment(text) => write!(f, "comment {:?}", text),
+            NodeKind::Other => f.write_str("another node"),
+        }
+    }
+}
+
+/// A node in an existing DOM tree, which can be hydrated.
+pub trait HydrateNode: Sized {
+    /// What the node is, with its tag name or its text.
+    fn kind(&self) -> NodeKind;
+
+    /// The node's children, in document order.
+    fn children(&self) -> Vec<Self>;
+
+    /// The element's attributes and their values, or nothing if the node
+    /// isn't an element.
+    fn attributes(&self) -> Vec<(String, String)>;
+}
+
+/// An `OwnedVNode` is treated as the DOM it would render as. Its unsafe
+/// text isn't parsed, and is seen as another kind of node.
+impl HydrateNode for &OwnedVNode {
+    fn kind(&self) -> NodeKind {
+        match self {
+            OwnedVNode::Text(text) => NodeKind::Text(text.clone()),
+            OwnedVNode::UnsafeText(_) => NodeKind::Other,
+            OwnedVNode::Comment(text) => NodeKind::Comment(text.clone()),
+            OwnedVNode::Element(element) => NodeKind::Element(element.name.to_string()),
+        }
+    }
+
+    fn children(&self) -> Vec<Self> {
+        match self {
+            OwnedVNode::Element(element) => element.children.iter().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn attributes(&self) -> Vec<(String, String)> {
+        match self {
+            OwnedVNode::Element(element) => element
+                .attributes
+                .iter()
+                .filter_map(|(name, value)| {
+                    rendered_value(name, value).map(|value| (name.to_string(), value))
+                })
+                .chain(
+                    element
+                        .events
+                        .iter()
+                        .map(|(name, handler)| (format!("on{}", name), handler.clone())),
+                )
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// A difference between the DOM and the tree being hydrated onto it, found by
+/// [`hydrate()`][hydrate].
+///
+/// [hydrate]: fn.hydrate.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    /// The element which differs, or which has a child which differs. It's
+    /// empty if the root node differs.
+    pub path: ElementPath,
+    pub kind: MismatchKind,
+}
+
+/// What differs between the DOM and the tree being hydrated onto it.
+///
+/// Children are numbered from 1, counting every node in the DOM.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MismatchKind {
+    /// A child isn't the kind of node expected, is a different element, or
+    /// has different text.
+    Node {
+        index: usize,
+        expected: NodeKind,
+        found: NodeKind,
+    },
+    /// An attribute is missing, has a different value, or isn't expected at
+    /// all.
+    Attribute {
+        name: String,
+        expected: Option<String>,
+        found: Option<String>,
+    },
+    /// The DOM has fewer children than expected.
+    Missing { index: usize, expected: NodeKind },
+    /// The DOM has more children than expected.
+    Extra { index: usize, found: NodeKind },
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        if !self.path.0.is_empty() {
+            write!(f, "{}: ", self.path)?;
+        }
+        match &self.kind {
+            MismatchKind::Node {
+                index,
+                expected,
+                found,
+            } => write!(f, "child {} should be {}, found {}", index, expected, found),
+            MismatchKind::Attribute {
+                name,
+                expected,
+                found,
+            } => match (expected, found) {
+                (Some(expected), Some(found)) => write!(
+                    f,
+                    "expected {}={:?}, found {}={:?}",
+                    name, expected, name, found
+                ),
+                (Some(expected), None) => {
+                    write!(f, "expected {}={:?}, found no {}", name, expected, name)
+                }
+                (None, Some(found)) => write!(f, "unexpected {}={:?}", name, found),
+                (None, None) => write!(f, "{} differs", name),
+            },
+            MismatchKind::Missing { index, expected } => {
+                write!(f, "child {} should be {}, found nothing", index, expected)
+            }
+            MismatchKind::Extra { index, found } => {
+                write!(f, "unexpected child {}: {}", index, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Mismatch {}
+
+/// The value an attribute is rendered with, or `None` if it's left out.
+fn rendered_value(name: &str, value: &str) -> Option<String> {
+    if value.is_empty() {
+        return None;
+    }
+    if escape::URL_ATTRIBUTES.contains(&name) {
+        if let Some("javascript") | Some("vbscript") = escape::url_scheme(value).as_deref() {
+            return Some(escape::UNSAFE_URL.to_string());
+        }
+    }
+    Some(value.to_string())
+}
+
+/// A child expected in the DOM.
+enum Expected<'a, T: OutputType + 'a> {
+    Node(NodeKind),
+    Element(VElement<'a, T>),
+    UnsafeText,
+}
+
+impl<'a, T: OutputType + 'a> Expected<'a, T> {
+    /// Whether a DOM node could be this one.
+    fn fits(&self, found: &NodeKind) -> bool {
+        match self {
+            Expected::Node(kind) => found.fits(kind),
+            Expected::Element(element) => expected_fits(element, found),
+            Expected::UnsafeText => true,
+        }
+    }
+}
+
+/// A child found in the DOM, with any text nodes following it merged into it.
+struct Found<'n, N> {
+    index: usize,
+    /// The position among the element siblings, for the `ElementPath`.
+    element: usize,
+    node: &'n N,
+    kind: NodeKind,
+}
+
+struct Hydrator<'f, N, T: OutputType> {
+    attach: &'f mut dyn FnMut(&N, &mut T::Events),
+    mismatches: Vec<Mismatch>,
+}
+
+impl<'f, N: HydrateNode, T: OutputType + Send> Hydrator<'f, N, T> {
+    fn mismatch(&mut self, path: &ElementPath, kind: MismatchKind) {
+        self.mismatches.push(Mismatch {
+            path: path.clone(),
+            kind,
+        });
+    }
+
+    /// Compare the children of an element, or the root node, whose path is
+    /// `path`.
+    fn nodes<'a>(
+        &mut self,
+        found: &[N],
+        expected: Vec<VNode<'a, T>>,
+        parent: &str,
+        path: &ElementPath,
+    ) {
+        let mut merged: Vec<Found<'_, N>> = Vec::new();
+        let mut elements = 0;
+        for (index, node) in found.iter().enumerate() {
+            match (node.kind(), merged.last_mut()) {
+                (NodeKind::Text(text), _) if text.is_empty() => {}
+                (
+                    NodeKind::Text(text),
+                    Some(Found {
+                        kind: NodeKind::Text(last),
+                        ..
+                    }),
+                ) => last.push_str(&text),
+                (kind, _) => {
+                    if let NodeKind::Element(_) = kind {
+                        elements += 1;
+                    }
+                    merged.push(Found {
+                        index: index + 1,
+                        element: elements,
+                        node,
+                        kind,
+                    })
+                }
+            }
+        }
+
+        let mut wanted: Vec<Expected<'a, T>> = Vec::new();
+        for vnode in expected {
+            match vnode {
+                VNode::Text("") => {}
+                VNode::Text(text) => {
+                    let text = if RAW_TEXT_ELEMENTS.contains(&parent) {
+                        escape::raw_text(parent, text)
+                    } else {
+                        text.to_string()
+                    };
+                    match wanted.last_mut() {
+                        Some(Expected::Node(NodeKind::Text(last))) => last.push_str(&text),
+                        _ => wanted.push(Expected::Node(NodeKind::Text(text))),
+                    }
+                }
+                VNode::UnsafeText(_) => wanted.push(Expected::UnsafeText),
+                VNode::Comment(text) => {
+                    wanted.push(Expected::Node(NodeKind::Comment(text.to_string())))
+                }
+                VNode::Element(element) => wanted.push(Expected::Element(element)),
+            }
+        }
+
+        let mut missing = found.len() + 1;
+        let mut found = merged.into_iter().peekable();
+        let mut wanted = wanted.into_iter().peekable();
+        while let Some(expected) = wanted.next() {
+            if let Expected::UnsafeText = expected {
+                while let Some(next) = found.peek() {
+                    if wanted.peek().is_some_and(|wanted| wanted.fits(&next.kind)) {
+                        break;
+                    }
+                    found.next();
+                }
+                continue;
+            }
+            let next = match found.next() {
+                Some(next) => next,
+                None => {
+                    let expected = match expected {
+                        Expected::Element(element) => NodeKind::Element(element.name.to_string()),
+                        Expected::Node(kind) => kind,
+                        Expected::UnsafeText => unreachable!(),
+                    };
+                    self.mismatch(
+                        path,
+                        MismatchKind::Missing {
+                            index: missing,
+                            expected,
+                        },
+                    );
+                    missing += 1;
+                    continue;
+                }
+            };
+            match expected {
+                Expected::Element(element) if expected_fits(&element, &next.kind) => {
+                    let path = path.child(element.name, next.element);
+                    self.element(next.node, element, path);
+                }
+                Expected::Element(element) => self.mismatch(
+                    path,
+                    MismatchKind::Node {
+                        index: next.index,
+                        expected: NodeKind::Element(element.name.to_string()),
+                        found: next.kind,
+                    },
+                ),
+                Expected::Node(kind) => {
+                    if kind != next.kind {
+                        self.mismatch(
+                            path,
+                            MismatchKind::Node {
+                                index: next.index,
+                                expected: kind,
+                                found: next.kind,
+                            },
+                        );
+                    }
+                }
+                Expected::UnsafeText => unreachable!(),
+            }
+        }
+        for extra in found {
+            self.mismatch(
+                path,
+                MismatchKind::Extra {
+                    index: extra.index,
+                    found: extra.kind,
+                },
+            );
+        }
+    }
+
+    fn element(&mut self, node: &N, element: VElement<'_, T>, path: ElementPath) {
+        let mut found = node.attributes();
+        for (name, value) in &element.attributes {
+            let expected = match rendered_value(name, value) {
+                Some(expected) => expected,
+                None => continue,
+            };
+            let value = found
+                .iter()
+                .position(|(found, _)| found == name)
+                .map(|index| found.remove(index).1);
+            if value.as_ref() != Some(&expected) {
+                self.mismatch(
+                    &path,
+                    MismatchKind::Attribute {
+                        name: name.to_string(),
+                        expected: Some(expected),
+                        found: value,
+                    },
+                );
+            }
+        }
+        for (name, value) in found {
+            if !name.starts_with("on") {
+                self.mismatch(
+                    &path,
+                    MismatchKind::Attribute {
+                        name,
+                        expected: None,
+                        found: Some(value),
+                    },
+                );
+            }
+        }
+        (self.attach)(node, element.events);
+        self.nodes(&node.children(), element.children, element.name, &path);
+    }
+}
+
+fn expected_fits<T: OutputType>(element: &VElement<'_, T>, found: &NodeKind) -> bool {
+    match found {
+        NodeKind::Element(name) => name.eq_ignore_ascii_case(element.name),
+        _ => false,
+    }
+}
+
+/// Walk an existing DOM tree alongside the [`VNode`][VNode] tree it should
+/// match, calling `attach` with each element's DOM node and its event
+/// handlers, and return the differences between them.
+///
+/// Elements are only attached to when their names match, and the children
+/// of an element which doesn't match aren't looked at. The DOM is never
+/// changed.
+///
+/// [VNode]: ../dom/enum.VNode.html
+pub fn hydrate<N, T, F>(node: &N, vnode: VNode<'_, T>, mut attach: F) -> Vec<Mismatch>
+where
+    N: HydrateNode,
+    T: OutputType + Send,
+    F: FnMut(&N, &mut T::Events),
+{
+    let mut hydrator: Hydrator<'_, N, T> = Hydrator {
+        attach: &mut attach,
+        mismatches: Vec::new(),
+    };
+    hydrator.nodes(
+        std::slice::from_ref(node),
+        vec![vnode],
+        "",
+        &ElementPath::new(),
+    );
+    hydrator.mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate as typed_html;
+    use crate::dom::DOMTree;
+    use crate::{html, unsafe_text};
+
+    fn mismatches(server: &mut DOMTree<String>, client: &mut DOMTree<String>) -> Vec<String> {
+        let dom = server.owned_vnode();
+        hydrate(&&dom, client.vnode(), |_, _| {})
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_matching_tree() {
+        let mut server: DOMTree<String> = html!(
+            <div class="main">
+                <p>"Hello " "Joe"</p>
+                <button onclick="alert(1)">"Click"</button>
+                <a href="javascript:alert(1)" title="">"Link"</a>
+            </div>
+            : String
+        );
+        let mut client: DOMTree<String> = html!(
+            <div class="main">
+                <p>"Hello Joe"</p>
+                <button onclick="go()">"Click"</button>
+                <a href="JavaScript:go()">"Link"</a>
+            </div>
+            : String
+        );
+        let dom = server.owned_vnode();
+        let mut attached = Vec::new();
+        let mismatches = hydrate(&&dom, client.vnode(), |node, events| {
+            if let NodeKind::Element(name) = node.kind() {
+                attached.push((name, events.click.clone()));
+            }
+        });
+        assert_eq!(Vec::<Mismatch>::new(), mismatches);
+        assert_eq!(
+            vec![
+                ("div".to_string(), None),
+                ("p".to_string(), None),
+                ("button".to_string(), Some("go()".to_string())),
+                ("a".to_string(), None),
+            ],
+            attached
+        );
+    }
+
+    #[test]
+    fn test_mismatches() {
+        let mut server: DOMTree<String> = html!(
+            <div class="main" id="root">
+                <p>"Hello Joe"</p>
+                <span>"Bye"</span>
+                <!-- "note" -->
+            </div>
+        );
+        let mut client: DOMTree<String> = html!(
+            <div class="side">
+                <p>"Hello Mike"</p>
+                <em>"Bye"</em>
+            </div>
+        );
+        assert_eq!(
+            vec![
+                "div: expected class=\"side\", found class=\"main\"",
+                "div: unexpected id=\"root\"",
+                "div > p:nth-child(1): child 1 should be text \"Hello Mike\", found text \"Hello Joe\"",
+                "div: child 2 should be <em>, found <span>",
+                "div: unexpected child 3: comment \"note\"",
+            ],
+            mismatches(&mut server, &mut client)
+        );
+
+        let mut server: DOMTree<String> = html!(<ul><li>"One"</li></ul>);
+        let mut client: DOMTree<String> = html!(<ul><li>"One"</li><li>"Two"</li></ul>);
+        assert_eq!(
+            vec!["ul: child 2 should be <li>, found nothing"],
+            mismatches(&mut server, &mut client)
+        );
+
+        let mut server: DOMTree<String> = html!(<p>"Hello " <b>"Joe"</b></p>);
+        let mut client: DOMTree<String> = html!(<p>"Hello " <b>"Mike"</b></p>);
+        assert_eq!(
+            vec!["p > b:nth-child(1): child 1 should be text \"Mike\", found text \"Joe\""],
+            mismatches(&mut server, &mut client)
+        );
+
+        let mut server: DOMTree<String> = html!(<ul/>);
+        let mut client: DOMTree<String> = html!(<ol/>);
+        assert_eq!(
+            vec!["child 1 should be <ol>, found <ul>"],
+            mismatches(&mut server, &mut client)
+        );
+    }
+
+    #[test]
+    fn test_unsafe_text() {
+        let mut server: DOMTree<String> = html!(
+            <div>
+                <b>"Bold"</b>
+                "and "
+                <i>"italic"</i>
+                <p>"After"</p>
+            </div>
+        );
+        let mut client: DOMTree<String> = html!(
+            <div>
+                { unsafe_text!("<b>Bold</b>and <i>italic</i>") }
+                <p>"After"</p>
+            </div>
+        );
+        assert_eq!(Vec::<String>::new(), mismatches(&mut server, &mut client));
+
+        let mut client: DOMTree<String> = html!(
+            <div>
+                { unsafe_text!("<b>Bold</b>and <i>italic</i>") }
+                <p>"After"</p>
+                <p>"More"</p>
+            </div>
+        );
+        assert_eq!(
+            vec!["div: child 5 should be <p>, found nothing"],
+            mismatches(&mut server, &mut client)
+        );
+    }
+}
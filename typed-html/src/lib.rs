@@ -51,6 +51,58 @@
 //!   handling, it will complain. You can put braces or parentheses around the
 //!   expression if the parser doesn't understand
 //!   it. You can use any Rust code inside a brace or parenthesis block.
+//! * The macro returns a single node. To return several siblings, wrap them in a
+//!   fragment, `<>...</>`, which returns a [`Fragment`][Fragment]. When a fragment
+//!   is used as a child, its children are inserted in its place, and they still
+//!   have to be of a type the parent element accepts.
+//! * Text nodes and attribute values can be format strings, written as
+//!   `f!"Hello, {name}!"`, which are interpolated with [`format!`][format].
+//!   (Rust 2021 reserves the plain `f"..."` prefix, hence the `!`.) The
+//!   result is a single string, which is escaped as a whole when it's
+//!   rendered, so the literal parts are text rather than markup, just like a
+//!   string literal's. For an attribute, the literal parts are checked at
+//!   compile time against what its type can be parsed from, like a string
+//!   literal is, so `tabindex=f!"x{n}"` is rejected. The formatted value is
+//!   then parsed at runtime, and panics if it can't be, as `tabindex=f!"-{n}-"`
+//!   would.
+//! * Comments are written as `<!-- "..." -->`, holding a string literal, a
+//!   format string or a code block, and produce a [`CommentNode`][CommentNode].
+//!   A literal can't contain `--`, which is rejected at compile time, while
+//!   other text is escaped at runtime. Comments can go anywhere an element's
+//!   children are a content category, but not in elements which only accept
+//!   particular children, like `<ul>`, and `dodrio!` doesn't support them.
+//!
+//! ```
+//! # use typed_html::html;
+//! # use typed_html::dom::DOMTree;
+//! # fn main() {
+//! let id = 42;
+//! let name = "Joe";
+//! let doc: DOMTree<String> = html!(
+//!     <a href=f!"/users/{id}">f!"Hello, {name}!"</a>
+//! );
+//! assert_eq!("<a href=\"/users/42\">Hello, Joe!</a>", doc.to_string());
+//! # }
+//! ```
+//!
+//! ```
+//! # use typed_html::html;
+//! # use typed_html::dom::DOMTree;
+//! # fn main() {
+//! let island = 3;
+//! let doc: DOMTree<String> = html!(
+//!     <div>
+//!         <!-- "[if IE]><p>Please upgrade your browser.</p><![endif]" -->
+//!         <!-- f!"island {island}" -->
+//!         <p>"Hello Joe!"</p>
+//!     </div>
+//! );
+//! assert_eq!(
+//!     "<div><!--[if IE]><p>Please upgrade your browser.</p><![endif]--><!--island 3--><p>Hello Joe!</p></div>",
+//!     doc.to_string()
+//! );
+//! # }
+//! ```
 //!
 //! # Valid HTML5
 //!
@@ -63,9 +115,14 @@
 //! The structure validation is simplistic by necessity, as it defers to the type
 //! system: a few elements will have one or more required children, and any element
 //! which accepts children will have a restriction on the type of the children,
-//! usually a broad group as defined by the HTML spec. Many elements have
-//! restrictions on children of children, or require a particular ordering of
-//! optional elements, which isn't currently validated.
+//! usually a broad group as defined by the HTML spec. A required child, like the
+//! `<title>` of `<head>`, can go anywhere among its siblings, and stays where
+//! it's written. Some elements also
+//! require a particular ordering of their children (such as `<table>`, where
+//! `<caption>` must come first and `<tfoot>` last), which the macro checks at
+//! compile time as long as none of the children are generated by code blocks.
+//! Many elements have restrictions on children of children, which isn't currently
+//! validated.
 //!
 //! # Attribute Values
 //!
@@ -82,9 +139,10 @@
 //! [`FromStr<A>`][FromStr] trait to try and parse the string literal into the
 //! expected type. This is extremely useful for eg. CSS classes, letting you type
 //! `class="css-class-1 css-class-2"` instead of going to the trouble of
-//! constructing a [`SpacedSet<Class>`][SpacedSet]. The big caveat for this:
-//! currently, the macro is not able to validate the string at compile time, and the
-//! conversion will panic at runtime if the string is invalid.
+//! constructing a [`SpacedSet<Class>`][SpacedSet]. The macro checks the string
+//! at compile time as far as it can: a string which can't be one of the values of
+//! an enumerated attribute, or which has letters in it for a number, is rejected.
+//! Beyond that, the conversion will panic at runtime if the string is invalid.
 //!
 //! ## Example
 //!
@@ -109,6 +167,96 @@
 //! # ; Ok(()) }
 //! ```
 //!
+//! To set an attribute only some of the time, write it as `name=?value`, where
+//! the value is an [`Option`][Option] of what the attribute would otherwise
+//! take. The attribute is left unset when the value is `None`. This works for
+//! data attributes and event handlers too. A component is given the `Option`
+//! itself as its prop.
+//!
+//! ```
+//! # use typed_html::html;
+//! # use typed_html::dom::DOMTree;
+//! # fn main() {
+//! let link: Option<&str> = None;
+//! let user_id = Some("joe");
+//! let doc: DOMTree<String> = html!(
+//!     <a href=?link data-id=?user_id>"Home"</a>
+//! );
+//! assert_eq!("<a data-id=\"joe\">Home</a>", doc.to_string());
+//! # }
+//! ```
+//!
+//! You can also spread a set of attributes onto an element with `{..attrs}`,
+//! which is useful for a component passing its caller's attributes on to an
+//! element it renders. The attributes are given as the element's attribute
+//! struct, like [`Attrs_input`][Attrs_input] for `<input>`, which implements
+//! [`Default`][Default] so you only need to fill in the attributes you want to
+//! set. Any attribute given explicitly overrides a spread one, wherever the
+//! spread appears in the tag. On a component, `{..props}` fills in any props
+//! not given explicitly, like Rust's struct update syntax.
+//!
+//! ```
+//! # use typed_html::html;
+//! # use typed_html::dom::DOMTree;
+//! # use typed_html::elements::Attrs_input;
+//! # fn main() {
+//! let attrs = Attrs_input {
+//!     placeholder: Some("Search".into()),
+//!     ..Default::default()
+//! };
+//! let doc: DOMTree<String> = html!(
+//!     <input {..attrs} type="search"/>
+//! );
+//! assert_eq!("<input placeholder=\"Search\" type=\"search\"/>", doc.to_string());
+//! # }
+//! ```
+//!
+//! To toggle classes on an element, use `class:name=value`, which adds the
+//! class `name` to the element's `class` attribute when `value` is true, on top
+//! of any classes it's given with `class=`. Class names are checked at compile
+//! time. Outside the macro, the [`classes!`][classes] macro builds a
+//! [`SpacedSet<Class>`][SpacedSet] from a mix of class names, optional classes
+//! and `(name, bool)` pairs.
+//!
+//! ```
+//! # use typed_html::html;
+//! # use typed_html::dom::DOMTree;
+//! # fn main() {
+//! let is_active = true;
+//! let count = 0;
+//! let doc: DOMTree<String> = html!(
+//!     <p class="tab" class:active=is_active class:disabled={count == 0}>"Home"</p>
+//! );
+//! assert_eq!("<p class=\"active disabled tab\">Home</p>", doc.to_string());
+//! # }
+//! ```
+//!
+//! The `key` pseudo-attribute gives an element a key identifying it among its
+//! siblings, so a list can be reordered without its elements being rebuilt.
+//! It's never rendered, but it's stored in the element's `key` field and
+//! exposed on [`VElement`][VElement], where [`diff()`][diff] uses it to match
+//! up children. The `dodrio!` macro passes it on to Dodrio's keyed diffing,
+//! which takes a `u32` and needs either all or none of an element's children
+//! to be keyed.
+//!
+//! ```
+//! # use typed_html::{html, text};
+//! # use typed_html::dom::{DOMTree, VNode};
+//! # fn main() {
+//! let mut doc: DOMTree<String> = html!(
+//!     <ul>
+//!         { ["one", "two"].iter().map(|name| html!(<li key=name>{ text!(*name) }</li>)) }
+//!     </ul>
+//! );
+//! assert_eq!("<ul><li>one</li><li>two</li></ul>", doc.to_string());
+//! if let VNode::Element(list) = doc.vnode() {
+//!     if let VNode::Element(item) = &list.children[1] {
+//!         assert_eq!(Some("two".to_string()), item.key);
+//!     }
+//! }
+//! # }
+//! ```
+//!
 //! # Generated Nodes
 //!
 //! Brace blocks in the child node position are expected to return an
@@ -134,6 +282,67 @@
 //! # ;}
 //! ```
 //!
+//! # Control Flow
+//!
+//! You can also use `if`, `if let`, `else`, `match` and `for` in the child node
+//! position, with nodes rather than Rust code inside their blocks. They expand
+//! to the same Rust control flow around the code adding each child to its
+//! parent, so every branch is checked against the content the parent accepts,
+//! just as if its nodes had been written out directly. A `match` arm can be a
+//! block of nodes, or a single node followed by a comma.
+//!
+//! ## Example
+//!
+//! ```
+//! # #![recursion_limit = "128"]
+//! # use typed_html::{html, text};
+//! # use typed_html::dom::DOMTree;
+//! enum Status {
+//!     Ok,
+//!     Error(String),
+//! }
+//!
+//! # fn main() {
+//! let user = Some("Joe");
+//! let items = vec![1, 2, 3];
+//! let status = Status::Error("Out of cheese.".to_string());
+//! let doc: DOMTree<String> = html!(
+//!     <div>
+//!         if let Some(name) = user {
+//!             <p>"Hello " { text!(name) }</p>
+//!         } else {
+//!             <p>"Please log in."</p>
+//!         }
+//!         <ul>
+//!             for item in items {
+//!                 <li>{ text!("{}", item) }</li>
+//!             }
+//!         </ul>
+//!         match status {
+//!             Status::Ok => <p>"Everything is fine."</p>,
+//!             Status::Error(message) => {
+//!                 <p class="error">{ text!(message) }</p>
+//!             }
+//!         }
+//!     </div>
+//! );
+//! assert_eq!(
+//!     "<div><p>Hello Joe</p><ul><li>1</li><li>2</li><li>3</li></ul><p class=\"error\">Out of cheese.</p></div>",
+//!     doc.to_string()
+//! );
+//! # }
+//! ```
+//!
+//! # Components
+//!
+//! Tags starting with a capital letter are components: structs implementing the
+//! [`Component`][Component] trait, whose fields are their props. The attributes
+//! of the tag become the struct's fields, and its children are passed to the
+//! component's `render()` method. The component declares the type of children it
+//! accepts and the type it renders into, so it takes part in the same type
+//! checking as an element does. See the [`component`][component] module for an
+//! example.
+//!
 //! # Rendering
 //!
 //! You have two options for actually producing something useful from the DOM tree
@@ -163,8 +372,12 @@
 //! The DOM tree structure also implements a method called `vnode()`, which renders
 //! the tree to a tree of [`VNode`][VNode]s, which is a mirror of the generated tree
 //! with every attribute value rendered into `String`s. You can walk this virtual
-//! DOM tree and use it to build an actual DOM tree with `stdweb` or pass it on to
-//! your favourite virtual DOM system.
+//! DOM tree and use it to build an actual DOM tree with `web-sys` or `stdweb`, or
+//! pass it on to your favourite virtual DOM system.
+//!
+//! To bring markup rendered on the server to life instead, the
+//! [`hydrate`][hydrate] module attaches a `VNode` tree's event handlers to the
+//! existing DOM and reports where the two differ.
 //!
 //! # Licence
 //!
@@ -186,12 +399,24 @@
 //! [to_string]: https://doc.rust-lang.org/std/string/trait.ToString.html#tymethod.to_string
 //! [Node]: dom/trait.Node.html
 //! [VNode]: dom/enum.VNode.html
+//! [Attrs_input]: elements/struct.Attrs_input.html
+//! [Default]: https://doc.rust-lang.org/std/default/trait.Default.html
+//! [classes]: macro.classes.html
+//! [VElement]: dom/struct.VElement.html
+//! [diff]: diff/fn.diff.html
+//! [hydrate]: hydrate/index.html
+//! [format]: https://doc.rust-lang.org/std/macro.format.html
+//! [CommentNode]: dom/struct.CommentNode.html
+//! [Option]: https://doc.rust-lang.org/std/option/enum.Option.html
 //! [FromStr]: https://doc.rust-lang.org/std/str/trait.FromStr.html
 //! [SpacedSet]: types/struct.SpacedSet.html
 //! [IntoIterator]: https://doc.rust-lang.org/std/iter/trait.IntoIterator.html
 //! [Into]: https://doc.rust-lang.org/std/convert/trait.Into.html
 //! [Into::into]: https://doc.rust-lang.org/std/convert/trait.Into.html#method.into
 //! [DOMTree]: dom/type.DOMTree.html
+//! [Component]: component/trait.Component.html
+//! [Fragment]: dom/struct.Fragment.html
+//! [component]: component/index.html
 
 pub extern crate htmlescape;
 
@@ -202,20 +427,51 @@ pub use typed_html_macros::html;
 #[cfg(feature = "dodrio_macro")]
 pub use typed_html_macros::dodrio;
 
+pub mod component;
+pub mod diff;
 pub mod dom;
 pub mod elements;
+pub mod escape;
 pub mod events;
+pub mod hydrate;
+pub mod lint;
 pub mod output;
+pub mod parse;
+pub mod sanitize;
+pub mod select;
+#[cfg(feature = "serde")]
+mod serialize;
 pub mod types;
+pub mod validate;
+
+pub use crate::parse::parse;
 
 /// Marker trait for outputs
 pub trait OutputType {
     /// The type that contains events for this output.
     type Events: Default + Display + Send;
     /// The type of event targets for this output.
-    type EventTarget: Send;
+    type EventTarget;
     /// The type that's returned from attaching an event listener to a target.
-    type EventListenerHandle: Send;
+    type EventListenerHandle;
+
+    /// Get the event handlers which are strings, as pairs of event names,
+    /// without the `on` prefix, and handlers.
+    ///
+    /// Only string output has these, so by default there are none.
+    fn string_events(_events: &Self::Events) -> Vec<(&'static str, String)> {
+        Vec::new()
+    }
+
+    /// Set an event handler from a string, as returned by
+    /// [`string_events()`][string_events].
+    ///
+    /// Returns false if the handler wasn't set, which by default it isn't.
+    ///
+    /// [string_events]: #method.string_events
+    fn set_string_event(_events: &mut Self::Events, _name: &str, _handler: &str) -> bool {
+        false
+    }
 }
 
 /// String output
@@ -223,12 +479,20 @@ impl OutputType for String {
     type Events = events::Events<String>;
     type EventTarget = ();
     type EventListenerHandle = ();
+
+    fn string_events(events: &Self::Events) -> Vec<(&'static str, String)> {
+        events
+            .iter()
+            .map(|(name, handler)| (name, handler.clone()))
+            .collect()
+    }
+
+    fn set_string_event(events: &mut Self::Events, name: &str, handler: &str) -> bool {
+        events.set(name, handler.to_string())
+    }
 }
 
+#[deprecated(note = "use typed_html::escape::attribute()")]
 pub fn escape_html_attribute(html_attr: String) -> String {
-    // Even though the code is quoting the variables with a double quote, escape all known quoting chars
-    html_attr
-        .replace("\"", "&quot;")
-        .replace("'", "&#39;")
-        .replace("`", "&#96;")
+    escape::attribute(&html_attr)
 }
@@ -0,0 +1,538 @@
+//! Accessibility linting for DOM trees.
+//!
+//! A [`Linter`][Linter] walks a rendered [`VNode`][VNode] tree and reports
+//! common [WCAG] failures which can be spotted from the markup alone. Each
+//! check is a [`Rule`][Rule] which can be turned on or off, and each
+//! [`Diagnostic`][Diagnostic] carries the path to the offending element, so
+//! a test suite can simply assert that a page produces no diagnostics.
+//!
+//! # Examples
+//!
+//! ```
+//! # use typed_html::html;
+//! # use typed_html::dom::{DOMTree, Node};
+//! # use typed_html::lint::{Linter, Rule};
+//! # fn main() {
+//! let mut doc: DOMTree<String> = html!(
+//!     <div>
+//!         <h1>"Kittens"</h1>
+//!         <h3>"Tabby"</h3>
+//!         <img src="tabby.jpg"/>
+//!     </div>
+//! );
+//! let linter = Linter::default().disable(Rule::HeadingOrder);
+//! let diagnostics = linter.check(&doc.vnode());
+//! assert_eq!(1, diagnostics.len());
+//! assert_eq!(
+//!     "div > img:nth-child(3): <img> has no alt attribute [image-alt]",
+//!     diagnostics[0].to_string()
+//! );
+//! # }
+//! ```
+//!
+//! [Linter]: struct.Linter.html
+//! [Rule]: enum.Rule.html
+//! [Diagnostic]: struct.Diagnostic.html
+//! [VNode]: ../dom/enum.VNode.html
+//! [WCAG]: https://www.w3.org/TR/WCAG21/
+
+use std::collections::{BTreeSet, HashSet};
+use std::fmt::{Display, Error, Formatter};
+
+use strum_macros::*;
+
+use crate::dom::{ElementPath, VElement, VNode};
+use crate::OutputType;
+
+/// The checks a [`Linter`][Linter] can perform.
+///
+/// The string form of a rule is the name it's reported under, eg.
+/// `image-alt`, and rules can be parsed from it too.
+///
+/// [Linter]: struct.Linter.html
+#[derive(
+    EnumString, Display, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, AsRefStr,
+)]
+pub enum Rule {
+    /// `<img>`, `<area>` and `<input type="image">` must have an `alt`
+    /// attribute. It may be empty for purely decorative images.
+    #[strum(to_string = "image-alt")]
+    ImageAlt,
+    /// Form controls must have a `<label>`, either around them or pointing at
+    /// them with `for`, or at least a `title`.
+    #[strum(to_string = "form-label")]
+    FormLabel,
+    /// Headings must not skip levels on the way down, eg. `<h1>` followed by
+    /// `<h4>`.
+    #[strum(to_string = "heading-order")]
+    HeadingOrder,
+    /// Buttons and links must have some text, an image with alt text or a
+    /// `title` to give them a name.
+    #[strum(to_string = "accessible-name")]
+    AccessibleName,
+    /// The `role` attribute must not contradict the element's native
+    /// semantics, eg. `<h2 role="checkbox">`.
+    #[strum(to_string = "role-conflict")]
+    RoleConflict,
+    /// `tabindex` must not be greater than zero, as that breaks the natural
+    /// tab order.
+    #[strum(to_string = "positive-tabindex")]
+    PositiveTabindex,
+}
+
+impl Rule {
+    /// Every rule, in the order they're documented.
+    pub const ALL: &'static [Rule] = &[
+        Rule::ImageAlt,
+        Rule::FormLabel,
+        Rule::HeadingOrder,
+        Rule::AccessibleName,
+        Rule::RoleConflict,
+        Rule::PositiveTabindex,
+    ];
+}
+
+/// A rule violation found by a [`Linter`][Linter].
+///
+/// [Linter]: struct.Linter.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub rule: Rule,
+    pub path: ElementPath,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(f, "{}: {} [{}]", self.path, self.message, self.rule)
+    }
+}
+
+/// An accessibility linter with a set of enabled [`Rule`][Rule]s.
+///
+/// `Linter::default()` enables every rule.
+///
+/// [Rule]: enum.Rule.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Linter {
+    rules: BTreeSet<Rule>,
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Linter {
+            rules: Rule::ALL.iter().cloned().collect(),
+        }
+    }
+}
+
+/// The implicit ARIA role of elements with strong native semantics, and the
+/// roles they're allowed to take instead, per [ARIA in HTML].
+///
+/// [ARIA in HTML]: https://www.w3.org/TR/html-aria/
+static NATIVE_ROLES: &[(&str, &str, &[&str])] = &[
+    (
+        "a",
+        "link",
+        &[
+            "button",
+            "checkbox",
+            "menuitem",
+            "menuitemcheckbox",
+            "menuitemradio",
+            "option",
+            "radio",
+            "switch",
+            "tab",
+            "treeitem",
+        ],
+    ),
+    (
+        "article",
+        "article",
+        &[
+            "application",
+            "document",
+            "feed",
+            "main",
+            "none",
+            "presentation",
+            "region",
+        ],
+    ),
+    (
+        "aside",
+        "complementary",
+        &["feed", "none", "note", "presentation", "region", "search"],
+    ),
+    (
+        "button",
+        "button",
+        &[
+            "checkbox",
+            "combobox",
+            "link",
+            "menuitem",
+            "menuitemcheckbox",
+            "menuitemradio",
+            "option",
+            "radio",
+            "switch",
+            "tab",
+        ],
+    ),
+    ("h1", "heading", &["none", "presentation", "tab"]),
+    ("h2", "heading", &["none", "presentation", "tab"]),
+    ("h3", "heading", &["none", "presentation", "tab"]),
+    ("h4", "heading", &["none", "presentation", "tab"]),
+    ("h5", "heading", &["none", "presentation", "tab"]),
+    ("h6", "heading", &["none", "presentation", "tab"]),
+    ("hr", "separator", &["none", "presentation"]),
+    ("main", "main", &[]),
+    (
+        "nav",
+        "navigation",
+        &["menu", "menubar", "none", "presentation", "tablist"],
+    ),
+    (
+        "ol",
+        "list",
+        &[
+            "directory",
+            "group",
+            "listbox",
+            "menu",
+            "menubar",
+            "none",
+            "presentation",
+            "radiogroup",
+            "tablist",
+            "toolbar",
+            "tree",
+        ],
+    ),
+    ("progress", "progressbar", &[]),
+    ("select", "combobox", &["listbox", "menu"]),
+    (
+        "table",
+        "table",
+        &["grid", "none", "presentation", "treegrid"],
+    ),
+    ("textarea", "textbox", &[]),
+    (
+        "ul",
+        "list",
+        &[
+            "directory",
+            "group",
+            "listbox",
+            "menu",
+            "menubar",
+            "none",
+            "presentation",
+            "radiogroup",
+            "tablist",
+            "toolbar",
+            "tree",
+        ],
+    ),
+];
+
+/// Input types which don't need a label, because they label themselves or
+/// aren't shown at all.
+static UNLABELLED_INPUT_TYPES: &[&str] = &["button", "hidden", "image", "reset", "submit"];
+
+fn attribute<'a, T: OutputType + Send>(
+    element: &'a VElement<'_, T>,
+    name: &str,
+) -> Option<&'a str> {
+    element
+        .attributes
+        .iter()
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.as_str())
+}
+
+fn heading_level(name: &str) -> Option<usize> {
+    match name {
+        "h1" => Some(1),
+        "h2" => Some(2),
+        "h3" => Some(3),
+        "h4" => Some(4),
+        "h5" => Some(5),
+        "h6" => Some(6),
+        _ => None,
+    }
+}
+
+/// Whether a node contains anything which would give its parent an
+/// accessible name.
+fn has_name<T: OutputType + Send>(node: &VNode<'_, T>) -> bool {
+    match node {
+        VNode::Text(text) | VNode::UnsafeText(text) => !text.trim().is_empty(),
+        VNode::Comment(_) => false,
+        VNode::Element(element) => {
+            let alt = attribute(element, "alt").map(|alt| !alt.trim().is_empty());
+            (element.name == "img" && alt == Some(true)) || element.children.iter().any(has_name)
+        }
+    }
+}
+
+fn collect_label_targets<T: OutputType + Send>(node: &VNode<'_, T>, out: &mut HashSet<String>) {
+    if let VNode::Element(element) = node {
+        if element.name == "label" {
+            if let Some(target) = attribute(element, "for") {
+                out.insert(target.to_string());
+            }
+        }
+        for child in &element.children {
+            collect_label_targets(child, out);
+        }
+    }
+}
+
+struct Walker<'a> {
+    rules: &'a BTreeSet<Rule>,
+    label_targets: HashSet<String>,
+    heading_level: Option<usize>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Walker<'a> {
+    fn report(&mut self, rule: Rule, path: &ElementPath, message: String) {
+        if self.rules.contains(&rule) {
+            self.diagnostics.push(Diagnostic {
+                rule,
+                path: path.clone(),
+                message,
+            });
+        }
+    }
+
+    fn element<T: OutputType + Send>(
+        &mut self,
+        element: &VElement<'_, T>,
+        path: ElementPath,
+        in_label: bool,
+    ) {
+        let name = element.name;
+        let input_type = if name == "input" {
+            Some(attribute(element, "type").unwrap_or("text"))
+        } else {
+            None
+        };
+
+        if (name == "img" || name == "area" || input_type == Some("image"))
+            && attribute(element, "alt").is_none()
+        {
+            self.report(
+                Rule::ImageAlt,
+                &path,
+                format!("<{}> has no alt attribute", name),
+            );
+        }
+
+        let is_control = match input_type {
+            Some(input_type) => !UNLABELLED_INPUT_TYPES.contains(&input_type),
+            None => name == "select" || name == "textarea",
+        };
+        if is_control && !in_label && attribute(element, "title").is_none() {
+            let labelled = attribute(element, "id")
+                .map(|id| self.label_targets.contains(id))
+                .unwrap_or(false);
+            if !labelled {
+                self.report(
+                    Rule::FormLabel,
+                    &path,
+                    format!("<{}> has no associated <label>", name),
+                );
+            }
+        }
+
+        if let Some(level) = heading_level(name) {
+            if let Some(previous) = self.heading_level {
+                if level > previous + 1 {
+                    self.report(
+                        Rule::HeadingOrder,
+                        &path,
+                        format!(
+                            "<{}> follows <h{}>, skipping a heading level",
+                            name, previous
+                        ),
+                    );
+                }
+            }
+            self.heading_level = Some(level);
+        }
+
+        let is_link = name == "a" && attribute(element, "href").is_some();
+        if (name == "button" || is_link)
+            && attribute(element, "title").is_none()
+            && !element.children.iter().any(has_name)
+        {
+            self.report(
+                Rule::AccessibleName,
+                &path,
+                format!("<{}> has no text or other accessible name", name),
+            );
+        }
+
+        if let Some(role) = attribute(element, "role") {
+            let native = NATIVE_ROLES
+                .iter()
+                .find(|(element, _, _)| *element == name)
+                .filter(|_| name != "a" || is_link);
+            if let Some((_, implicit, allowed)) = native {
+                if role != *implicit && !allowed.contains(&role) {
+                    self.report(
+                        Rule::RoleConflict,
+                        &path,
+                        format!(
+                            "role=\"{}\" contradicts the native {} role of <{}>",
+                            role, implicit, name
+                        ),
+                    );
+                }
+            }
+        }
+
+        if let Some(tabindex) = attribute(element, "tabindex") {
+            if tabindex
+                .trim()
+                .parse::<isize>()
+                .map(|i| i > 0)
+                .unwrap_or(false)
+            {
+                self.report(
+                    Rule::PositiveTabindex,
+                    &path,
+                    format!("tabindex=\"{}\" is greater than zero", tabindex),
+                );
+            }
+        }
+
+        let in_label = in_label || name == "label";
+        let elements = element.children.iter().filter_map(|child| match child {
+            VNode::Element(child) => Some(child),
+            _ => None,
+        });
+        for (index, child) in elements.enumerate() {
+            self.element(child, path.child(child.name, index + 1), in_label);
+        }
+    }
+}
+
+impl Linter {
+    /// Construct a linter with no rules enabled.
+    pub fn none() -> Self {
+        Linter {
+            rules: BTreeSet::new(),
+        }
+    }
+
+    /// Enable a rule.
+    pub fn enable(mut self, rule: Rule) -> Self {
+        self.rules.insert(rule);
+        self
+    }
+
+    /// Disable a rule.
+    pub fn disable(mut self, rule: Rule) -> Self {
+        self.rules.remove(&rule);
+        self
+    }
+
+    /// Test whether a rule is enabled.
+    pub fn is_enabled(&self, rule: Rule) -> bool {
+        self.rules.contains(&rule)
+    }
+
+    /// Lint a tree, returning its diagnostics in document order.
+    pub fn check<T: OutputType + Send>(&self, vnode: &VNode<'_, T>) -> Vec<Diagnostic> {
+        let mut label_targets = HashSet::new();
+        collect_label_targets(vnode, &mut label_targets);
+        let mut walker = Walker {
+            rules: &self.rules,
+            label_targets,
+            heading_level: None,
+            diagnostics: Vec::new(),
+        };
+        if let VNode::Element(element) = vnode {
+            walker.element(element, ElementPath::new().child(element.name, 1), false);
+        }
+        walker.diagnostics
+    }
+}
+
+/// Lint a tree with every rule enabled.
+///
+/// This is shorthand for `Linter::default().check(vnode)`.
+pub fn lint<T: OutputType + Send>(vnode: &VNode<'_, T>) -> Vec<Diagnostic> {
+    Linter::default().check(vnode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate as typed_html;
+    use crate::dom::DOMTree;
+    use crate::html;
+
+    fn rules(doc: &mut DOMTree<String>) -> Vec<Rule> {
+        lint(&doc.vnode()).into_iter().map(|d| d.rule).collect()
+    }
+
+    #[test]
+    fn test_clean_tree() {
+        let mut doc: DOMTree<String> = html!(
+            <form>
+                <h1>"Sign in"</h1>
+                <h2>"Details"</h2>
+                <label>"Name" <input name="name"/></label>
+                <label for="pass">"Password"</label>
+                <input id="pass" type="password"/>
+                <input type="submit"/>
+                <img src="logo.png" alt=""/>
+                <a href="/help"><img src="help.png" alt="Help"/></a>
+                <button title="Close"/>
+                <ul role="menu"/>
+            </form>
+        );
+        assert_eq!(Vec::<Rule>::new(), rules(&mut doc));
+    }
+
+    #[test]
+    fn test_rule_violations() {
+        let mut doc: DOMTree<String> = html!(
+            <div tabindex=2>
+                <h1>"Title"</h1>
+                <h4 role="checkbox">"Subtitle"</h4>
+                <textarea/>
+                <button>" "</button>
+                <img src="cat.jpg"/>
+            </div>
+        );
+        assert_eq!(
+            vec![
+                Rule::PositiveTabindex,
+                Rule::HeadingOrder,
+                Rule::RoleConflict,
+                Rule::FormLabel,
+                Rule::AccessibleName,
+                Rule::ImageAlt,
+            ],
+            rules(&mut doc)
+        );
+    }
+
+    #[test]
+    fn test_disabled_rules() {
+        let mut doc: DOMTree<String> = html!(<div tabindex=1><img src="cat.jpg"/></div>);
+        let linter = Linter::none().enable(Rule::ImageAlt);
+        let diagnostics = linter.check(&doc.vnode());
+        assert_eq!(1, diagnostics.len());
+        assert_eq!(Rule::ImageAlt, diagnostics[0].rule);
+        assert_eq!("div > img:nth-child(1)", diagnostics[0].path.to_string());
+        assert_eq!(Ok(Rule::PositiveTabindex), "positive-tabindex".parse());
+    }
+}
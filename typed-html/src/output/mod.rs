@@ -2,3 +2,5 @@
 pub mod stdweb;
 #[cfg(feature = "dodrio_macro")]
 pub mod dodrio;
+#[cfg(feature = "web-sys")]
+pub mod web_sys;
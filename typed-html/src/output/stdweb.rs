@@ -1,12 +1,16 @@
 use std::fmt::{Display, Error, Formatter};
 use std::marker::PhantomData;
+use std::sync::{Arc, Mutex, TryLockError};
 
+use stdweb::js;
+use stdweb::unstable::TryInto;
 use stdweb::web::event::*;
-use stdweb::web::{self, Element, EventListenerHandle, IElement, IEventTarget, INode};
+use stdweb::web::{self, Element, EventListenerHandle, IElement, IEventTarget, INode, NodeType};
 
-use crate::OutputType;
 use crate::dom::VNode;
 use crate::events::EventHandler;
+use crate::hydrate::{self, HydrateNode, Mismatch, NodeKind};
+use crate::OutputType;
 
 /// DOM output using the stdweb crate
 pub struct Stdweb;
@@ -125,14 +129,19 @@ impl Display for Events {
 }
 
 /// Wrapper type for closures as event handlers.
-pub struct EFn<F, E>(Option<F>, PhantomData<E>);
+///
+/// The closure is shared by every listener made from it, so a tree can be
+/// built more than once. While it's running, it isn't called again: an event
+/// it triggers itself, as by calling `click()` inside an `onclick` handler,
+/// is skipped rather than calling it re-entrantly.
+pub struct EFn<F, E>(Arc<Mutex<F>>, PhantomData<fn(E)>);
 
 impl<F, E> EFn<F, E>
 where
     F: FnMut(E) + 'static + Send,
 {
     pub fn new(f: F) -> Self {
-        EFn(Some(f), PhantomData)
+        EFn(Arc::new(Mutex::new(f)), PhantomData)
     }
 }
 
@@ -152,8 +161,15 @@ where
     E: ConcreteEvent + 'static + Send,
 {
     fn attach(&mut self, target: &mut <Stdweb as OutputType>::EventTarget) -> EventListenerHandle {
-        let handler = self.0.take().unwrap();
-        target.add_event_listener(handler)
+        let handler = self.0.clone();
+        target.add_event_listener(move |event: E| {
+            let mut handler = match handler.try_lock() {
+                Ok(handler) => handler,
+                Err(TryLockError::Poisoned(err)) => err.into_inner(),
+                Err(TryLockError::WouldBlock) => return,
+            };
+            (*handler)(event);
+        })
     }
 
     fn render(&self) -> Option<String> {
@@ -161,32 +177,173 @@ where
     }
 }
 
+impl HydrateNode for web::Node {
+    fn kind(&self) -> NodeKind {
+        match self.node_type() {
+            NodeType::Element => NodeKind::Element(self.node_name()),
+            NodeType::Text => NodeKind::Text(self.text_content().unwrap_or_default()),
+            NodeType::Comment => NodeKind::Comment(self.text_content().unwrap_or_default()),
+            _ => NodeKind::Other,
+        }
+    }
+
+    fn children(&self) -> Vec<Self> {
+        self.child_nodes().iter().collect()
+    }
+
+    fn attributes(&self) -> Vec<(String, String)> {
+        let element: Element = match self.clone().try_into() {
+            Ok(element) => element,
+            Err(_) => return Vec::new(),
+        };
+        element
+            .get_attribute_names()
+            .into_iter()
+            .filter_map(|name| element.get_attribute(&name).map(|value| (name, value)))
+            .collect()
+    }
+}
+
+/// A DOM tree built by [`Stdweb::build()`][build] or hydrated by
+/// [`Stdweb::hydrate()`][hydrate], with the event listeners attached to it.
+///
+/// Like stdweb's own `EventListenerHandle`, dropping it leaves the listeners
+/// attached. [`unmount()`][unmount] removes them.
+///
+/// [build]: struct.Stdweb.html#method.build
+/// [hydrate]: struct.Stdweb.html#method.hydrate
+/// [unmount]: #method.unmount
+pub struct Mounted {
+    node: web::Node,
+    listeners: Vec<EventListenerHandle>,
+}
+
+impl Mounted {
+    /// The root node of the tree.
+    pub fn node(&self) -> &web::Node {
+        &self.node
+    }
+
+    /// Split the tree into its root node and its event listeners.
+    pub fn into_parts(self) -> (web::Node, Vec<EventListenerHandle>) {
+        (self.node, self.listeners)
+    }
+
+    /// Remove the event listeners, and take the root node out of its parent
+    /// if it has one, returning it.
+    pub fn unmount(self) -> web::Node {
+        for listener in self.listeners {
+            listener.remove();
+        }
+        if let Some(parent) = self.node.parent_node() {
+            // This only fails if the node isn't a child of its parent.
+            let _ = parent.remove_child(&self.node);
+        }
+        self.node
+    }
+
+    /// Build a new tree from `vnode` in place of this one, removing the old
+    /// tree's event listeners and replacing its root node in its parent.
+    ///
+    /// Event handlers aren't used up by being attached, so this can be given
+    /// a `VNode` of the same tree again after it's been changed.
+    pub fn rebuild(
+        &mut self,
+        document: &web::Document,
+        vnode: VNode<'_, Stdweb>,
+    ) -> Result<(), web::error::InvalidCharacterError> {
+        let mounted = Stdweb::build(document, vnode)?;
+        for listener in self.listeners.drain(..) {
+            listener.remove();
+        }
+        if let Some(parent) = self.node.parent_node() {
+            // This only fails if the new node can't go where the old one was,
+            // which a freshly built one always can.
+            let _ = parent.replace_child(&mounted.node, &self.node);
+        }
+        *self = mounted;
+        Ok(())
+    }
+}
+
 impl Stdweb {
-    pub fn install_handlers(target: &mut Element, handlers: &mut Events) {
+    /// Attach every handler to an element, returning their listeners.
+    pub fn install_handlers(
+        target: &mut Element,
+        handlers: &mut Events,
+    ) -> Vec<EventListenerHandle> {
+        let mut listeners = Vec::new();
         for_events!(handler in handlers => {
-            handler.attach(target);
+            listeners.push(handler.attach(target));
         });
+        listeners
     }
 
+    /// Create a comment node, which stdweb has no binding for.
+    fn create_comment(document: &web::Document, text: &str) -> web::Node {
+        js!( return @{document}.createComment(@{text}); )
+            .try_into()
+            .expect("createComment didn't return a node")
+    }
+
+    /// Build DOM nodes from a [`VNode`][VNode] tree, attaching its event
+    /// handlers.
+    ///
+    /// [VNode]: ../../dom/enum.VNode.html
     pub fn build(
         document: &web::Document,
         vnode: VNode<'_, Stdweb>,
+    ) -> Result<Mounted, web::error::InvalidCharacterError> {
+        let mut listeners = Vec::new();
+        let node = Stdweb::build_node(document, vnode, &mut listeners)?;
+        Ok(Mounted { node, listeners })
+    }
+
+    fn build_node(
+        document: &web::Document,
+        vnode: VNode<'_, Stdweb>,
+        listeners: &mut Vec<EventListenerHandle>,
     ) -> Result<web::Node, web::error::InvalidCharacterError> {
         match vnode {
             VNode::Text(text) => Ok(document.create_text_node(text).into()),
             VNode::UnsafeText(text) => Ok(document.create_text_node(text).into()),
+            VNode::Comment(text) => Ok(Stdweb::create_comment(document, text)),
             VNode::Element(element) => {
                 let mut node = document.create_element(element.name)?;
                 for (key, value) in element.attributes {
-                    node.set_attribute(key, &value)?;
+                    node.set_attribute(&key, &value)?;
                 }
-                Stdweb::install_handlers(&mut node, element.events);
+                listeners.extend(Stdweb::install_handlers(&mut node, element.events));
                 for child in element.children {
-                    let child_node = Stdweb::build(document, child)?;
+                    let child_node = Stdweb::build_node(document, child, listeners)?;
                     node.append_child(&child_node);
                 }
                 Ok(node.into())
             }
         }
     }
+
+    /// Hydrate DOM nodes rendered on the server from the same tree as
+    /// `vnode`, attaching its event handlers to the existing elements.
+    ///
+    /// Nothing is rebuilt: the returned [`Mounted`][Mounted] holds `node`
+    /// and the listeners attached to it, along with the differences between
+    /// the DOM and `vnode`. See the [`hydrate`][hydrate] module for how
+    /// they're compared.
+    ///
+    /// [Mounted]: struct.Mounted.html
+    /// [hydrate]: ../../hydrate/index.html
+    pub fn hydrate(node: &web::Node, vnode: VNode<'_, Stdweb>) -> (Mounted, Vec<Mismatch>) {
+        let mut listeners = Vec::new();
+        let mismatches = hydrate::hydrate(node, vnode, |node, events: &mut Events| {
+            if let Ok(mut element) = node.clone().try_into() {
+                listeners.extend(Stdweb::install_handlers(&mut element, events));
+            }
+        });
+        let mounted = Mounted {
+            node: node.clone(),
+            listeners,
+        };
+        (mounted, mismatches)
+    }
 }
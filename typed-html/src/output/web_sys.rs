@@ -0,0 +1,400 @@
+//! DOM output using the web-sys crate.
+//!
+//! [`WebSys::build()`][build] turns a [`VNode`][VNode] tree into real DOM
+//! nodes, attaching its event handlers, which are closures taking the
+//! `web_sys` type for their event, eg. [`MouseEvent`][MouseEvent] for
+//! `onclick`. The listeners stay attached for as long as the returned
+//! [`Tree`][Tree] is kept.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use typed_html::html;
+//! # use typed_html::dom::Node;
+//! # use typed_html::output::web_sys::WebSys;
+//! # fn main() {
+//! let mut doc = html!(
+//!     <button onclick={ |event: web_sys::MouseEvent| event.prevent_default() }>
+//!         "Click me"
+//!     </button>
+//!     : WebSys
+//! );
+//! let document = web_sys::window().unwrap().document().unwrap();
+//! let body = document.body().unwrap();
+//! let tree = WebSys::build(&document, doc.vnode()).unwrap();
+//! body.append_child(tree.node()).unwrap();
+//! // Keep the listeners attached for the life of the page.
+//! tree.forget();
+//! # }
+//! ```
+//!
+//! [build]: struct.WebSys.html#method.build
+//! [VNode]: ../../dom/enum.VNode.html
+//! [MouseEvent]: https://docs.rs/web-sys/latest/web_sys/struct.MouseEvent.html
+//! [Tree]: struct.Tree.html
+
+use std::fmt::{Display, Error, Formatter};
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex, TryLockError};
+
+use web_sys::wasm_bindgen::closure::Closure;
+use web_sys::wasm_bindgen::{JsCast, JsValue};
+use web_sys::{
+    Document, DragEvent, Element, FocusEvent, HtmlTemplateElement, InputEvent, KeyboardEvent,
+    MouseEvent, ProgressEvent, SubmitEvent, UiEvent, WheelEvent,
+};
+
+use crate::dom::VNode;
+use crate::events::EventHandler;
+use crate::hydrate::{self, HydrateNode, Mismatch, NodeKind};
+use crate::OutputType;
+
+/// DOM output using the web-sys crate
+pub struct WebSys;
+impl OutputType for WebSys {
+    type Events = Events;
+    type EventTarget = EventTarget;
+    type EventListenerHandle = EventListener;
+}
+
+/// The element an event handler is being attached to, and the name of the
+/// event, without the `on` prefix.
+pub struct EventTarget {
+    pub element: Element,
+    pub event: &'static str,
+}
+
+/// An event listener attached to an element, which is removed when this is
+/// dropped.
+pub struct EventListener {
+    target: Element,
+    event: &'static str,
+    /// The listener's closure, which is only `None` once it's been forgotten.
+    closure: Option<Closure<dyn FnMut(web_sys::Event)>>,
+}
+
+impl EventListener {
+    /// Leave the listener attached for the life of the page.
+    pub fn forget(mut self) {
+        if let Some(closure) = self.closure.take() {
+            closure.forget();
+        }
+    }
+}
+
+impl Drop for EventListener {
+    fn drop(&mut self) {
+        if let Some(closure) = &self.closure {
+            // This only fails if the target isn't an event target, which it is.
+            let _ = self
+                .target
+                .remove_event_listener_with_callback(self.event, closure.as_ref().unchecked_ref());
+        }
+    }
+}
+
+macro_rules! declare_events {
+    ($($name:ident : $type:ty ,)*) => {
+        /// Container type for DOM events.
+        pub struct Events {
+            $(
+                pub $name: Option<Box<dyn EventHandler<WebSys, $type> + Send>>,
+            )*
+        }
+
+        impl Default for Events {
+            fn default() -> Self {
+                Events {
+                    $(
+                        $name: None,
+                    )*
+                }
+            }
+        }
+
+        impl Events {
+            /// Attach every handler to an element.
+            fn attach(&mut self, element: &Element) -> Vec<EventListener> {
+                let mut listeners = Vec::new();
+                $(
+                    if let Some(ref mut handler) = self.$name {
+                        let mut target = EventTarget {
+                            element: element.clone(),
+                            event: stringify!($name),
+                        };
+                        listeners.push(handler.attach(&mut target));
+                    }
+                )*
+                listeners
+            }
+        }
+    }
+}
+
+// Events without a more specific type in the HTML standard are plain
+// `web_sys::Event`s.
+declare_events! {
+    abort: web_sys::Event,
+    autocomplete: web_sys::Event,
+    autocompleteerror: web_sys::Event,
+    blur: FocusEvent,
+    cancel: web_sys::Event,
+    canplay: web_sys::Event,
+    canplaythrough: web_sys::Event,
+    change: web_sys::Event,
+    click: MouseEvent,
+    close: web_sys::Event,
+    contextmenu: MouseEvent,
+    cuechange: web_sys::Event,
+    dblclick: MouseEvent,
+    drag: DragEvent,
+    dragend: DragEvent,
+    dragenter: DragEvent,
+    dragexit: DragEvent,
+    dragleave: DragEvent,
+    dragover: DragEvent,
+    dragstart: DragEvent,
+    drop: DragEvent,
+    durationchange: web_sys::Event,
+    emptied: web_sys::Event,
+    ended: web_sys::Event,
+    error: web_sys::Event,
+    focus: FocusEvent,
+    input: InputEvent,
+    invalid: web_sys::Event,
+    keydown: KeyboardEvent,
+    keypress: KeyboardEvent,
+    keyup: KeyboardEvent,
+    load: web_sys::Event,
+    loadeddata: web_sys::Event,
+    loadedmetadata: web_sys::Event,
+    loadstart: ProgressEvent,
+    mousedown: MouseEvent,
+    mouseenter: MouseEvent,
+    mouseleave: MouseEvent,
+    mousemove: MouseEvent,
+    mouseout: MouseEvent,
+    mouseover: MouseEvent,
+    mouseup: MouseEvent,
+    mousewheel: WheelEvent,
+    pause: web_sys::Event,
+    play: web_sys::Event,
+    playing: web_sys::Event,
+    progress: ProgressEvent,
+    ratechange: web_sys::Event,
+    reset: web_sys::Event,
+    resize: UiEvent,
+    scroll: web_sys::Event,
+    seeked: web_sys::Event,
+    seeking: web_sys::Event,
+    select: web_sys::Event,
+    show: web_sys::Event,
+    sort: web_sys::Event,
+    stalled: web_sys::Event,
+    submit: SubmitEvent,
+    suspend: web_sys::Event,
+    timeupdate: web_sys::Event,
+    toggle: web_sys::Event,
+    volumechange: web_sys::Event,
+    waiting: web_sys::Event,
+}
+
+impl Display for Events {
+    fn fmt(&self, _f: &mut Formatter) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Wrapper type for closures as event handlers.
+///
+/// The closure is shared by every listener made from it, so a tree can be
+/// built more than once. While it's running, it isn't called again: an event
+/// it triggers itself, as by calling `click()` inside an `onclick` handler,
+/// is skipped rather than calling it re-entrantly.
+pub struct EFn<F, E>(Arc<Mutex<F>>, PhantomData<fn(E)>);
+
+impl<F, E> EFn<F, E>
+where
+    F: FnMut(E) + 'static + Send,
+{
+    pub fn new(f: F) -> Self {
+        EFn(Arc::new(Mutex::new(f)), PhantomData)
+    }
+}
+
+impl<F, E> From<F> for Box<dyn EventHandler<WebSys, E> + Send>
+where
+    F: FnMut(E) + 'static + Send,
+    E: JsCast + 'static,
+{
+    fn from(f: F) -> Self {
+        Box::new(EFn::new(f))
+    }
+}
+
+impl<F, E> EventHandler<WebSys, E> for EFn<F, E>
+where
+    F: FnMut(E) + 'static + Send,
+    E: JsCast + 'static,
+{
+    fn attach(&mut self, target: &mut EventTarget) -> EventListener {
+        let handler = self.0.clone();
+        let closure = Closure::new(move |event: web_sys::Event| {
+            let mut handler = match handler.try_lock() {
+                Ok(handler) => handler,
+                Err(TryLockError::Poisoned(err)) => err.into_inner(),
+                Err(TryLockError::WouldBlock) => return,
+            };
+            (*handler)(event.unchecked_into());
+        });
+        // This only fails if the target isn't an event target, which it is.
+        let _ = target
+            .element
+            .add_event_listener_with_callback(target.event, closure.as_ref().unchecked_ref());
+        EventListener {
+            target: target.element.clone(),
+            event: target.event,
+            closure: Some(closure),
+        }
+    }
+
+    fn render(&self) -> Option<String> {
+        None
+    }
+}
+
+/// A DOM tree built by [`WebSys::build()`][build] or hydrated by
+/// [`WebSys::hydrate()`][hydrate], with the event listeners attached to it,
+/// which are removed when it's dropped.
+///
+/// [build]: struct.WebSys.html#method.build
+/// [hydrate]: struct.WebSys.html#method.hydrate
+pub struct Tree {
+    node: web_sys::Node,
+    listeners: Vec<EventListener>,
+}
+
+impl Tree {
+    /// The root node of the tree.
+    pub fn node(&self) -> &web_sys::Node {
+        &self.node
+    }
+
+    /// Split the tree into its root node and its event listeners.
+    pub fn into_parts(self) -> (web_sys::Node, Vec<EventListener>) {
+        (self.node, self.listeners)
+    }
+
+    /// Leave the event listeners attached for the life of the page, and
+    /// return the root node.
+    pub fn forget(self) -> web_sys::Node {
+        for listener in self.listeners {
+            listener.forget();
+        }
+        self.node
+    }
+}
+
+impl HydrateNode for web_sys::Node {
+    fn kind(&self) -> NodeKind {
+        match self.node_type() {
+            web_sys::Node::ELEMENT_NODE => NodeKind::Element(self.node_name()),
+            web_sys::Node::TEXT_NODE => NodeKind::Text(self.text_content().unwrap_or_default()),
+            web_sys::Node::COMMENT_NODE => {
+                NodeKind::Comment(self.text_content().unwrap_or_default())
+            }
+            _ => NodeKind::Other,
+        }
+    }
+
+    fn children(&self) -> Vec<Self> {
+        let nodes = self.child_nodes();
+        (0..nodes.length())
+            .filter_map(|index| nodes.item(index))
+            .collect()
+    }
+
+    fn attributes(&self) -> Vec<(String, String)> {
+        let element = match self.dyn_ref::<Element>() {
+            Some(element) => element,
+            None => return Vec::new(),
+        };
+        element
+            .get_attribute_names()
+            .iter()
+            .filter_map(|name| name.as_string())
+            .filter_map(|name| element.get_attribute(&name).map(|value| (name, value)))
+            .collect()
+    }
+}
+
+impl WebSys {
+    /// Build DOM nodes from a [`VNode`][VNode] tree, attaching its event
+    /// handlers.
+    ///
+    /// Unsafe text is parsed as HTML, and may become any number of nodes
+    /// inside a `DocumentFragment`. Attributes with empty values are left
+    /// out, as they are when rendering to a string.
+    ///
+    /// [VNode]: ../../dom/enum.VNode.html
+    pub fn build(document: &Document, vnode: VNode<'_, WebSys>) -> Result<Tree, JsValue> {
+        let mut listeners = Vec::new();
+        let node = WebSys::build_node(document, vnode, &mut listeners)?;
+        Ok(Tree { node, listeners })
+    }
+
+    fn build_node(
+        document: &Document,
+        vnode: VNode<'_, WebSys>,
+        listeners: &mut Vec<EventListener>,
+    ) -> Result<web_sys::Node, JsValue> {
+        match vnode {
+            VNode::Text(text) => Ok(document.create_text_node(text).into()),
+            VNode::UnsafeText(html) => {
+                let template: HtmlTemplateElement =
+                    document.create_element("template")?.unchecked_into();
+                template.set_inner_html(html);
+                Ok(template.content().into())
+            }
+            VNode::Comment(text) => Ok(document.create_comment(text).into()),
+            VNode::Element(element) => {
+                let node = document.create_element(element.name)?;
+                for (key, value) in element.attributes {
+                    if !value.is_empty() {
+                        node.set_attribute(&key, &value)?;
+                    }
+                }
+                listeners.extend(element.events.attach(&node));
+                for child in element.children {
+                    let child_node = WebSys::build_node(document, child, listeners)?;
+                    node.append_child(&child_node)?;
+                }
+                Ok(node.into())
+            }
+        }
+    }
+
+    /// Hydrate DOM nodes rendered on the server from the same tree as
+    /// `vnode`, attaching its event handlers to the existing elements.
+    ///
+    /// Nothing is rebuilt: the returned [`Tree`][Tree] holds `node` and the
+    /// listeners attached to it, along with the differences between the DOM
+    /// and `vnode`. See the [`hydrate`][hydrate] module for how they're
+    /// compared.
+    ///
+    /// [Tree]: struct.Tree.html
+    /// [hydrate]: ../../hydrate/index.html
+    pub fn hydrate(node: &web_sys::Node, vnode: VNode<'_, WebSys>) -> (Tree, Vec<Mismatch>) {
+        let mut listeners = Vec::new();
+        let mismatches = hydrate::hydrate(node, vnode, |node, events: &mut Events| {
+            if let Some(element) = node.dyn_ref::<Element>() {
+                listeners.extend(events.attach(element));
+            }
+        });
+        let tree = Tree {
+            node: node.clone(),
+            listeners,
+        };
+        (tree, mismatches)
+    }
+}
@@ -0,0 +1,1137 @@
+//! Parsing HTML into typed DOM trees.
+//!
+//! [`parse()`][parse] reads an HTML document into the same element structs
+//! the [`html!`][html] macro builds, converting attribute values through
+//! the same [`FromStr`][FromStr] implementations and enforcing the same
+//! content model. Where the macro would refuse to compile, the parser returns
+//! a [`ParseError`][ParseError] pointing at the offending line and column.
+//!
+//! The parser is strict: unlike a browser, it doesn't close elements
+//! implicitly or move misplaced elements around, so every non-void element
+//! needs a closing tag and every child must be allowed where it appears.
+//! Comments and doctypes are skipped. Event handler attributes can't be
+//! parsed, as there's nothing to convert them into.
+//!
+//! # Examples
+//!
+//! ```
+//! # use typed_html::dom::DOMTree;
+//! # use typed_html::parse::parse;
+//! let doc: DOMTree<String> = parse(r#"<p class="greeting">Hello <em>Joe</em></p>"#).unwrap();
+//! assert_eq!(r#"<p class="greeting">Hello <em>Joe</em></p>"#, doc.to_string());
+//!
+//! let error = parse::<String>("<ul>\n  <p>Hello Joe</p>\n</ul>").err().unwrap();
+//! assert_eq!("2:3: <p> is not allowed inside <ul>", error.to_string());
+//! ```
+//!
+//! [parse]: fn.parse.html
+//! [html]: ../macro.html.html
+//! [FromStr]: https://doc.rust-lang.org/std/str/trait.FromStr.html
+//! [ParseError]: struct.ParseError.html
+
+use std::fmt::{Display, Error, Formatter};
+
+use htmlescape::decode_html;
+
+use crate::dom::{AttributeError, DOMTree, Element, Node, OwnedVNode, TextNode};
+use crate::elements::{parse_element, ChildOrder, ParsedNode, TEXT_NODE};
+use crate::OutputType;
+
+/// Elements which never have children or a closing tag.
+pub(crate) static VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Elements whose content is text which isn't parsed for entities.
+pub(crate) static RAW_TEXT_ELEMENTS: &[&str] = &["script", "style"];
+
+/// Elements whose content is text which is parsed for entities.
+static ESCAPABLE_RAW_TEXT_ELEMENTS: &[&str] = &["textarea", "title"];
+
+/// A position in the parser's input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Location {
+    /// The byte offset from the start of the input.
+    pub offset: usize,
+    /// The line number, counting from 1.
+    pub line: usize,
+    /// The column in characters, counting from 1.
+    pub column: usize,
+}
+
+impl Default for Location {
+    fn default() -> Self {
+        Location {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// The error returned when the parser's input isn't a valid document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// Where in the input the problem was found.
+    pub location: Location,
+    pub kind: ParseErrorKind,
+}
+
+/// The kinds of [`ParseError`][ParseError].
+///
+/// [ParseError]: struct.ParseError.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The input isn't well formed HTML.
+    Syntax(String),
+    /// There's no element with this name.
+    UnknownElement(String),
+    /// The element has no attribute with this name.
+    UnknownAttribute {
+        element: &'static str,
+        attribute: String,
+    },
+    /// The attribute's value couldn't be converted into its type.
+    InvalidAttribute {
+        element: &'static str,
+        attribute: String,
+        reason: String,
+    },
+    /// The attribute is an event handler, which can't be parsed.
+    EventHandler {
+        element: &'static str,
+        attribute: String,
+    },
+    /// Unsafe text, which can't be checked, in an
+    /// [`OwnedVNode`][OwnedVNode].
+    ///
+    /// [OwnedVNode]: ../dom/enum.OwnedVNode.html
+    UnsafeText,
+    /// A closing tag with no matching opening tag.
+    UnexpectedClosingTag(String),
+    /// A closing tag which doesn't match the currently open element.
+    MismatchedClosingTag { expected: String, found: String },
+    /// The input ended before the element was closed.
+    UnclosedElement(String),
+    /// The child isn't allowed inside its parent, or at the top level of a
+    /// fragment if there is no parent.
+    NotAllowed {
+        child: &'static str,
+        parent: Option<&'static str>,
+    },
+    /// A required child is missing, or something else is in its place.
+    MissingChild {
+        parent: &'static str,
+        child: &'static str,
+    },
+    /// The children don't follow the order required by their parent. `child`
+    /// is the first one out of place, or `None` if the children stop short.
+    ChildOrder {
+        parent: &'static str,
+        child: Option<&'static str>,
+        order: &'static ChildOrder,
+    },
+    /// The input contains no nodes.
+    Empty,
+    /// A document has more than one root node.
+    MultipleRoots,
+}
+
+fn fmt_node(f: &mut Formatter, name: &str) -> Result<(), Error> {
+    if name == TEXT_NODE {
+        f.write_str("text")
+    } else {
+        write!(f, "<{}>", name)
+    }
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            ParseErrorKind::Syntax(message) => f.write_str(message),
+            ParseErrorKind::UnknownElement(name) => write!(f, "unknown element <{}>", name),
+            ParseErrorKind::UnknownAttribute { element, attribute } => {
+                write!(f, "<{}> has no attribute {:?}", element, attribute)
+            }
+            ParseErrorKind::InvalidAttribute {
+                element,
+                attribute,
+                reason,
+            } => write!(
+                f,
+                "invalid value for attribute {:?} on <{}>: {}",
+                attribute, element, reason
+            ),
+            ParseErrorKind::EventHandler { element, attribute } => write!(
+                f,
+                "event handler {:?} on <{}> can't be parsed",
+                attribute, element
+            ),
+            ParseErrorKind::UnsafeText => f.write_str("unsafe text can't be converted"),
+            ParseErrorKind::UnexpectedClosingTag(name) => {
+                write!(f, "unexpected closing tag </{}>", name)
+            }
+            ParseErrorKind::MismatchedClosingTag { expected, found } => {
+                write!(f, "expected </{}>, found </{}>", expected, found)
+            }
+            ParseErrorKind::UnclosedElement(name) => write!(f, "<{}> is never closed", name),
+            ParseErrorKind::NotAllowed { child, parent } => {
+                fmt_node(f, child)?;
+                match parent {
+                    Some(parent) => write!(f, " is not allowed inside <{}>", parent),
+                    None => f.write_str(" is not allowed here"),
+                }
+            }
+            ParseErrorKind::MissingChild { parent, child } => {
+                write!(f, "<{}> is missing its required <{}> child", parent, child)
+            }
+            ParseErrorKind::ChildOrder {
+                parent,
+                child: Some(child),
+                order,
+            } => {
+                fmt_node(f, child)?;
+                write!(
+                    f,
+                    " is not allowed here: the children of <{}> must follow the order {}",
+                    parent, order
+                )
+            }
+            ParseErrorKind::ChildOrder {
+                parent,
+                child: None,
+                order,
+            } => write!(
+                f,
+                "<{}> is missing children: they must follow the order {}",
+                parent, order
+            ),
+            ParseErrorKind::Empty => f.write_str("no content"),
+            ParseErrorKind::MultipleRoots => f.write_str("a document can only have one root node"),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(f, "{}: {}", self.location, self.kind)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Conversion from a [`ParsedNode`][ParsedNode] into a node type or content
+/// category.
+///
+/// This is implemented for every element, for [`TextNode`][TextNode], and for
+/// `dyn Node<T>` and each of the content category traits, and is used to
+/// check that a parsed child is allowed where it appears.
+///
+/// [ParsedNode]: ../elements/enum.ParsedNode.html
+/// [TextNode]: ../dom/struct.TextNode.html
+pub trait FromParsed<T: OutputType + Send + 'static> {
+    /// Whether text nodes convert into this type.
+    const ACCEPTS_TEXT: bool;
+
+    /// Convert the node, or give it back if it isn't of this type.
+    fn from_parsed(node: ParsedNode<T>) -> Result<Box<Self>, ParsedNode<T>>;
+}
+
+impl<T: OutputType + Send + 'static> FromParsed<T> for TextNode<T> {
+    const ACCEPTS_TEXT: bool = true;
+
+    fn from_parsed(node: ParsedNode<T>) -> Result<Box<Self>, ParsedNode<T>> {
+        match node {
+            ParsedNode::Text(node) => Ok(node),
+            node => Err(node),
+        }
+    }
+}
+
+impl<T: OutputType + Send + 'static> FromParsed<T> for dyn Node<T> {
+    const ACCEPTS_TEXT: bool = true;
+
+    fn from_parsed(node: ParsedNode<T>) -> Result<Box<Self>, ParsedNode<T>> {
+        Ok(node.into_node())
+    }
+}
+
+/// A node as it appears in the input, before it's checked against the
+/// element definitions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RawNode {
+    Element(RawElement),
+    Text { text: String, location: Location },
+}
+
+/// An element as it appears in the input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RawElement {
+    /// The element name, in lower case.
+    pub name: String,
+    pub attributes: Vec<RawAttribute>,
+    pub children: Vec<RawNode>,
+    pub location: Location,
+}
+
+/// An attribute as it appears in the input, with its character references
+/// decoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RawAttribute {
+    /// The attribute name, in lower case.
+    pub name: String,
+    /// The attribute value, which is empty if there wasn't one.
+    pub value: String,
+    pub location: Location,
+}
+
+impl RawNode {
+    /// The location of the node in the input.
+    pub fn location(&self) -> Location {
+        match self {
+            RawNode::Element(element) => element.location,
+            RawNode::Text { location, .. } => *location,
+        }
+    }
+
+    /// Test whether the node is text consisting only of whitespace.
+    pub fn is_whitespace(&self) -> bool {
+        match self {
+            RawNode::Text { text, .. } => text.trim().is_empty(),
+            _ => false,
+        }
+    }
+
+    fn build<T: OutputType + Send + 'static>(self) -> Result<ParsedChild<T>, ParseError> {
+        match self {
+            RawNode::Text { text, location } => Ok(ParsedChild {
+                node: ParsedNode::Text(Box::new(TextNode::new(text))),
+                location,
+            }),
+            RawNode::Element(element) => {
+                let children = element
+                    .children
+                    .into_iter()
+                    .map(RawNode::build)
+                    .collect::<Result<_, _>>()?;
+                match parse_element(
+                    &element.name,
+                    element.location,
+                    element.attributes,
+                    children,
+                ) {
+                    Some(node) => Ok(ParsedChild {
+                        node: node?,
+                        location: element.location,
+                    }),
+                    None => Err(ParseError {
+                        location: element.location,
+                        kind: ParseErrorKind::UnknownElement(element.name),
+                    }),
+                }
+            }
+        }
+    }
+}
+
+impl RawAttribute {
+    pub(crate) fn apply<T, E>(
+        self,
+        element_name: &'static str,
+        element: &mut E,
+    ) -> Result<(), ParseError>
+    where
+        T: OutputType + Send + 'static,
+        E: Element<T>,
+    {
+        let error = |kind| ParseError {
+            location: self.location,
+            kind,
+        };
+        if self.name.len() > 2 && self.name.starts_with("on") {
+            return Err(error(ParseErrorKind::EventHandler {
+                element: element_name,
+                attribute: self.name.clone(),
+            }));
+        }
+        element
+            .set_attribute(&self.name, &self.value)
+            .map_err(|err| match err {
+                AttributeError::Unknown => error(ParseErrorKind::UnknownAttribute {
+                    element: element_name,
+                    attribute: self.name.clone(),
+                }),
+                AttributeError::Invalid(reason) => error(ParseErrorKind::InvalidAttribute {
+                    element: element_name,
+                    attribute: self.name.clone(),
+                    reason,
+                }),
+            })
+    }
+}
+
+/// A node which has been parsed, but not yet added to its parent.
+pub(crate) struct ParsedChild<T: OutputType + Send + 'static> {
+    node: ParsedNode<T>,
+    location: Location,
+}
+
+impl<T: OutputType + Send + 'static> ParsedChild<T> {
+    fn is_whitespace(&self) -> bool {
+        match &self.node {
+            ParsedNode::Text(text) => text.as_str().trim().is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Convert the node into the type its parent expects.
+    pub(crate) fn convert<C>(self, parent: Option<&'static str>) -> Result<Box<C>, ParseError>
+    where
+        C: ?Sized + FromParsed<T>,
+    {
+        let location = self.location;
+        C::from_parsed(self.node).map_err(|node| ParseError {
+            location,
+            kind: ParseErrorKind::NotAllowed {
+                child: node.name(),
+                parent,
+            },
+        })
+    }
+}
+
+/// The children of an element being built by the parser, handed out first as
+/// its required children, picked out by name along with where they were
+/// among the others, and then as its optional ones.
+pub(crate) struct Children<T: OutputType + Send + 'static> {
+    parent: &'static str,
+    location: Location,
+    required: std::slice::Iter<'static, &'static str>,
+    // Where the last required child was, since the next one has to come after it.
+    next: usize,
+    children: Vec<ParsedChild<T>>,
+}
+
+impl<T: OutputType + Send + 'static> Children<T> {
+    /// Prepare the children of an element of type `E`, dropping whitespace
+    /// where the element can't have text, and checking their order if it
+    /// requires one.
+    pub(crate) fn new<E: Element<T>>(
+        parent: &'static str,
+        location: Location,
+        children: Vec<ParsedChild<T>>,
+        accepts_text: bool,
+    ) -> Result<Self, ParseError> {
+        let required = E::required_children();
+        let mut elements = 0;
+        let children: Vec<ParsedChild<T>> = children
+            .into_iter()
+            .filter(|child| {
+                if child.is_whitespace() {
+                    accepts_text && elements >= required.len()
+                } else {
+                    elements += 1;
+                    true
+                }
+            })
+            .collect();
+
+        if let Some(order) = E::child_order() {
+            let names: Vec<&str> = children.iter().map(|child| child.node.name()).collect();
+            if let Err(index) = order.check(&names) {
+                let child = children.get(index);
+                return Err(ParseError {
+                    location: child.map(|child| child.location).unwrap_or(location),
+                    kind: ParseErrorKind::ChildOrder {
+                        parent,
+                        child: child.map(|child| child.node.name()),
+                        order,
+                    },
+                });
+            }
+        }
+
+        Ok(Children {
+            parent,
+            location,
+            required: required.iter(),
+            next: 0,
+            children,
+        })
+    }
+
+    /// Take the next required child, and the number of optional children
+    /// which came before it.
+    pub(crate) fn required<C>(&mut self) -> Result<(Box<C>, usize), ParseError>
+    where
+        C: ?Sized + FromParsed<T>,
+    {
+        let name = self
+            .required
+            .next()
+            .expect("more required children requested than the element declares");
+        let index = self.children[self.next..]
+            .iter()
+            .position(|child| child.node.name() == *name)
+            .map(|index| self.next + index);
+        let (location, node) = match index.map(|index| self.children.remove(index)) {
+            Some(child) => (child.location, C::from_parsed(child.node)),
+            // Point at the child if it's out of order, or else at whatever is
+            // where it should be.
+            None => (
+                self.children
+                    .iter()
+                    .find(|child| child.node.name() == *name)
+                    .or_else(|| self.children.get(self.next))
+                    .map_or(self.location, |child| child.location),
+                Err(ParsedNode::Text(Box::new(TextNode::new("")))),
+            ),
+        };
+        let node = node.map_err(|_| ParseError {
+            location,
+            kind: ParseErrorKind::MissingChild {
+                parent: self.parent,
+                child: name,
+            },
+        })?;
+        self.next = index.unwrap_or(self.next);
+        Ok((node, self.next))
+    }
+
+    /// Check that there are no children left, for elements which only have
+    /// required children.
+    pub(crate) fn finish(self) -> Result<(), ParseError> {
+        match self.children.into_iter().next() {
+            None => Ok(()),
+            Some(child) => Err(ParseError {
+                location: child.location,
+                kind: ParseErrorKind::NotAllowed {
+                    child: child.node.name(),
+                    parent: Some(self.parent),
+                },
+            }),
+        }
+    }
+}
+
+impl<T: OutputType + Send + 'static> IntoIterator for Children<T> {
+    type Item = ParsedChild<T>;
+    type IntoIter = std::vec::IntoIter<ParsedChild<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.children.into_iter()
+    }
+}
+
+/// Decode the character references in a string, leaving any `&` which
+/// doesn't start one as it is.
+fn decode_entities(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest[1..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '#'))
+            .map(|end| end + 1);
+        match end {
+            Some(end) if rest[end..].starts_with(';') => match decode_html(&rest[..=end]) {
+                Ok(decoded) => {
+                    out.push_str(&decoded);
+                    rest = &rest[end + 1..];
+                }
+                Err(_) => {
+                    out.push('&');
+                    rest = &rest[1..];
+                }
+            },
+            _ => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Find the first occurrence of the ASCII string `pattern` in `input`,
+/// ignoring ASCII case, without copying the input.
+fn find_ignore_case(input: &str, pattern: &str) -> Option<usize> {
+    let (input, pattern) = (input.as_bytes(), pattern.as_bytes());
+    let first = *pattern.first()?;
+    let mut start = 0;
+    while let Some(offset) = input[start..]
+        .iter()
+        .position(|byte| byte.eq_ignore_ascii_case(&first))
+    {
+        let index = start + offset;
+        if input[index..]
+            .get(..pattern.len())
+            .is_some_and(|candidate| candidate.eq_ignore_ascii_case(pattern))
+        {
+            return Some(index);
+        }
+        start = index + 1;
+    }
+    None
+}
+
+fn is_name_char(c: char) -> bool {
+    !(c.is_whitespace() || c == '/' || c == '>' || c == '=' || c == '"' || c == '\'' || c == '<')
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    location: Location,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Parser {
+            input,
+            location: Location::default(),
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.location.offset..]
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.location.offset >= self.input.len()
+    }
+
+    fn starts_with(&self, prefix: &str) -> bool {
+        let rest = self.rest().as_bytes();
+        rest.len() >= prefix.len() && rest[..prefix.len()].eq_ignore_ascii_case(prefix.as_bytes())
+    }
+
+    /// Move forward by `len` bytes, returning what was skipped.
+    fn advance(&mut self, len: usize) -> &'a str {
+        let skipped = &self.rest()[..len];
+        for c in skipped.chars() {
+            if c == '\n' {
+                self.location.line += 1;
+                self.location.column = 1;
+            } else {
+                self.location.column += 1;
+            }
+        }
+        self.location.offset += len;
+        skipped
+    }
+
+    /// Move forward while `pred` holds, returning what was skipped.
+    fn advance_while<F: Fn(char) -> bool>(&mut self, pred: F) -> &'a str {
+        let len = self
+            .rest()
+            .find(|c| !pred(c))
+            .unwrap_or_else(|| self.rest().len());
+        self.advance(len)
+    }
+
+    /// Move forward to the first occurrence of `pattern` (ignoring ASCII
+    /// case), returning what was skipped.
+    fn advance_until(&mut self, pattern: &str) -> Option<&'a str> {
+        let len = find_ignore_case(self.rest(), pattern)?;
+        Some(self.advance(len))
+    }
+
+    fn error<A>(&self, location: Location, kind: ParseErrorKind) -> Result<A, ParseError> {
+        Err(ParseError { location, kind })
+    }
+
+    fn syntax_error<A>(&self, message: &str) -> Result<A, ParseError> {
+        self.error(self.location, ParseErrorKind::Syntax(message.to_string()))
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), ParseError> {
+        if self.starts_with(token) {
+            self.advance(token.len());
+            Ok(())
+        } else {
+            self.syntax_error(&format!("expected {:?}", token))
+        }
+    }
+
+    /// Parse nodes until the closing tag of `parent`, or the end of the input
+    /// if there's no parent.
+    fn nodes(&mut self, parent: Option<(&str, Location)>) -> Result<Vec<RawNode>, ParseError> {
+        let mut nodes = Vec::new();
+        loop {
+            if self.is_at_end() {
+                return match parent {
+                    None => Ok(nodes),
+                    Some((name, location)) => {
+                        self.error(location, ParseErrorKind::UnclosedElement(name.to_string()))
+                    }
+                };
+            }
+            let location = self.location;
+            if self.starts_with("<!--") {
+                self.advance(4);
+                if self.advance_until("-->").is_none() {
+                    return self.syntax_error("unterminated comment");
+                }
+                self.advance(3);
+            } else if self.starts_with("<!") {
+                if self.advance_until(">").is_none() {
+                    return self.syntax_error("unterminated doctype");
+                }
+                self.advance(1);
+            } else if self.starts_with("</") {
+                self.advance(2);
+                let name = self.advance_while(is_name_char).to_ascii_lowercase();
+                self.advance_while(char::is_whitespace);
+                self.expect(">")?;
+                return match parent {
+                    Some((expected, _)) if expected == name => Ok(nodes),
+                    Some((expected, _)) => self.error(
+                        location,
+                        ParseErrorKind::MismatchedClosingTag {
+                            expected: expected.to_string(),
+                            found: name,
+                        },
+                    ),
+                    None => self.error(location, ParseErrorKind::UnexpectedClosingTag(name)),
+                };
+            } else if self.rest().starts_with('<')
+                && self.rest()[1..].starts_with(|c: char| c.is_ascii_alphabetic())
+            {
+                nodes.push(RawNode::Element(self.element()?));
+            } else {
+                // Text starting with a `<` which doesn't open a tag takes the
+                // `<` with it, and it may start with a multibyte character.
+                let first = self.rest().chars().next().map_or(0, char::len_utf8);
+                let text = match self.rest()[first..].find('<') {
+                    Some(len) => self.advance(len + first),
+                    None => self.advance(self.rest().len()),
+                };
+                nodes.push(RawNode::Text {
+                    text: decode_entities(text),
+                    location,
+                });
+            }
+        }
+    }
+
+    fn attributes(&mut self) -> Result<Vec<RawAttribute>, ParseError> {
+        let mut attributes: Vec<RawAttribute> = Vec::new();
+        loop {
+            self.advance_while(char::is_whitespace);
+            if self.is_at_end() {
+                return self.syntax_error("unterminated tag");
+            }
+            if self.starts_with(">") || self.starts_with("/>") {
+                return Ok(attributes);
+            }
+            let location = self.location;
+            let name = self.advance_while(is_name_char).to_ascii_lowercase();
+            if name.is_empty() {
+                return self.syntax_error("expected an attribute name");
+            }
+            self.advance_while(char::is_whitespace);
+            let value = if self.starts_with("=") {
+                self.advance(1);
+                self.advance_while(char::is_whitespace);
+                let value = if self.starts_with("\"") || self.starts_with("'") {
+                    let quote = self.advance(1);
+                    let value = match self.advance_until(quote) {
+                        Some(value) => value,
+                        None => return self.syntax_error("unterminated attribute value"),
+                    };
+                    self.advance(1);
+                    value
+                } else {
+                    self.advance_while(|c| !(c.is_whitespace() || c == '>'))
+                };
+                decode_entities(value)
+            } else {
+                String::new()
+            };
+            if attributes.iter().any(|attr| attr.name == name) {
+                return self.error(
+                    location,
+                    ParseErrorKind::Syntax(format!("duplicate attribute {:?}", name)),
+                );
+            }
+            attributes.push(RawAttribute {
+                name,
+                value,
+                location,
+            });
+        }
+    }
+
+    fn element(&mut self) -> Result<RawElement, ParseError> {
+        let location = self.location;
+        self.advance(1);
+        let name = self.advance_while(is_name_char).to_ascii_lowercase();
+        let attributes = self.attributes()?;
+        let self_closing = self.starts_with("/>");
+        self.advance(if self_closing { 2 } else { 1 });
+
+        let children = if self_closing || VOID_ELEMENTS.contains(&name.as_str()) {
+            Vec::new()
+        } else if RAW_TEXT_ELEMENTS.contains(&name.as_str())
+            || ESCAPABLE_RAW_TEXT_ELEMENTS.contains(&name.as_str())
+        {
+            let text_location = self.location;
+            let text = match self.advance_until(&format!("</{}", name)) {
+                Some(text) => text,
+                None => return self.error(location, ParseErrorKind::UnclosedElement(name)),
+            };
+            self.advance(name.len() + 2);
+            self.advance_while(char::is_whitespace);
+            self.expect(">")?;
+            let text = if RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+                text.to_string()
+            } else {
+                decode_entities(text)
+            };
+            if text.is_empty() {
+                Vec::new()
+            } else {
+                vec![RawNode::Text {
+                    text,
+                    location: text_location,
+                }]
+            }
+        } else {
+            self.nodes(Some((&name, location)))?
+        };
+
+        Ok(RawElement {
+            name,
+            attributes,
+            children,
+            location,
+        })
+    }
+}
+
+/// Parse an HTML document into a [`DOMTree`][DOMTree].
+///
+/// The document must have exactly one root node, which is usually an
+/// `<html>` element but can be any element, or text. Whitespace, comments
+/// and a doctype around it are ignored.
+///
+/// [DOMTree]: ../dom/type.DOMTree.html
+pub fn parse<T: OutputType + Send + 'static>(input: &str) -> Result<DOMTree<T>, ParseError> {
+    let mut parser = Parser::new(input);
+    let mut nodes = parser
+        .nodes(None)?
+        .into_iter()
+        .filter(|node| !node.is_whitespace());
+    match (nodes.next(), nodes.next()) {
+        (None, _) => parser.error(parser.location, ParseErrorKind::Empty),
+        (Some(root), None) => Ok(root.build()?.node.into_node()),
+        (Some(_), Some(second)) => parser.error(second.location(), ParseErrorKind::MultipleRoots),
+    }
+}
+
+/// Parse HTML into [`RawNode`][RawNode]s, checking only that it's well
+/// formed, not that the elements and attributes exist or that they're used
+/// correctly.
+///
+/// [RawNode]: enum.RawNode.html
+pub fn parse_raw(input: &str) -> Result<Vec<RawNode>, ParseError> {
+    Parser::new(input).nodes(None)
+}
+
+/// Convert a list of [`RawNode`][RawNode]s into nodes of type `C`, as
+/// [`parse_fragment()`][parse_fragment] does.
+///
+/// [RawNode]: enum.RawNode.html
+/// [parse_fragment]: fn.parse_fragment.html
+pub fn from_raw<T, C>(nodes: Vec<RawNode>) -> Result<Vec<Box<C>>, ParseError>
+where
+    T: OutputType + Send + 'static,
+    C: ?Sized + FromParsed<T>,
+{
+    nodes
+        .into_iter()
+        .filter(|node| C::ACCEPTS_TEXT || !node.is_whitespace())
+        .map(|node| node.build()?.convert(None))
+        .collect()
+}
+
+/// Convert an [`OwnedVNode`][OwnedVNode] into a node of type `C`, checking
+/// it the way [`parse()`][parse] checks a document.
+///
+/// Attribute values go through the same [`FromStr`][FromStr]
+/// implementations, and children must be allowed where they appear.
+/// Comments and keys are dropped, while event handlers and unsafe text are
+/// rejected, as neither can be checked. Errors are reported at line 1, column 1, as
+/// there's no input to point into.
+///
+/// # Examples
+///
+/// ```
+/// # use typed_html::dom::{Node, OwnedVElement, OwnedVNode};
+/// # use typed_html::parse::from_owned;
+/// let node = OwnedVNode::Element(OwnedVElement {
+///     name: "p",
+///     attributes: vec![("class".into(), "greeting".to_string())],
+///     key: None,
+///     events: Vec::new(),
+///     children: vec![OwnedVNode::Text("Hello Joe".to_string())],
+/// });
+/// let doc = from_owned::<String, dyn Node<String>>(node).unwrap();
+/// assert_eq!(r#"<p class="greeting">Hello Joe</p>"#, doc.to_string());
+/// ```
+///
+/// [OwnedVNode]: ../dom/enum.OwnedVNode.html
+/// [parse]: fn.parse.html
+/// [FromStr]: https://doc.rust-lang.org/std/str/trait.FromStr.html
+pub fn from_owned<T, C>(node: OwnedVNode) -> Result<Box<C>, ParseError>
+where
+    T: OutputType + Send + 'static,
+    C: ?Sized + FromParsed<T>,
+{
+    match owned_to_raw(node)? {
+        Some(node) => node.build()?.convert(None),
+        None => Err(ParseError {
+            location: Location::default(),
+            kind: ParseErrorKind::Empty,
+        }),
+    }
+}
+
+/// Convert an [`OwnedVNode`][OwnedVNode] into a [`RawNode`][RawNode], or
+/// `None` if it's a comment.
+///
+/// [OwnedVNode]: ../dom/enum.OwnedVNode.html
+/// [RawNode]: enum.RawNode.html
+fn owned_to_raw(node: OwnedVNode) -> Result<Option<RawNode>, ParseError> {
+    let location = Location::default();
+    match node {
+        OwnedVNode::Text(text) => Ok(Some(RawNode::Text { text, location })),
+        OwnedVNode::Comment(_) => Ok(None),
+        OwnedVNode::UnsafeText(_) => Err(ParseError {
+            location,
+            kind: ParseErrorKind::UnsafeText,
+        }),
+        OwnedVNode::Element(element) => {
+            let attributes = element
+                .attributes
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .chain(
+                    element
+                        .events
+                        .into_iter()
+                        .map(|(name, handler)| (format!("on{}", name), handler)),
+                )
+                .map(|(name, value)| RawAttribute {
+                    name,
+                    value,
+                    location,
+                })
+                .collect();
+            let mut children = Vec::new();
+            for child in element.children {
+                children.extend(owned_to_raw(child)?);
+            }
+            Ok(Some(RawNode::Element(RawElement {
+                name: element.name.to_string(),
+                attributes,
+                children,
+                location,
+            })))
+        }
+    }
+}
+
+/// Parse a fragment of HTML into a list of nodes of type `C`, which is
+/// usually a content category, eg. `dyn FlowContent<T>`.
+///
+/// Whitespace between the nodes is dropped unless `C` accepts text.
+///
+/// # Examples
+///
+/// ```
+/// # use typed_html::elements::PhrasingContent;
+/// # use typed_html::parse::parse_fragment;
+/// let nodes = parse_fragment::<String, dyn PhrasingContent<String>>(
+///     "Hello <b>Joe</b>!"
+/// ).unwrap();
+/// assert_eq!(3, nodes.len());
+///
+/// let error = parse_fragment::<String, dyn PhrasingContent<String>>("<div/>").err().unwrap();
+/// assert_eq!("1:1: <div> is not allowed here", error.to_string());
+/// ```
+pub fn parse_fragment<T, C>(input: &str) -> Result<Vec<Box<C>>, ParseError>
+where
+    T: OutputType + Send + 'static,
+    C: ?Sized + FromParsed<T>,
+{
+    from_raw(parse_raw(input)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate as typed_html;
+    use crate::dom::VNode;
+    use crate::elements::FlowContent;
+    use crate::html;
+    use std::borrow::Cow;
+
+    fn error(input: &str) -> String {
+        parse::<String>(input).map(|_| ()).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_parse_document() {
+        let input = r#"<!DOCTYPE html>
+            <html>
+                <head>
+                    <title>Hello &amp; welcome</title>
+                    <meta charset="utf-8">
+                    <script>init(1)</script>
+                </head>
+                <body id="main" class="page dark">
+                    <!-- the content -->
+                    <p data-greeting="hi">Hello <b>Joe</b> &lt;3</p>
+                    <input type=checkbox checked disabled="">
+                </body>
+            </html>"#;
+        let doc: DOMTree<String> = parse(input).unwrap();
+        let expected: DOMTree<String> = html!(
+            <html>
+                <head>
+                    <title>"Hello & welcome"</title>
+                    <meta charset="utf-8"/>
+                    <script>"init(1)"</script>
+                </head>
+                <body id="main" class="page dark">
+                    <p data-greeting="hi">"Hello "<b>"Joe"</b>" <3"</p>
+                    <input type="checkbox" checked=true disabled=true/>
+                </body>
+            </html>
+        );
+        let squash = |s: String| s.split_whitespace().collect::<Vec<_>>().join(" ");
+        assert_eq!(
+            squash(expected.to_string()).replace("> <", "><"),
+            squash(doc.to_string()).replace("> <", "><")
+        );
+    }
+
+    #[test]
+    fn test_parse_typed() {
+        let mut doc: DOMTree<String> =
+            parse(r#"<a href="/joe" rel="author nofollow">Joe</a>"#).unwrap();
+        match doc.vnode() {
+            VNode::Element(element) => {
+                assert_eq!("a", element.name);
+                assert_eq!(
+                    vec![
+                        (Cow::from("href"), "/joe".to_string()),
+                        (Cow::from("rel"), "author nofollow".to_string())
+                    ],
+                    element.attributes
+                );
+            }
+            _ => panic!("expected an element"),
+        }
+        let nodes = parse_fragment::<String, dyn FlowContent<String>>("<p>1</p> <p>2</p>").unwrap();
+        assert_eq!(3, nodes.len());
+    }
+
+    #[test]
+    fn test_parse_head() {
+        let doc: DOMTree<String> =
+            parse(r#"<head><meta charset="utf-8"><title>Hi</title></head>"#).unwrap();
+        assert_eq!(
+            r#"<head><meta charset="utf-8"/><title>Hi</title></head>"#,
+            doc.to_string()
+        );
+        let doc: DOMTree<String> =
+            parse(r#"<head><base href="/"><title>Hi</title></head>"#).unwrap();
+        let expected: DOMTree<String> = html!(<head><base href="/"/><title>"Hi"</title></head>);
+        assert_eq!(expected.to_string(), doc.to_string());
+        assert_eq!(
+            "1:1: <head> is missing children: they must follow the order \
+             [[_*, title, _*, base?, _*] | [_*, base, _*, title, _*]]",
+            error(r#"<head><meta charset="utf-8"></head>"#)
+        );
+        assert_eq!(
+            "1:7: <html> is missing its required <body> child",
+            error("<html><body></body><head><title>Hi</title></head></html>")
+        );
+    }
+
+    #[test]
+    fn test_parse_non_ascii() {
+        let doc: DOMTree<String> = parse("<p>élan <b>ü</b>ñ</p>").unwrap();
+        assert_eq!("<p>élan <b>ü</b>ñ</p>", doc.to_string());
+        let nodes = parse_raw("élan").unwrap();
+        assert_eq!(1, nodes.len());
+        let nodes = parse_raw("<p>x</p>ü").unwrap();
+        assert_eq!(2, nodes.len());
+        let doc: DOMTree<String> = parse("<p>Ünïcödé<!-- é --><SCRIPT>ü()</Script></p>").unwrap();
+        assert_eq!("<p>Ünïcödé<script>ü()</script></p>", doc.to_string());
+    }
+
+    #[test]
+    fn test_find_ignore_case() {
+        assert_eq!(Some(5), find_ignore_case("élé</ScRiPt>", "</script"));
+        assert_eq!(Some(2), find_ignore_case("---->", "-->"));
+        assert_eq!(None, find_ignore_case("</scrip", "</script"));
+        assert_eq!(None, find_ignore_case("", ">"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            "1:1: unknown element <frameset>",
+            error("<frameset></frameset>")
+        );
+        assert_eq!(
+            "1:4: <p> has no attribute \"foo\"",
+            error("<p foo=bar></p>")
+        );
+        assert_eq!(
+            "1:4: invalid value for attribute \"rel\" on <a>: Matching variant not found",
+            error("<a rel=bogus></a>")
+        );
+        assert_eq!(
+            "1:9: event handler \"onclick\" on <button> can't be parsed",
+            error("<button onclick=\"go()\"></button>")
+        );
+        assert_eq!("1:6: expected </p>, found </div>", error("<p>Hi</div>"));
+        assert_eq!("1:1: <p> is never closed", error("<p>Hi"));
+        assert_eq!("1:3: unexpected closing tag </p>", error("Hi</p>"));
+        assert_eq!(
+            "1:8: a document can only have one root node",
+            error("<p></p><p></p>")
+        );
+        assert_eq!("1:3: no content", error("  "));
+        assert_eq!(
+            "2:5: <div> is not allowed inside <p>",
+            error("<p>\n    <div></div>\n</p>")
+        );
+        assert_eq!(
+            "1:5: text is not allowed inside <ul>",
+            error("<ul>  Hello</ul>")
+        );
+        assert_eq!(
+            "1:7: <html> is missing its required <head> child",
+            error("<html><body></body></html>")
+        );
+        assert_eq!(
+            "1:23: <thead> is not allowed here: the children of <table> must follow the order \
+             [caption?, colgroup*, thead?, tbody* | tr+, tfoot?]",
+            error("<table><tbody></tbody><thead></thead></table>")
+        );
+    }
+}
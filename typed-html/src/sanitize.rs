@@ -0,0 +1,404 @@
+//! Sanitising untrusted HTML.
+//!
+//! [`unsafe_text!`][unsafe_text] inserts HTML as it is, which is only safe if
+//! you trust where it came from. A [`Sanitizer`][Sanitizer] instead parses a
+//! fragment of HTML and keeps only the elements and attributes on its
+//! allowlist, returning typed nodes which can be inserted into a tree like
+//! any other, and which are escaped like any other when it's rendered.
+//!
+//! Disallowed elements are replaced by their children, except for elements
+//! like `<script>` and `<style>` whose content is dropped along with them.
+//! Attributes are dropped if they're not allowed, if their values can't be
+//! converted into their types, or if they're URLs with a scheme other than
+//! the allowed ones, which are `http`, `https` and `mailto` by default.
+//! Relative URLs are always allowed.
+//!
+//! What's left must still follow the content model, as the result is built
+//! with [`from_raw()`][from_raw], and input which doesn't is an error.
+//!
+//! # Examples
+//!
+//! ```
+//! # use typed_html::html;
+//! # use typed_html::dom::DOMTree;
+//! # use typed_html::sanitize::sanitize;
+//! # fn main() {
+//! let comment = r#"<p onclick="steal()">Hello <a href="javascript:steal()">Joe</a>!</p>
+//!     <script>steal()</script>"#;
+//! let nodes = sanitize::<String>(comment).unwrap();
+//! let doc: DOMTree<String> = html!(<div>{ nodes }</div>);
+//! assert_eq!("<div><p>Hello <a>Joe</a>!</p>\n    </div>", doc.to_string());
+//! # }
+//! ```
+//!
+//! [unsafe_text]: ../macro.unsafe_text.html
+//! [Sanitizer]: struct.Sanitizer.html
+//! [from_raw]: ../parse/fn.from_raw.html
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::marker::PhantomData;
+
+use crate::dom::{AttributeError, Element};
+use crate::elements::FlowContent;
+use crate::escape::{url_scheme, URL_ATTRIBUTES};
+use crate::parse::{from_raw, parse_raw, ParseError, RawAttribute, RawNode};
+use crate::OutputType;
+
+/// Elements whose content is dropped along with them when they're not
+/// allowed, rather than taking their place.
+static DROP_CONTENT: &[&str] = &[
+    "iframe", "noscript", "object", "script", "style", "template", "textarea", "title",
+];
+
+/// An element on a [`Sanitizer`][Sanitizer]'s allowlist.
+///
+/// [Sanitizer]: struct.Sanitizer.html
+struct AllowedElement {
+    attributes: BTreeSet<String>,
+    check: fn(&str, &str) -> Result<(), AttributeError>,
+}
+
+/// An allowlist of elements and attributes, for sanitising untrusted HTML.
+///
+/// `Sanitizer::default()` allows a set of elements for formatted text, like
+/// the output of a Markdown renderer, with the `href` of links, the `src`
+/// and `alt` of images and a few other attributes which can't be used for
+/// scripting or styling. Elements are added to the allowlist by their types,
+/// eg. `Sanitizer::none().allow::<elements::p<String>>(&[])`.
+pub struct Sanitizer<T: OutputType + Send + 'static> {
+    elements: BTreeMap<&'static str, AllowedElement>,
+    schemes: BTreeSet<String>,
+    output: PhantomData<T>,
+}
+
+impl<T: OutputType + Send + 'static> Default for Sanitizer<T> {
+    fn default() -> Self {
+        use crate::elements::*;
+        Sanitizer::none()
+            .allow::<a<T>>(&["href", "title"])
+            .allow::<abbr<T>>(&["title"])
+            .allow::<b<T>>(&[])
+            .allow::<blockquote<T>>(&["cite"])
+            .allow::<br<T>>(&[])
+            .allow::<caption<T>>(&[])
+            .allow::<code<T>>(&[])
+            .allow::<dd<T>>(&[])
+            .allow::<del<T>>(&["cite", "datetime"])
+            .allow::<dl<T>>(&[])
+            .allow::<dt<T>>(&[])
+            .allow::<em<T>>(&[])
+            .allow::<h1<T>>(&[])
+            .allow::<h2<T>>(&[])
+            .allow::<h3<T>>(&[])
+            .allow::<h4<T>>(&[])
+            .allow::<h5<T>>(&[])
+            .allow::<h6<T>>(&[])
+            .allow::<hr<T>>(&[])
+            .allow::<i<T>>(&[])
+            .allow::<img<T>>(&["alt", "height", "src", "title", "width"])
+            .allow::<ins<T>>(&["cite", "datetime"])
+            .allow::<kbd<T>>(&[])
+            .allow::<li<T>>(&[])
+            .allow::<ol<T>>(&["reversed", "start"])
+            .allow::<p<T>>(&[])
+            .allow::<pre<T>>(&[])
+            .allow::<q<T>>(&["cite"])
+            .allow::<s<T>>(&[])
+            .allow::<samp<T>>(&[])
+            .allow::<small<T>>(&[])
+            .allow::<span<T>>(&[])
+            .allow::<strong<T>>(&[])
+            .allow::<sub<T>>(&[])
+            .allow::<sup<T>>(&[])
+            .allow::<table<T>>(&[])
+            .allow::<tbody<T>>(&[])
+            .allow::<td<T>>(&["colspan", "rowspan"])
+            .allow::<tfoot<T>>(&[])
+            .allow::<th<T>>(&["colspan", "rowspan", "scope"])
+            .allow::<thead<T>>(&[])
+            .allow::<tr<T>>(&[])
+            .allow::<ul<T>>(&[])
+    }
+}
+
+impl<T: OutputType + Send + 'static> Sanitizer<T> {
+    /// Construct a sanitizer which allows no elements, and the URL schemes
+    /// `http`, `https` and `mailto`.
+    pub fn none() -> Self {
+        Sanitizer {
+            elements: BTreeMap::new(),
+            schemes: ["http", "https", "mailto"]
+                .iter()
+                .map(|scheme| scheme.to_string())
+                .collect(),
+            output: PhantomData,
+        }
+    }
+
+    /// Allow elements of type `E`, with the attributes named in
+    /// `attributes`, replacing any attributes allowed for it before.
+    ///
+    /// Attributes are given by their HTML names, eg. `accept-charset`.
+    ///
+    /// # Panics
+    ///
+    /// If `E` has no attribute with one of the names.
+    pub fn allow<E: Element<T>>(mut self, attributes: &[&str]) -> Self {
+        for attribute in attributes {
+            if let Err(AttributeError::Unknown) = E::check_attribute(attribute, "") {
+                panic!("<{}> has no attribute {:?}", E::name(), attribute);
+            }
+        }
+        self.elements.insert(
+            E::name(),
+            AllowedElement {
+                attributes: attributes.iter().map(|name| name.to_string()).collect(),
+                check: E::check_attribute,
+            },
+        );
+        self
+    }
+
+    /// Remove elements of type `E` from the allowlist.
+    pub fn disallow<E: Element<T>>(mut self) -> Self {
+        self.elements.remove(E::name());
+        self
+    }
+
+    /// Allow URLs with a scheme, eg. `ftp`.
+    pub fn allow_scheme(mut self, scheme: &str) -> Self {
+        self.schemes.insert(scheme.to_ascii_lowercase());
+        self
+    }
+
+    /// Stop allowing URLs with a scheme.
+    pub fn disallow_scheme(mut self, scheme: &str) -> Self {
+        self.schemes.remove(&scheme.to_ascii_lowercase());
+        self
+    }
+
+    /// Test whether elements of type `E` are allowed.
+    pub fn is_allowed<E: Element<T>>(&self) -> bool {
+        self.elements.contains_key(E::name())
+    }
+
+    /// Parse a fragment of untrusted HTML, keeping only what's allowed.
+    ///
+    /// Returns an error if the input isn't well formed, or if the allowed
+    /// elements don't follow the content model.
+    pub fn sanitize(&self, input: &str) -> Result<Vec<Box<dyn FlowContent<T>>>, ParseError> {
+        from_raw(self.clean(parse_raw(input)?))
+    }
+
+    /// Filter a list of nodes through the allowlist.
+    pub fn clean(&self, nodes: Vec<RawNode>) -> Vec<RawNode> {
+        let mut out = Vec::new();
+        for node in nodes {
+            match node {
+                RawNode::Text { .. } => out.push(node),
+                RawNode::Element(mut element) => match self.elements.get(element.name.as_str()) {
+                    Some(allowed) => {
+                        element
+                            .attributes
+                            .retain(|attribute| self.allows_attribute(allowed, attribute));
+                        element.children = self.clean(element.children);
+                        out.push(RawNode::Element(element));
+                    }
+                    None if DROP_CONTENT.contains(&element.name.as_str()) => (),
+                    None => out.extend(self.clean(element.children)),
+                },
+            }
+        }
+        out
+    }
+
+    fn allows_attribute(&self, allowed: &AllowedElement, attribute: &RawAttribute) -> bool {
+        let (name, value) = (attribute.name.as_str(), attribute.value.as_str());
+        allowed.attributes.contains(name)
+            && (allowed.check)(name, value).is_ok()
+            && match name {
+                "ping" => value.split_whitespace().all(|url| self.allows_url(url)),
+                "srcset" => value.split(',').all(|candidate| {
+                    let url = candidate.split_whitespace().next().unwrap_or("");
+                    self.allows_url(url)
+                }),
+                name if URL_ATTRIBUTES.contains(&name) => self.allows_url(value),
+                _ => true,
+            }
+    }
+
+    fn allows_url(&self, url: &str) -> bool {
+        match url_scheme(url) {
+            None => true,
+            Some(scheme) => self.schemes.contains(&scheme),
+        }
+    }
+}
+
+/// Sanitise a fragment of untrusted HTML with `Sanitizer::default()`.
+pub fn sanitize<T: OutputType + Send + 'static>(
+    input: &str,
+) -> Result<Vec<Box<dyn FlowContent<T>>>, ParseError> {
+    Sanitizer::default().sanitize(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elements::{a, div, img, p};
+
+    fn clean(input: &str) -> String {
+        render(sanitize::<String>(input).unwrap())
+    }
+
+    fn render(nodes: Vec<Box<dyn FlowContent<String>>>) -> String {
+        nodes.iter().map(|node| node.to_string()).collect()
+    }
+
+    #[test]
+    fn test_keeps_allowed() {
+        let input = r#"<p>Hello <em>Joe</em>, <a href="https://example.com/" title="Joe">see here</a>.</p><ul><li>One</li></ul>"#;
+        assert_eq!(input, clean(input));
+    }
+
+    #[test]
+    fn test_drops_scripts() {
+        assert_eq!(
+            "<p>Hello</p>",
+            clean("<p>Hello<script>alert(1)</script></p>")
+        );
+        assert_eq!("", clean("<style>* { display: none }</style>"));
+        assert_eq!(
+            "<b>x</b>",
+            clean(r#"<b onclick="alert(1)" onmouseover="alert(1)">x</b>"#)
+        );
+        assert_eq!("<b>x</b>", clean(r#"<b style="position: fixed">x</b>"#));
+        assert_eq!(
+            "<p>x</p>",
+            clean(r#"<p><iframe src="https://evil.com/">x</iframe>x</p>"#)
+        );
+    }
+
+    #[test]
+    fn test_unwraps_disallowed() {
+        assert_eq!(
+            "<p>Hello <b>Joe</b></p>",
+            clean(r#"<div class="x"><p>Hello <font color="red"><b>Joe</b></font></p></div>"#)
+        );
+        assert_eq!("Hello", clean("<form><button>Hello</button></form>"));
+    }
+
+    #[test]
+    fn test_url_schemes() {
+        for href in &[
+            "javascript:alert(1)",
+            "JaVaScRiPt:alert(1)",
+            " javascript:alert(1)",
+            "java\tscript:alert(1)",
+            "&#106;avascript:alert(1)",
+            "&#x6A;avascript:alert(1)",
+            "data:text/html,<script>alert(1)</script>",
+            "vbscript:msgbox(1)",
+        ] {
+            assert_eq!(
+                "<a>x</a>",
+                clean(&format!(r#"<a href="{}">x</a>"#, href)),
+                "{}",
+                href
+            );
+        }
+        for href in &[
+            "https://example.com/",
+            "HTTP://example.com/",
+            "mailto:joe@example.com",
+            "/joe",
+            "joe",
+            "joe?a:b",
+            "#joe",
+            "//example.com/",
+        ] {
+            let input = format!(r#"<a href="{}">x</a>"#, href);
+            assert!(clean(&input).contains("href"), "{}", href);
+        }
+        // Browsers decode numeric references without a semicolon, but as the
+        // `&` is escaped, this stays a relative URL.
+        assert_eq!(
+            r#"<a href="&amp;#106avascript:alert(1)">x</a>"#,
+            clean(r#"<a href="&#106avascript:alert(1)">x</a>"#)
+        );
+        assert_eq!(
+            r#"<img alt="x"/>"#,
+            clean(r#"<img src="javascript:alert(1)" alt="x">"#)
+        );
+        assert_eq!(
+            "<blockquote>x</blockquote>",
+            clean(r#"<blockquote cite="javascript:alert(1)">x</blockquote>"#)
+        );
+    }
+
+    #[test]
+    fn test_escapes_output() {
+        assert_eq!(
+            "<p>&lt;script&gt;alert(1)&lt;/script&gt;</p>",
+            clean("<p>&lt;script&gt;alert(1)&lt;/script&gt;</p>")
+        );
+        assert_eq!(
+            r#"<a title="&quot;&gt;&lt;script&gt;alert(1)&lt;/script&gt;">x</a>"#,
+            clean(r#"<a title='"><script>alert(1)</script>'>x</a>"#)
+        );
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        assert_eq!(r#"<img alt="x"/>"#, clean(r#"<img width="100%" alt="x">"#));
+        assert_eq!(r#"<img width="100"/>"#, clean(r#"<img width="100">"#));
+    }
+
+    #[test]
+    fn test_custom_allowlist() {
+        let sanitizer = Sanitizer::<String>::none()
+            .allow::<p<String>>(&["class"])
+            .allow::<a<String>>(&["href"])
+            .allow_scheme("ftp")
+            .disallow_scheme("mailto");
+        assert!(sanitizer.is_allowed::<p<String>>());
+        assert!(!sanitizer.is_allowed::<div<String>>());
+        assert_eq!(
+            r#"<p class="intro"><a href="ftp://example.com/">x</a><a>y</a> z</p>"#,
+            render(
+                sanitizer
+                    .sanitize(
+                        r#"<p class="intro"><a href="ftp://example.com/">x</a><a href="mailto:joe@example.com">y</a> <em>z</em></p>"#
+                    )
+                    .unwrap()
+            )
+        );
+        let sanitizer = sanitizer.disallow::<a<String>>();
+        assert_eq!(
+            "<p>x</p>",
+            render(sanitizer.sanitize(r#"<p><a href="/">x</a></p>"#).unwrap())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "<img> has no attribute \"onerror\"")]
+    fn test_unknown_attribute() {
+        Sanitizer::<String>::none().allow::<img<String>>(&["onerror"]);
+    }
+
+    #[test]
+    fn test_non_ascii() {
+        assert_eq!("<p>Hi <b>é</b></p>", clean("<p>Hi <b>é</b></p>"));
+        assert_eq!("élan <em>ü</em>", clean("élan <em>ü</em>"));
+        assert_eq!(
+            "<p>ñ</p>ü",
+            clean("<p><script>ö</script>ñ</p><font>ü</font>")
+        );
+    }
+
+    #[test]
+    fn test_content_model() {
+        assert!(sanitize::<String>("<li>x</li>").is_err());
+        assert!(sanitize::<String>("<p>unclosed").is_err());
+    }
+}
@@ -0,0 +1,1078 @@
+//! CSS selector queries over DOM trees.
+//!
+//! [`Selector`][Selector] parses a group of [CSS Level 3 selectors][selectors]
+//! and finds the elements in a [`VNode`][VNode] tree which match it, in
+//! document order. It supports type, universal, class, ID and attribute
+//! selectors, all four combinators, the structural pseudo-classes
+//! (`:root`, `:empty`, `:nth-child()`, `:first-of-type` and the rest) and
+//! `:not()`. Pseudo-classes which depend on the state of a browser, like
+//! `:hover` or `:visited`, and pseudo-elements are rejected when the
+//! selector is parsed.
+//!
+//! Class names and IDs in a selector are parsed as the [`Class`][Class] and
+//! [`Id`][Id] types used by the `class` and `id` attributes, and element
+//! names are checked against the declared elements, so a selector which
+//! could never match anything is an error rather than an empty result.
+//! Attribute names match the HTML names, eg. `[http-equiv]`, and attributes
+//! with empty values, like a `false` boolean attribute, don't match, as
+//! they aren't rendered.
+//!
+//! # Examples
+//!
+//! ```
+//! # use typed_html::html;
+//! # use typed_html::dom::{DOMTree, Node};
+//! # fn main() {
+//! let mut doc: DOMTree<String> = html!(
+//!     <nav>
+//!         <ul>
+//!             <li><a href="/">"Home"</a></li>
+//!             <li class="active"><a href="/about">"About"</a></li>
+//!             <li class="active"><a>"Contact"</a></li>
+//!         </ul>
+//!     </nav>
+//! );
+//! let vnode = doc.vnode();
+//! let links = vnode.query_selector_all("nav > ul li.active a[href]").unwrap();
+//! assert_eq!(1, links.len());
+//! assert_eq!("/about", links[0].attributes[0].1);
+//! # }
+//! ```
+//!
+//! [selectors]: https://www.w3.org/TR/selectors-3/
+//! [Selector]: struct.Selector.html
+//! [VNode]: ../dom/enum.VNode.html
+//! [Class]: ../types/struct.Class.html
+//! [Id]: ../types/struct.Id.html
+
+use std::fmt::{Display, Error, Formatter};
+use std::str::FromStr;
+
+use crate::dom::{VElement, VNode};
+use crate::elements::element_attributes;
+use crate::types::{Class, Id};
+use crate::OutputType;
+
+/// The error returned when a selector can't be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SelectorError {
+    /// The column in the selector where the problem was found, counting
+    /// characters from 1.
+    pub column: usize,
+    pub kind: SelectorErrorKind,
+}
+
+/// The kinds of [`SelectorError`][SelectorError].
+///
+/// [SelectorError]: struct.SelectorError.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SelectorErrorKind {
+    /// The selector isn't well formed.
+    Syntax(String),
+    /// There's no element with this name.
+    UnknownElement(String),
+    /// A class selector isn't a valid [`Class`][Class].
+    ///
+    /// [Class]: ../types/struct.Class.html
+    InvalidClass { class: String, reason: &'static str },
+    /// An ID selector isn't a valid [`Id`][Id].
+    ///
+    /// [Id]: ../types/struct.Id.html
+    InvalidId { id: String, reason: &'static str },
+    /// The pseudo-class or pseudo-element can't be matched against a
+    /// `VNode` tree.
+    Unsupported(String),
+}
+
+impl Display for SelectorErrorKind {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            SelectorErrorKind::Syntax(message) => f.write_str(message),
+            SelectorErrorKind::UnknownElement(name) => write!(f, "unknown element <{}>", name),
+            SelectorErrorKind::InvalidClass { class, reason } => {
+                write!(f, "{:?} is not a valid class name: {}", class, reason)
+            }
+            SelectorErrorKind::InvalidId { id, reason } => {
+                write!(f, "{:?} is not a valid ID: {}", id, reason)
+            }
+            SelectorErrorKind::Unsupported(name) => write!(f, "{} is not supported", name),
+        }
+    }
+}
+
+impl Display for SelectorError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(f, "column {}: {}", self.column, self.kind)
+    }
+}
+
+impl std::error::Error for SelectorError {}
+
+/// A parsed group of selectors, which matches an element if any of them
+/// does.
+///
+/// # Examples
+///
+/// ```
+/// # use typed_html::html;
+/// # use typed_html::dom::{DOMTree, Node};
+/// # use typed_html::select::Selector;
+/// # fn main() {
+/// let selector: Selector = "tr:nth-child(odd) > td:first-child".parse().unwrap();
+/// let mut doc: DOMTree<String> = html!(
+///     <table>
+///         <tr><td>"1"</td><td>"a"</td></tr>
+///         <tr><td>"2"</td><td>"b"</td></tr>
+///         <tr><td>"3"</td><td>"c"</td></tr>
+///     </table>
+/// );
+/// let vnode = doc.vnode();
+/// assert_eq!(2, selector.select(&vnode).len());
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Selector(Vec<ComplexSelector>);
+
+impl Selector {
+    /// Parse a comma separated group of selectors.
+    pub fn parse(selector: &str) -> Result<Self, SelectorError> {
+        Parser::new(selector).selector_group().map(Selector)
+    }
+
+    /// Find every element in a tree which matches this selector, including
+    /// the root, in document order.
+    pub fn select<'b, 'a, T: OutputType>(
+        &self,
+        root: &'b VNode<'a, T>,
+    ) -> Vec<&'b VElement<'a, T>> {
+        let mut found = Vec::new();
+        walk(std::slice::from_ref(root), None, &mut |scope| {
+            if self.matches(scope) {
+                found.push(scope.element);
+            }
+            false
+        });
+        found
+    }
+
+    /// Find the first element in a tree which matches this selector, in
+    /// document order.
+    pub fn select_first<'b, 'a, T: OutputType>(
+        &self,
+        root: &'b VNode<'a, T>,
+    ) -> Option<&'b VElement<'a, T>> {
+        let mut found = None;
+        walk(std::slice::from_ref(root), None, &mut |scope| {
+            if self.matches(scope) {
+                found = Some(scope.element);
+            }
+            found.is_some()
+        });
+        found
+    }
+
+    fn matches<T: OutputType>(&self, scope: &Scope<'_, '_, '_, T>) -> bool {
+        self.0.iter().any(|selector| selector.matches(0, scope))
+    }
+}
+
+impl FromStr for Selector {
+    type Err = SelectorError;
+    fn from_str(selector: &str) -> Result<Self, Self::Err> {
+        Selector::parse(selector)
+    }
+}
+
+/// Find the first element in a tree which matches a selector.
+///
+/// See [`Selector::select_first()`][select_first].
+///
+/// [select_first]: struct.Selector.html#method.select_first
+pub fn query_selector<'b, 'a, T: OutputType>(
+    root: &'b VNode<'a, T>,
+    selector: &str,
+) -> Result<Option<&'b VElement<'a, T>>, SelectorError> {
+    Ok(Selector::parse(selector)?.select_first(root))
+}
+
+/// Find every element in a tree which matches a selector.
+///
+/// See [`Selector::select()`][select].
+///
+/// [select]: struct.Selector.html#method.select
+pub fn query_selector_all<'b, 'a, T: OutputType>(
+    root: &'b VNode<'a, T>,
+    selector: &str,
+) -> Result<Vec<&'b VElement<'a, T>>, SelectorError> {
+    Ok(Selector::parse(selector)?.select(root))
+}
+
+/// A sequence of compound selectors joined by combinators, stored from
+/// right to left: `combinators[i]` joins `compounds[i]` to `compounds[i + 1]`
+/// on its left.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct ComplexSelector {
+    compounds: Vec<Compound>,
+    combinators: Vec<Combinator>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Combinator {
+    /// `a b`
+    Descendant,
+    /// `a > b`
+    Child,
+    /// `a + b`
+    NextSibling,
+    /// `a ~ b`
+    SubsequentSibling,
+}
+
+/// Simple selectors which all have to match the same element.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Compound(Vec<Simple>);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Simple {
+    Type(String),
+    Id(Id),
+    Class(Class),
+    Attribute {
+        name: String,
+        operator: Option<(AttributeOperator, String)>,
+    },
+    /// `:nth-child(an+b)` and its relatives.
+    Nth {
+        a: i64,
+        b: i64,
+        of_type: bool,
+        from_end: bool,
+    },
+    OnlyChild {
+        of_type: bool,
+    },
+    Root,
+    Empty,
+    Not(Compound),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum AttributeOperator {
+    /// `[name=value]`
+    Equals,
+    /// `[name~=value]`
+    Includes,
+    /// `[name|=value]`
+    DashMatch,
+    /// `[name^=value]`
+    Prefix,
+    /// `[name$=value]`
+    Suffix,
+    /// `[name*=value]`
+    Substring,
+}
+
+/// An element, with what's needed to match combinators and structural
+/// pseudo-classes against it: its siblings, and the chain of its ancestors.
+struct Scope<'s, 'b, 'a, T: OutputType> {
+    element: &'b VElement<'a, T>,
+    siblings: &'b [VNode<'a, T>],
+    index: usize,
+    parent: Option<&'s Scope<'s, 'b, 'a, T>>,
+}
+
+impl<'s, 'b, 'a, T: OutputType> Scope<'s, 'b, 'a, T> {
+    /// The element siblings before this one, nearest first.
+    fn previous_siblings(&self) -> impl Iterator<Item = Scope<'s, 'b, 'a, T>> + '_ {
+        self.siblings[..self.index]
+            .iter()
+            .enumerate()
+            .rev()
+            .filter_map(move |(index, node)| match node {
+                VNode::Element(element) => Some(Scope {
+                    element,
+                    siblings: self.siblings,
+                    index,
+                    parent: self.parent,
+                }),
+                _ => None,
+            })
+    }
+
+    /// This element's position among its element siblings, counting from 1,
+    /// or from the end, and only counting elements of the same type.
+    fn position(&self, of_type: bool, from_end: bool) -> i64 {
+        let siblings = if from_end {
+            &self.siblings[self.index + 1..]
+        } else {
+            &self.siblings[..self.index]
+        };
+        let count = siblings
+            .iter()
+            .filter(|node| match node {
+                VNode::Element(element) => !of_type || element.name == self.element.name,
+                _ => false,
+            })
+            .count();
+        count as i64 + 1
+    }
+
+    fn attribute(&self, name: &str) -> Option<&'b str> {
+        let element = self.element;
+        element
+            .attributes
+            .iter()
+            .find(|(key, _)| html_name_eq(key, name))
+            .map(|(_, value)| value.as_str())
+            .filter(|value| !value.is_empty())
+    }
+}
+
+/// Compare an attribute's name in a `VElement`, which may use underscores
+/// in place of dashes, with its HTML name.
+fn html_name_eq(key: &str, name: &str) -> bool {
+    key.len() == name.len()
+        && key
+            .bytes()
+            .zip(name.bytes())
+            .all(|(k, n)| k == n || (k == b'_' && n == b'-'))
+}
+
+/// Visit every element in `nodes` and their descendants in document order,
+/// until `visit` returns true.
+fn walk<'b, 'a, T: OutputType>(
+    nodes: &'b [VNode<'a, T>],
+    parent: Option<&Scope<'_, 'b, 'a, T>>,
+    visit: &mut dyn FnMut(&Scope<'_, 'b, 'a, T>) -> bool,
+) -> bool {
+    for (index, node) in nodes.iter().enumerate() {
+        if let VNode::Element(element) = node {
+            let scope = Scope {
+                element,
+                siblings: nodes,
+                index,
+                parent,
+            };
+            if visit(&scope) || walk(&element.children, Some(&scope), visit) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+impl ComplexSelector {
+    /// Whether `compounds[index]` and everything to its left matches, with
+    /// `compounds[index]` matching `scope`.
+    fn matches<T: OutputType>(&self, index: usize, scope: &Scope<'_, '_, '_, T>) -> bool {
+        if !self.compounds[index].matches(scope) {
+            return false;
+        }
+        let combinator = match self.combinators.get(index) {
+            None => return true,
+            Some(combinator) => combinator,
+        };
+        match combinator {
+            Combinator::Child => scope
+                .parent
+                .map(|parent| self.matches(index + 1, parent))
+                .unwrap_or(false),
+            Combinator::Descendant => {
+                let mut ancestor = scope.parent;
+                while let Some(scope) = ancestor {
+                    if self.matches(index + 1, scope) {
+                        return true;
+                    }
+                    ancestor = scope.parent;
+                }
+                false
+            }
+            Combinator::NextSibling => scope
+                .previous_siblings()
+                .next()
+                .map(|sibling| self.matches(index + 1, &sibling))
+                .unwrap_or(false),
+            Combinator::SubsequentSibling => scope
+                .previous_siblings()
+                .any(|sibling| self.matches(index + 1, &sibling)),
+        }
+    }
+}
+
+impl Compound {
+    fn matches<T: OutputType>(&self, scope: &Scope<'_, '_, '_, T>) -> bool {
+        self.0.iter().all(|simple| simple.matches(scope))
+    }
+}
+
+impl Simple {
+    fn matches<T: OutputType>(&self, scope: &Scope<'_, '_, '_, T>) -> bool {
+        match self {
+            Simple::Type(name) => scope.element.name == name.as_str(),
+            Simple::Id(id) => scope.attribute("id") == Some(id.as_str()),
+            Simple::Class(class) => scope
+                .attribute("class")
+                .map(|classes| {
+                    classes
+                        .split_ascii_whitespace()
+                        .any(|item| item == class.as_str())
+                })
+                .unwrap_or(false),
+            Simple::Attribute { name, operator } => match (scope.attribute(name), operator) {
+                (None, _) => false,
+                (Some(_), None) => true,
+                (Some(value), Some((operator, expected))) => operator.matches(value, expected),
+            },
+            Simple::Nth {
+                a,
+                b,
+                of_type,
+                from_end,
+            } => {
+                let position = scope.position(*of_type, *from_end);
+                if *a == 0 {
+                    position == *b
+                } else {
+                    // Widened so that huge values of `a` and `b` can't overflow.
+                    let (a, n) = (i128::from(*a), i128::from(position) - i128::from(*b));
+                    n % a == 0 && n / a >= 0
+                }
+            }
+            Simple::OnlyChild { of_type } => {
+                scope.position(*of_type, false) == 1 && scope.position(*of_type, true) == 1
+            }
+            Simple::Root => scope.parent.is_none(),
+            Simple::Empty => scope.element.children.iter().all(|child| match child {
+                VNode::Text(text) | VNode::UnsafeText(text) => text.is_empty(),
+                VNode::Comment(_) => true,
+                VNode::Element(_) => false,
+            }),
+            Simple::Not(compound) => !compound.matches(scope),
+        }
+    }
+}
+
+impl AttributeOperator {
+    fn matches(self, value: &str, expected: &str) -> bool {
+        match self {
+            AttributeOperator::Equals => value == expected,
+            AttributeOperator::Includes => {
+                !expected.is_empty()
+                    && !expected.contains(|c: char| c.is_ascii_whitespace())
+                    && value.split_ascii_whitespace().any(|item| item == expected)
+            }
+            AttributeOperator::DashMatch => {
+                value == expected
+                    || (value.starts_with(expected) && value[expected.len()..].starts_with('-'))
+            }
+            AttributeOperator::Prefix => !expected.is_empty() && value.starts_with(expected),
+            AttributeOperator::Suffix => !expected.is_empty() && value.ends_with(expected),
+            AttributeOperator::Substring => !expected.is_empty() && value.contains(expected),
+        }
+    }
+}
+
+/// Parse the argument of `:nth-child()` and friends, `odd`, `even` or
+/// `an+b`, into `(a, b)`.
+fn parse_nth(argument: &str) -> Option<(i64, i64)> {
+    let argument = argument.trim().to_ascii_lowercase();
+    match argument.as_str() {
+        "odd" => return Some((2, 1)),
+        "even" => return Some((2, 0)),
+        _ => (),
+    }
+    let (a, b) = match argument.find('n') {
+        None => return parse_integer(&argument, true).map(|b| (0, b)),
+        Some(index) => (&argument[..index], argument[index + 1..].trim_start()),
+    };
+    let a = match a {
+        "" | "+" => 1,
+        "-" => -1,
+        a => parse_integer(a, true)?,
+    };
+    let b = if b.is_empty() {
+        0
+    } else {
+        let sign = match b.as_bytes()[0] {
+            b'+' => 1,
+            b'-' => -1,
+            _ => return None,
+        };
+        sign * parse_integer(b[1..].trim_start(), false)?
+    };
+    Some((a, b))
+}
+
+/// Parse a decimal integer, with an optional sign if `signed` is true.
+fn parse_integer(input: &str, signed: bool) -> Option<i64> {
+    let digits = match input.as_bytes().first() {
+        Some(b'+') | Some(b'-') if signed => &input[1..],
+        _ => input,
+    };
+    if digits.is_empty() || !digits.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    input.parse().ok()
+}
+
+struct Parser<'i> {
+    input: &'i str,
+    position: usize,
+}
+
+impl<'i> Parser<'i> {
+    fn new(input: &'i str) -> Self {
+        Parser { input, position: 0 }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn peek_second(&self) -> Option<char> {
+        self.input[self.position..].chars().nth(1)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Skip whitespace, returning whether there was any.
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.position;
+        while self
+            .peek()
+            .map(|c| c.is_ascii_whitespace())
+            .unwrap_or(false)
+        {
+            self.bump();
+        }
+        self.position != start
+    }
+
+    fn error_at(&self, position: usize, kind: SelectorErrorKind) -> SelectorError {
+        SelectorError {
+            column: self.input[..position].chars().count() + 1,
+            kind,
+        }
+    }
+
+    fn syntax_error<A>(&self, message: &str) -> Result<A, SelectorError> {
+        let message = match self.peek() {
+            Some(c) => format!("{}, found {:?}", message, c),
+            None => format!("{}, found the end of the selector", message),
+        };
+        Err(self.error_at(self.position, SelectorErrorKind::Syntax(message)))
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), SelectorError> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            self.syntax_error(&format!("expected {:?}", expected))
+        }
+    }
+
+    fn selector_group(&mut self) -> Result<Vec<ComplexSelector>, SelectorError> {
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_whitespace();
+            selectors.push(self.complex_selector()?);
+            if self.peek().is_none() {
+                return Ok(selectors);
+            }
+            self.expect(',')?;
+        }
+    }
+
+    fn complex_selector(&mut self) -> Result<ComplexSelector, SelectorError> {
+        let mut compounds = vec![self.compound()?];
+        let mut combinators = Vec::new();
+        loop {
+            let whitespace = self.skip_whitespace();
+            let combinator = match self.peek() {
+                None | Some(',') => break,
+                Some('>') => Combinator::Child,
+                Some('+') => Combinator::NextSibling,
+                Some('~') => Combinator::SubsequentSibling,
+                _ if whitespace => Combinator::Descendant,
+                _ => return self.syntax_error("expected a combinator"),
+            };
+            if combinator != Combinator::Descendant {
+                self.bump();
+                self.skip_whitespace();
+            }
+            combinators.push(combinator);
+            compounds.push(self.compound()?);
+        }
+        compounds.reverse();
+        combinators.reverse();
+        Ok(ComplexSelector {
+            compounds,
+            combinators,
+        })
+    }
+
+    fn compound(&mut self) -> Result<Compound, SelectorError> {
+        let mut simples = Vec::new();
+        let start = self.position;
+        if !self.eat('*') && self.at_identifier() {
+            let name = self.identifier()?.to_ascii_lowercase();
+            if element_attributes(&name).is_none() {
+                return Err(self.error_at(start, SelectorErrorKind::UnknownElement(name)));
+            }
+            simples.push(Simple::Type(name));
+        }
+        loop {
+            let start = self.position;
+            match self.peek() {
+                Some('#') => {
+                    self.bump();
+                    let id = self.identifier()?;
+                    match Id::from_str(&id) {
+                        Ok(id) => simples.push(Simple::Id(id)),
+                        Err(reason) => {
+                            let kind = SelectorErrorKind::InvalidId { id, reason };
+                            return Err(self.error_at(start, kind));
+                        }
+                    }
+                }
+                Some('.') => {
+                    self.bump();
+                    let class = self.identifier()?;
+                    match Class::from_str(&class) {
+                        Ok(class) => simples.push(Simple::Class(class)),
+                        Err(reason) => {
+                            let kind = SelectorErrorKind::InvalidClass { class, reason };
+                            return Err(self.error_at(start, kind));
+                        }
+                    }
+                }
+                Some('[') => simples.push(self.attribute()?),
+                Some(':') => simples.push(self.pseudo_class()?),
+                _ => break,
+            }
+        }
+        if self.position == start {
+            return self.syntax_error("expected a selector");
+        }
+        Ok(Compound(simples))
+    }
+
+    fn attribute(&mut self) -> Result<Simple, SelectorError> {
+        self.expect('[')?;
+        self.skip_whitespace();
+        let name = self.identifier()?.to_ascii_lowercase();
+        self.skip_whitespace();
+        if self.eat(']') {
+            return Ok(Simple::Attribute {
+                name,
+                operator: None,
+            });
+        }
+        let operator = match self.peek() {
+            Some('=') => AttributeOperator::Equals,
+            Some('~') => AttributeOperator::Includes,
+            Some('|') => AttributeOperator::DashMatch,
+            Some('^') => AttributeOperator::Prefix,
+            Some('$') => AttributeOperator::Suffix,
+            Some('*') => AttributeOperator::Substring,
+            _ => return self.syntax_error("expected an attribute operator or ']'"),
+        };
+        self.bump();
+        if operator != AttributeOperator::Equals {
+            self.expect('=')?;
+        }
+        self.skip_whitespace();
+        let value = match self.peek() {
+            Some(quote) if quote == '"' || quote == '\'' => self.string()?,
+            _ if self.at_identifier() => self.identifier()?,
+            _ => return self.syntax_error("expected an identifier or a string"),
+        };
+        self.skip_whitespace();
+        self.expect(']')?;
+        Ok(Simple::Attribute {
+            name,
+            operator: Some((operator, value)),
+        })
+    }
+
+    fn pseudo_class(&mut self) -> Result<Simple, SelectorError> {
+        let start = self.position;
+        self.expect(':')?;
+        if self.peek() == Some(':') {
+            self.bump();
+            let name = format!("the pseudo-element ::{}", self.identifier()?);
+            return Err(self.error_at(start, SelectorErrorKind::Unsupported(name)));
+        }
+        let name = self.identifier()?.to_ascii_lowercase();
+        if !self.eat('(') {
+            return match name.as_str() {
+                "root" => Ok(Simple::Root),
+                "empty" => Ok(Simple::Empty),
+                "first-child" => Ok(nth(0, 1, false, false)),
+                "last-child" => Ok(nth(0, 1, false, true)),
+                "first-of-type" => Ok(nth(0, 1, true, false)),
+                "last-of-type" => Ok(nth(0, 1, true, true)),
+                "only-child" => Ok(Simple::OnlyChild { of_type: false }),
+                "only-of-type" => Ok(Simple::OnlyChild { of_type: true }),
+                _ => {
+                    let name = format!("the pseudo-class :{}", name);
+                    Err(self.error_at(start, SelectorErrorKind::Unsupported(name)))
+                }
+            };
+        }
+        let (of_type, from_end) = match name.as_str() {
+            "not" => {
+                self.skip_whitespace();
+                let compound = self.compound()?;
+                self.skip_whitespace();
+                self.expect(')')?;
+                return Ok(Simple::Not(compound));
+            }
+            "nth-child" => (false, false),
+            "nth-last-child" => (false, true),
+            "nth-of-type" => (true, false),
+            "nth-last-of-type" => (true, true),
+            _ => {
+                let name = format!("the pseudo-class :{}()", name);
+                return Err(self.error_at(start, SelectorErrorKind::Unsupported(name)));
+            }
+        };
+        let argument_start = self.position;
+        let argument_end = match self.input[argument_start..].find(')') {
+            Some(length) => argument_start + length,
+            None => {
+                self.position = self.input.len();
+                return self.syntax_error("expected ')'");
+            }
+        };
+        let argument = &self.input[argument_start..argument_end];
+        let (a, b) = parse_nth(argument).ok_or_else(|| {
+            let message = format!("{:?} is not of the form an+b", argument.trim());
+            self.error_at(argument_start, SelectorErrorKind::Syntax(message))
+        })?;
+        self.position = argument_end + 1;
+        Ok(nth(a, b, of_type, from_end))
+    }
+
+    fn at_identifier(&self) -> bool {
+        let mut chars = self.input[self.position..].chars();
+        let first = match chars.next() {
+            Some('-') => chars.next(),
+            first => first,
+        };
+        match first {
+            Some('\\') => true,
+            Some(c) => is_name_start(c),
+            None => false,
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String, SelectorError> {
+        if !self.at_identifier() {
+            return self.syntax_error("expected an identifier");
+        }
+        let mut identifier = String::new();
+        while let Some(c) = self.peek() {
+            if c == '\\' {
+                identifier.push(self.escape()?);
+            } else if is_name_start(c) || c == '-' || c.is_ascii_digit() {
+                identifier.push(c);
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        Ok(identifier)
+    }
+
+    fn string(&mut self) -> Result<String, SelectorError> {
+        let start = self.position;
+        let quote = self.bump();
+        let mut string = String::new();
+        loop {
+            match self.peek() {
+                None => {
+                    let kind = SelectorErrorKind::Syntax("unterminated string".to_string());
+                    return Err(self.error_at(start, kind));
+                }
+                Some('\\') if self.peek_second() == Some('\n') => {
+                    self.position += 2;
+                }
+                Some('\\') => string.push(self.escape()?),
+                c if c == quote => {
+                    self.bump();
+                    return Ok(string);
+                }
+                Some(c) => {
+                    string.push(c);
+                    self.bump();
+                }
+            }
+        }
+    }
+
+    /// Parse a backslash escape: up to six hex digits and an optional space,
+    /// or any other character standing for itself.
+    fn escape(&mut self) -> Result<char, SelectorError> {
+        self.expect('\\')?;
+        let digits = self.input[self.position..]
+            .bytes()
+            .take(6)
+            .take_while(|c| c.is_ascii_hexdigit())
+            .count();
+        if digits == 0 {
+            return match self.bump() {
+                Some(c) => Ok(c),
+                None => self.syntax_error("expected an escaped character"),
+            };
+        }
+        let hex = &self.input[self.position..self.position + digits];
+        self.position += digits;
+        if self
+            .peek()
+            .map(|c| c.is_ascii_whitespace())
+            .unwrap_or(false)
+        {
+            self.bump();
+        }
+        Ok(u32::from_str_radix(hex, 16)
+            .ok()
+            .and_then(std::char::from_u32)
+            .filter(|&c| c != '\0')
+            .unwrap_or('\u{fffd}'))
+    }
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || !c.is_ascii()
+}
+
+fn nth(a: i64, b: i64, of_type: bool, from_end: bool) -> Simple {
+    Simple::Nth {
+        a,
+        b,
+        of_type,
+        from_end,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate as typed_html;
+    use crate::dom::DOMTree;
+    use crate::html;
+    use std::borrow::Cow;
+
+    fn doc() -> DOMTree<String> {
+        html!(
+            <div id="root" class="page">
+                <nav>
+                    <ul>
+                        <li><a href="/">"Home"</a></li>
+                        <li class="active item"><a href="/about" lang="en-GB">"About"</a></li>
+                        <li class="item"><a>"Contact"</a></li>
+                        <li class="item"><a href="https://example.com/">"Elsewhere"</a></li>
+                    </ul>
+                </nav>
+                <h1>"Title"</h1>
+                <p id="intro">"One"</p>
+                <p>"Two"</p>
+                <!-- "comment" -->
+                <p></p>
+                <input type="checkbox" checked=false disabled=true/>
+            </div>
+        )
+    }
+
+    /// Describe each match by its name and its text, or its first attribute.
+    fn select(selector: &str) -> Vec<String> {
+        let mut doc = doc();
+        let vnode = doc.vnode();
+        query_selector_all(&vnode, selector)
+            .unwrap()
+            .into_iter()
+            .map(|element| {
+                let text = element.children.iter().find_map(|child| match child {
+                    VNode::Text(text) => Some(text.to_string()),
+                    _ => None,
+                });
+                let attr = element.attributes.first().map(|(_, value)| value.clone());
+                format!("{}:{}", element.name, text.or(attr).unwrap_or_default())
+            })
+            .collect()
+    }
+
+    fn error(selector: &str) -> String {
+        Selector::parse(selector).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_simple_selectors() {
+        assert_eq!(vec!["p:One", "p:Two", "p:"], select("p"));
+        assert_eq!(vec!["p:One", "p:Two", "p:"], select("P"));
+        assert_eq!(16, select("*").len());
+        assert_eq!(vec!["p:One"], select("#intro"));
+        assert_eq!(vec!["p:One"], select("p#intro"));
+        assert_eq!(
+            vec!["li:active item", "li:item", "li:item"],
+            select(".item")
+        );
+        assert_eq!(vec!["li:active item"], select("li.item.active"));
+        assert_eq!(vec!["li:active item"], select(".\\61 ctive"));
+        assert!(select("h1.item").is_empty());
+    }
+
+    #[test]
+    fn test_attribute_selectors() {
+        assert_eq!(vec!["a:Home", "a:About", "a:Elsewhere"], select("a[href]"));
+        assert_eq!(vec!["a:About"], select("[href='/about']"));
+        assert_eq!(vec!["a:About"], select("[href=\"/about\"]"));
+        assert_eq!(vec!["li:active item"], select("[class~=active]"));
+        assert!(select("[class~='active item']").is_empty());
+        assert_eq!(vec!["a:About"], select("[lang|=en]"));
+        assert!(select("[lang|=en-G]").is_empty());
+        assert_eq!(vec!["a:Elsewhere"], select("a[href^=https]"));
+        assert_eq!(vec!["a:About"], select("a[href$=out]"));
+        assert_eq!(vec!["a:About", "a:Elsewhere"], select("a[href*=o]"));
+        assert!(select("a[href^='']").is_empty());
+        assert_eq!(vec!["input:"], select("input[disabled]"));
+        assert!(select("input[checked]").is_empty());
+    }
+
+    #[test]
+    fn test_combinators() {
+        assert_eq!(vec!["a:About"], select("nav > ul li.active a[href]"));
+        assert_eq!(vec!["ul:"], select("div > nav > ul"));
+        assert!(select("div > ul").is_empty());
+        assert_eq!(4, select("div li").len());
+        assert_eq!(vec!["p:One"], select("h1 + p"));
+        assert_eq!(vec!["p:One", "p:Two", "p:"], select("h1 ~ p"));
+        assert_eq!(vec!["p:"], select("p + p + p"));
+        assert_eq!(vec!["p:"], select("p+p+p"));
+        assert_eq!(vec!["h1:Title", "p:One"], select("p#intro, h1"));
+        assert_eq!(
+            vec!["a:Contact"],
+            select("li.active ~ li:not([href]) > a:not([href])")
+        );
+    }
+
+    #[test]
+    fn test_pseudo_classes() {
+        assert_eq!(vec!["div:page"], select(":root"));
+        assert_eq!(vec!["p:", "input:"], select("div > :empty"));
+        assert_eq!(vec!["a:Home"], select("li:first-child a"));
+        assert_eq!(vec!["li:item"], select("li:last-child"));
+        assert_eq!(vec!["div:page", "ul:"], select(":only-child:not(a)"));
+        assert_eq!(
+            vec!["h1:Title"],
+            select("div > :only-of-type:not(nav):not(input)")
+        );
+        assert_eq!(vec!["p:One"], select("p:first-of-type"));
+        assert_eq!(vec!["p:"], select("p:last-of-type"));
+        assert_eq!(vec!["li:", "li:item"], select("li:nth-child(odd)"));
+        assert_eq!(
+            vec!["li:active item", "li:item"],
+            select("li:nth-child(2n)")
+        );
+        assert_eq!(
+            vec!["li:active item", "li:item"],
+            select("li:nth-child( even )")
+        );
+        assert_eq!(vec!["li:item"], select("li:nth-child(3)"));
+        assert_eq!(
+            vec!["li:", "li:active item"],
+            select("li:nth-child(-n + 2)")
+        );
+        assert_eq!(
+            vec!["li:item", "li:item"],
+            select("li:nth-last-child(-n+2)")
+        );
+        assert_eq!(vec!["p:Two"], select("p:nth-of-type(2)"));
+        assert_eq!(vec!["p:One"], select("p:nth-last-of-type(3)"));
+        assert_eq!(
+            vec!["li:active item", "li:item", "li:item"],
+            select("li:nth-child(n+2)")
+        );
+        assert_eq!(select("li"), select("li:nth-child(n-9223372036854775807)"));
+        assert_eq!(select("li"), select("li:nth-child(-n+9223372036854775807)"));
+        assert_eq!(
+            Vec::<String>::new(),
+            select("li:nth-child(-9223372036854775807n-9223372036854775807)")
+        );
+        assert_eq!(vec!["nav:"], select("div > :not(p):not(h1):not(input)"));
+    }
+
+    #[test]
+    fn test_parse_nth() {
+        assert_eq!(Some((2, 1)), parse_nth("odd"));
+        assert_eq!(Some((2, 0)), parse_nth(" EVEN "));
+        assert_eq!(Some((0, 5)), parse_nth("5"));
+        assert_eq!(Some((0, -5)), parse_nth("-5"));
+        assert_eq!(Some((1, 0)), parse_nth("n"));
+        assert_eq!(Some((-1, 3)), parse_nth("-n+3"));
+        assert_eq!(Some((3, -2)), parse_nth("+3n - 2"));
+        assert_eq!(None, parse_nth("3n-+2"));
+        assert_eq!(None, parse_nth("3n 2"));
+        assert_eq!(None, parse_nth("3 n"));
+        assert_eq!(None, parse_nth(""));
+        assert_eq!(None, parse_nth("odd n"));
+    }
+
+    #[test]
+    fn test_select_first() {
+        let mut doc = doc();
+        let vnode = doc.vnode();
+        let first = query_selector(&vnode, "li > a").unwrap().unwrap();
+        assert_eq!(vec![(Cow::from("href"), "/".to_string())], first.attributes);
+        assert!(query_selector(&vnode, "table").unwrap().is_none());
+        let text: VNode<String> = VNode::Text("text");
+        assert!(query_selector_all(&text, "*").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!("column 1: unknown element <bogus>", error("BOGUS"));
+        assert_eq!(
+            "column 2: \"-x\" is not a valid class name: \
+             class name must start with an alphabetic character",
+            error("p.-x")
+        );
+        assert_eq!(
+            "column 1: the pseudo-class :hover is not supported",
+            error(":hover")
+        );
+        assert_eq!(
+            "column 2: the pseudo-element ::before is not supported",
+            error("p::before")
+        );
+        assert_eq!(
+            "column 3: expected a selector, found the end of the selector",
+            error("p>")
+        );
+        assert_eq!("column 3: expected a selector, found ','", error("p,,a"));
+        assert_eq!("column 2: expected a combinator, found '!'", error("p!"));
+        assert_eq!("column 4: unterminated string", error("[a='b"));
+        assert_eq!(
+            "column 14: \"2n+\" is not of the form an+b",
+            error("li:nth-child( 2n+)")
+        );
+        assert_eq!(
+            "column 3: expected an attribute operator or ']', found '!'",
+            error("[a!=b]")
+        );
+    }
+}
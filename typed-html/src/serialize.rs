@@ -0,0 +1,357 @@
+//! Serde support for DOM trees, behind the `serde` feature.
+//!
+//! Trees are serialised through [`OwnedVNode`][OwnedVNode], whose
+//! documentation describes the format.
+//!
+//! [OwnedVNode]: ../dom/enum.OwnedVNode.html
+
+use std::fmt::{self, Formatter};
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeStruct, Serializer};
+
+use crate::dom::{OwnedVElement, OwnedVNode, VNode};
+use crate::elements::{element_attributes, element_name};
+use crate::events::EVENT_NAMES;
+use crate::OutputType;
+
+const NODE_KINDS: &[&str] = &["text", "unsafe_text", "comment", "element"];
+const ELEMENT_FIELDS: &[&str] = &["name", "key", "attributes", "data", "events", "children"];
+
+fn is_data_attribute(name: &str) -> bool {
+    name.len() > 5 && name.starts_with("data-")
+}
+
+/// A list of name and value pairs, serialised as a map in the same order.
+struct Pairs<'a, K>(&'a [(K, String)]);
+
+impl<'a, K: Serialize> Serialize for Pairs<'a, K> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for (name, value) in self.0 {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for OwnedVNode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            OwnedVNode::Text(text) => {
+                serializer.serialize_newtype_variant("OwnedVNode", 0, "text", text)
+            }
+            OwnedVNode::UnsafeText(text) => {
+                serializer.serialize_newtype_variant("OwnedVNode", 1, "unsafe_text", text)
+            }
+            OwnedVNode::Comment(text) => {
+                serializer.serialize_newtype_variant("OwnedVNode", 2, "comment", text)
+            }
+            OwnedVNode::Element(element) => {
+                serializer.serialize_newtype_variant("OwnedVNode", 3, "element", element)
+            }
+        }
+    }
+}
+
+impl Serialize for OwnedVElement {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (data, attributes): (Vec<_>, Vec<_>) = self
+            .attributes
+            .iter()
+            .cloned()
+            .partition(|(name, _)| is_data_attribute(name));
+        let mut state = serializer.serialize_struct("OwnedVElement", 6)?;
+        state.serialize_field("name", self.name)?;
+        match &self.key {
+            Some(key) => state.serialize_field("key", key)?,
+            None => state.skip_field("key")?,
+        }
+        if attributes.is_empty() {
+            state.skip_field("attributes")?;
+        } else {
+            state.serialize_field("attributes", &Pairs(&attributes))?;
+        }
+        if data.is_empty() {
+            state.skip_field("data")?;
+        } else {
+            state.serialize_field("data", &Pairs(&data))?;
+        }
+        if self.events.is_empty() {
+            state.skip_field("events")?;
+        } else {
+            state.serialize_field("events", &Pairs(&self.events))?;
+        }
+        if self.children.is_empty() {
+            state.skip_field("children")?;
+        } else {
+            state.serialize_field("children", &self.children)?;
+        }
+        state.end()
+    }
+}
+
+impl<'a, T: OutputType + 'a> Serialize for VNode<'a, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        OwnedVNode::from(self).serialize(serializer)
+    }
+}
+
+/// A map deserialised into a list of pairs, keeping its order.
+struct PairList(Vec<(String, String)>);
+
+impl<'de> Deserialize<'de> for PairList {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PairVisitor;
+
+        impl<'de> Visitor<'de> for PairVisitor {
+            type Value = PairList;
+
+            fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+                f.write_str("a map of names to strings")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<PairList, A::Error> {
+                let mut pairs = Vec::new();
+                while let Some(pair) = map.next_entry()? {
+                    pairs.push(pair);
+                }
+                Ok(PairList(pairs))
+            }
+        }
+
+        deserializer.deserialize_map(PairVisitor)
+    }
+}
+
+enum NodeKind {
+    Text,
+    UnsafeText,
+    Comment,
+    Element,
+}
+
+impl<'de> Deserialize<'de> for NodeKind {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match String::deserialize(deserializer)?.as_str() {
+            "text" => Ok(NodeKind::Text),
+            "unsafe_text" => Ok(NodeKind::UnsafeText),
+            "comment" => Ok(NodeKind::Comment),
+            "element" => Ok(NodeKind::Element),
+            kind => Err(de::Error::unknown_variant(kind, NODE_KINDS)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for OwnedVNode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct NodeVisitor;
+
+        impl<'de> Visitor<'de> for NodeVisitor {
+            type Value = OwnedVNode;
+
+            fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+                f.write_str("a map with a single node kind")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<OwnedVNode, A::Error> {
+                let node = match map.next_key()? {
+                    Some(NodeKind::Text) => OwnedVNode::Text(map.next_value()?),
+                    Some(NodeKind::UnsafeText) => OwnedVNode::UnsafeText(map.next_value()?),
+                    Some(NodeKind::Comment) => OwnedVNode::Comment(map.next_value()?),
+                    Some(NodeKind::Element) => OwnedVNode::Element(map.next_value()?),
+                    None => return Err(de::Error::invalid_length(0, &self)),
+                };
+                match map.next_key::<String>()? {
+                    None => Ok(node),
+                    Some(_) => Err(de::Error::invalid_length(2, &self)),
+                }
+            }
+        }
+
+        deserializer.deserialize_map(NodeVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for OwnedVElement {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ElementVisitor;
+
+        impl<'de> Visitor<'de> for ElementVisitor {
+            type Value = OwnedVElement;
+
+            fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+                f.write_str("an element")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<OwnedVElement, A::Error> {
+                let mut name: Option<String> = None;
+                let mut key = None;
+                let mut attributes = PairList(Vec::new());
+                let mut data = PairList(Vec::new());
+                let mut events = PairList(Vec::new());
+                let mut children = Vec::new();
+                while let Some(field) = map.next_key::<String>()? {
+                    match field.as_str() {
+                        "name" => name = Some(map.next_value()?),
+                        "key" => key = Some(map.next_value()?),
+                        "attributes" => attributes = map.next_value()?,
+                        "data" => data = map.next_value()?,
+                        "events" => events = map.next_value()?,
+                        "children" => children = map.next_value()?,
+                        field => return Err(de::Error::unknown_field(field, ELEMENT_FIELDS)),
+                    }
+                }
+                let name = name.ok_or_else(|| de::Error::missing_field("name"))?;
+                let mut element = build_element(name, attributes.0, data.0, events.0, children)
+                    .map_err(de::Error::custom)?;
+                element.key = key;
+                Ok(element)
+            }
+        }
+
+        deserializer.deserialize_struct("OwnedVElement", ELEMENT_FIELDS, ElementVisitor)
+    }
+}
+
+/// Check the names in a deserialised element, and find `'static` copies of
+/// them.
+///
+/// Element, attribute and event names must be declared ones, which already
+/// have `'static` copies. `data-` attribute names are kept as they are.
+fn build_element(
+    name: String,
+    attributes: Vec<(String, String)>,
+    data: Vec<(String, String)>,
+    events: Vec<(String, String)>,
+    children: Vec<OwnedVNode>,
+) -> Result<OwnedVElement, String> {
+    let unknown = || format!("unknown element <{}>", name);
+    let known = element_attributes(&name).ok_or_else(unknown)?;
+    let element = element_name(&name).ok_or_else(unknown)?;
+    let mut typed = Vec::with_capacity(attributes.len() + data.len());
+    for (attribute, value) in attributes {
+        match known.iter().find(|(known, _)| *known == attribute) {
+            Some((attribute, _)) => typed.push(((*attribute).into(), value)),
+            None => return Err(format!("<{}> has no attribute {:?}", name, attribute)),
+        }
+    }
+    for (attribute, value) in data {
+        if !is_data_attribute(&attribute) {
+            return Err(format!("{:?} is not a data attribute", attribute));
+        }
+        typed.push((attribute.into(), value));
+    }
+    let events = events
+        .into_iter()
+        .map(
+            |(event, handler)| match EVENT_NAMES.iter().find(|known| **known == event) {
+                Some(event) => Ok((*event, handler)),
+                None => Err(format!("unknown event {:?}", event)),
+            },
+        )
+        .collect::<Result<_, _>>()?;
+    Ok(OwnedVElement {
+        name: element,
+        attributes: typed,
+        key: None,
+        events,
+        children,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate as typed_html;
+    use crate::dom::{DOMTree, Node};
+    use crate::parse::from_owned;
+    use crate::{html, text};
+
+    #[test]
+    fn test_serialize() {
+        let greeting = "Hello <Joe>";
+        let mut doc: DOMTree<String> = html!(
+            <div class="greeting" data-lang="en">
+                <p key="greeting" onclick="greet()">{ text!("{}", greeting) }</p>
+                <br/>
+            </div>
+            : String
+        );
+        let json = serde_json::to_string(&doc.vnode()).unwrap();
+        assert_eq!(
+            r#"{"element":{"name":"div","attributes":{"class":"greeting"},"data":{"data-lang":"en"},"children":[{"element":{"name":"p","key":"greeting","events":{"click":"greet()"},"children":[{"text":"Hello <Joe>"}]}},{"element":{"name":"br"}}]}}"#,
+            json
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut doc: DOMTree<String> = html!(
+            <meta key="refresh" http-equiv="refresh" content="5"/>
+        );
+        let owned = doc.owned_vnode();
+        let json = serde_json::to_string(&owned).unwrap();
+        assert!(json.contains(r#""http-equiv":"refresh""#));
+        let copy: OwnedVNode = serde_json::from_str(&json).unwrap();
+        assert_eq!(owned, copy);
+
+        let doc = from_owned::<String, dyn Node<String>>(copy).unwrap();
+        assert_eq!(
+            r#"<meta content="5" http-equiv="refresh"/>"#,
+            doc.to_string()
+        );
+    }
+
+    #[test]
+    fn test_reject_names() {
+        let error = |json| {
+            serde_json::from_str::<OwnedVNode>(json)
+                .unwrap_err()
+                .to_string()
+        };
+        assert!(error(r#"{"element":{"name":"bogus"}}"#).starts_with("unknown element <bogus>"));
+        assert!(
+            error(r#"{"element":{"name":"p","attributes":{"bogus":""}}}"#)
+                .starts_with("<p> has no attribute \"bogus\"")
+        );
+        assert!(error(r#"{"element":{"name":"p","data":{"lang":"en"}}}"#)
+            .starts_with("\"lang\" is not a data attribute"));
+        assert!(
+            error(r#"{"element":{"name":"p","key":"greeting","events":{"bogus":""}}}"#)
+                .starts_with("unknown event \"bogus\"")
+        );
+        assert!(error(r#"{"element":{"class":"p"}}"#).starts_with("unknown field `class`"));
+        assert!(error(r#"{"text":"a","comment":"b"}"#).starts_with("invalid length 2"));
+        assert!(error(r#"{"html":"a"}"#).starts_with("unknown variant `html`"));
+    }
+
+    #[test]
+    fn test_reject_values() {
+        let error = |json| {
+            let node = serde_json::from_str::<OwnedVNode>(json).unwrap();
+            from_owned::<String, dyn Node<String>>(node)
+                .map(|_| ())
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            "1:1: invalid value for attribute \"width\" on <img>: invalid digit found in string",
+            error(
+                r#"{"element":{"name":"img","attributes":{"src":"a.png","alt":"","width":"wide"}}}"#
+            )
+        );
+        assert_eq!(
+            "1:1: <li> is not allowed inside <p>",
+            error(r#"{"element":{"name":"p","children":[{"element":{"name":"li"}}]}}"#)
+        );
+        assert_eq!(
+            "1:1: event handler \"onclick\" on <p> can't be parsed",
+            error(r#"{"element":{"name":"p","key":"greeting","events":{"click":"go()"}}}"#)
+        );
+        assert_eq!(
+            "1:1: unsafe text can't be converted",
+            error(r#"{"unsafe_text":"<b>"}"#)
+        );
+    }
+}
@@ -4,7 +4,7 @@ use std::fmt::{Display, Error, Formatter};
 use std::ops::Deref;
 use std::str::FromStr;
 
-use super::Id;
+use super::{Id, SpacedSet};
 
 /// A valid CSS class.
 ///
@@ -78,3 +78,89 @@ impl Deref for Class {
         &self.0
     }
 }
+
+/// A value which the [`classes!`][classes] macro can add to a set of classes.
+///
+/// Class names given as strings are validated with
+/// [`Class::from_str()`][from_str], and panic if they're invalid. An `Option`
+/// adds its value if there is one, and a `(value, bool)` pair adds its value
+/// if the `bool` is true.
+///
+/// [classes]: ../macro.classes.html
+/// [from_str]: struct.Class.html#method.from_str
+pub trait ClassItem {
+    /// Add the class, or classes, to `classes`.
+    fn add_to(self, classes: &mut SpacedSet<Class>);
+}
+
+impl ClassItem for Class {
+    fn add_to(self, classes: &mut SpacedSet<Class>) {
+        classes.insert(self);
+    }
+}
+
+impl ClassItem for &str {
+    fn add_to(self, classes: &mut SpacedSet<Class>) {
+        classes.insert(Class::new(self));
+    }
+}
+
+impl ClassItem for String {
+    fn add_to(self, classes: &mut SpacedSet<Class>) {
+        classes.insert(Class::new(self));
+    }
+}
+
+impl ClassItem for SpacedSet<Class> {
+    fn add_to(self, classes: &mut SpacedSet<Class>) {
+        classes.extend(self.iter().cloned());
+    }
+}
+
+impl<C: ClassItem> ClassItem for Option<C> {
+    fn add_to(self, classes: &mut SpacedSet<Class>) {
+        if let Some(class) = self {
+            class.add_to(classes);
+        }
+    }
+}
+
+impl<C: ClassItem> ClassItem for (C, bool) {
+    fn add_to(self, classes: &mut SpacedSet<Class>) {
+        if self.1 {
+            self.0.add_to(classes);
+        }
+    }
+}
+
+/// Build a [`SpacedSet<Class>`][SpacedSet] from a list of class names,
+/// optional classes and `(class, bool)` pairs.
+///
+/// Each item can be anything implementing [`ClassItem`][ClassItem]. Class
+/// names are validated when they're added, and the macro panics if one is
+/// invalid.
+///
+/// # Examples
+///
+/// ```
+/// # use typed_html::classes;
+/// # use typed_html::types::Class;
+/// # fn main() {
+/// let is_active = true;
+/// let theme: Option<Class> = None;
+/// let classes = classes!["button", ("active", is_active), ("hidden", false), theme];
+/// assert_eq!("active button", classes.to_string());
+/// # }
+/// ```
+///
+/// [SpacedSet]: types/struct.SpacedSet.html
+/// [ClassItem]: types/trait.ClassItem.html
+#[macro_export]
+macro_rules! classes {
+    ($($item:expr),* $(,)?) => {{
+        #[allow(unused_mut)]
+        let mut classes = $crate::types::SpacedSet::<$crate::types::Class>::new();
+        $( $crate::types::ClassItem::add_to($item, &mut classes); )*
+        classes
+    }};
+}
@@ -3,7 +3,7 @@
 use strum_macros::*;
 
 mod class;
-pub use self::class::Class;
+pub use self::class::{Class, ClassItem};
 
 mod id;
 pub use self::id::Id;
@@ -28,7 +28,181 @@ pub type Integrity = String;
 pub type Nonce = String;
 pub type Target = String;
 
-#[derive(EnumString, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr)]
+/// The strings an attribute value can be parsed from, as far as the `html!`
+/// macro can tell at compile time.
+///
+/// `html!` checks a string literal given for an attribute against this, and
+/// likewise the literal parts of a `f!"..."` format string, which have to fit
+/// around whatever the arguments turn out to be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueSyntax {
+    /// Any string might parse.
+    Any,
+    /// Only strings made of these ASCII characters parse.
+    Characters(&'static str),
+    /// Only one of these strings parses.
+    OneOf(&'static [&'static str]),
+}
+
+impl ValueSyntax {
+    /// Get the syntax of the type of an attribute, given a function which
+    /// picks the attribute out of its element's attributes.
+    ///
+    /// This lets `html!` name an attribute's type without knowing it.
+    pub const fn of<A, V: AttributeValue>(_attribute: fn(&A) -> &Option<V>) -> ValueSyntax {
+        V::SYNTAX
+    }
+
+    /// Test whether a format string whose literal parts are `parts` could
+    /// produce a string with this syntax. A string literal is a format string
+    /// with one part.
+    pub const fn matches(&self, parts: &[&str]) -> bool {
+        match self {
+            ValueSyntax::Any => true,
+            ValueSyntax::Characters(chars) => {
+                let mut index = 0;
+                while index < parts.len() {
+                    let part = parts[index].as_bytes();
+                    let mut byte = 0;
+                    while byte < part.len() {
+                        if find(chars.as_bytes(), &[part[byte]], 0).is_none() {
+                            return false;
+                        }
+                        byte += 1;
+                    }
+                    index += 1;
+                }
+                true
+            }
+            ValueSyntax::OneOf(values) => {
+                let mut index = 0;
+                while index < values.len() {
+                    if fits(values[index].as_bytes(), parts) {
+                        return true;
+                    }
+                    index += 1;
+                }
+                false
+            }
+        }
+    }
+}
+
+/// Test whether `value` starts with the first of `parts`, ends with the last,
+/// and has the others in between in order, leaving room for arguments.
+const fn fits(value: &[u8], parts: &[&str]) -> bool {
+    let first = parts[0].as_bytes();
+    if !occurs_at(value, first, 0) {
+        return false;
+    }
+    if parts.len() == 1 {
+        return value.len() == first.len();
+    }
+    let mut position = first.len();
+    let mut index = 1;
+    while index < parts.len() - 1 {
+        match find(value, parts[index].as_bytes(), position) {
+            Some(found) => position = found + parts[index].len(),
+            None => return false,
+        }
+        index += 1;
+    }
+    let last = parts[parts.len() - 1].as_bytes();
+    value.len() >= position + last.len() && occurs_at(value, last, value.len() - last.len())
+}
+
+/// Find the first occurrence of `needle` in `haystack` at or after `from`.
+const fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    let mut start = from;
+    while start + needle.len() <= haystack.len() {
+        if occurs_at(haystack, needle, start) {
+            return Some(start);
+        }
+        start += 1;
+    }
+    None
+}
+
+const fn occurs_at(haystack: &[u8], needle: &[u8], at: usize) -> bool {
+    if at + needle.len() > haystack.len() {
+        return false;
+    }
+    let mut byte = 0;
+    while byte < needle.len() {
+        if haystack[at + byte] != needle[byte] {
+            return false;
+        }
+        byte += 1;
+    }
+    true
+}
+
+/// A type of attribute value, with the strings it can be parsed from.
+pub trait AttributeValue {
+    const SYNTAX: ValueSyntax;
+}
+
+macro_rules! attribute_value {
+    ($syntax:expr => $($type:ty),*) => {
+        $(
+            impl AttributeValue for $type {
+                const SYNTAX: ValueSyntax = $syntax;
+            }
+        )*
+    };
+
+    ($($type:ty),*) => {
+        $(
+            impl AttributeValue for $type {
+                const SYNTAX: ValueSyntax =
+                    ValueSyntax::OneOf(<$type as strum::VariantNames>::VARIANTS);
+            }
+        )*
+    };
+}
+
+attribute_value!(ValueSyntax::Any => String, Id, Class, Mime, LanguageTag);
+attribute_value!(ValueSyntax::Characters("+0123456789") => usize);
+attribute_value!(ValueSyntax::Characters("+-0123456789") => isize);
+attribute_value!(
+    AreaShape,
+    BoolOrDefault,
+    ButtonType,
+    Bool,
+    CrossOrigin,
+    FormEncodingType,
+    FormMethod,
+    FormDialogMethod,
+    HTTPEquiv,
+    ImageLoading,
+    ImageDecoding,
+    InputType,
+    LinkType,
+    Metadata,
+    OnOff,
+    OrderedListType,
+    Preload,
+    ReferrerPolicy,
+    Role,
+    Sandbox,
+    TableHeaderScope,
+    TextDirection,
+    VideoKind,
+    Wrap
+);
+
+// A list could be anything once its separators are accounted for.
+impl<A> AttributeValue for SpacedList<A> {
+    const SYNTAX: ValueSyntax = ValueSyntax::Any;
+}
+
+impl<A: Ord> AttributeValue for SpacedSet<A> {
+    const SYNTAX: ValueSyntax = ValueSyntax::Any;
+}
+
+#[derive(
+    EnumString, EnumVariantNames, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr,
+)]
 pub enum AreaShape {
     #[strum(to_string = "rect")]
     Rectangle,
@@ -40,7 +214,9 @@ pub enum AreaShape {
     Default,
 }
 
-#[derive(EnumString, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr)]
+#[derive(
+    EnumString, EnumVariantNames, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr,
+)]
 pub enum BoolOrDefault {
     #[strum(to_string = "true")]
     True,
@@ -50,7 +226,9 @@ pub enum BoolOrDefault {
     False,
 }
 
-#[derive(EnumString, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr)]
+#[derive(
+    EnumString, EnumVariantNames, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr,
+)]
 pub enum ButtonType {
     #[strum(to_string = "submit")]
     Submit,
@@ -60,7 +238,9 @@ pub enum ButtonType {
     Button,
 }
 
-#[derive(EnumString, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr)]
+#[derive(
+    EnumString, EnumVariantNames, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr,
+)]
 pub enum Bool {
     #[strum(to_string = "true")]
     True,
@@ -78,7 +258,9 @@ impl From<bool> for Bool {
     }
 }
 
-#[derive(EnumString, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr)]
+#[derive(
+    EnumString, EnumVariantNames, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr,
+)]
 pub enum CrossOrigin {
     #[strum(to_string = "anonymous")]
     Anonymous,
@@ -86,7 +268,9 @@ pub enum CrossOrigin {
     UseCredentials,
 }
 
-#[derive(EnumString, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr)]
+#[derive(
+    EnumString, EnumVariantNames, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr,
+)]
 pub enum FormEncodingType {
     #[strum(to_string = "application/x-www-form-urlencoded")]
     UrlEncoded,
@@ -96,7 +280,9 @@ pub enum FormEncodingType {
     Text,
 }
 
-#[derive(EnumString, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr)]
+#[derive(
+    EnumString, EnumVariantNames, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr,
+)]
 pub enum FormMethod {
     #[strum(to_string = "post")]
     Post,
@@ -104,7 +290,9 @@ pub enum FormMethod {
     Get,
 }
 
-#[derive(EnumString, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr)]
+#[derive(
+    EnumString, EnumVariantNames, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr,
+)]
 pub enum FormDialogMethod {
     #[strum(to_string = "post")]
     Post,
@@ -114,7 +302,9 @@ pub enum FormDialogMethod {
     Dialog,
 }
 
-#[derive(EnumString, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr)]
+#[derive(
+    EnumString, EnumVariantNames, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr,
+)]
 pub enum HTTPEquiv {
     #[strum(to_string = "content-security-policy")]
     ContentSecurityPolicy,
@@ -122,7 +312,19 @@ pub enum HTTPEquiv {
     Refresh,
 }
 
-#[derive(EnumString, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr)]
+#[derive(
+    EnumString, EnumVariantNames, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr,
+)]
+pub enum ImageLoading {
+    #[strum(to_string = "eager")]
+    Eager,
+    #[strum(to_string = "lazy")]
+    Lazy,
+}
+
+#[derive(
+    EnumString, EnumVariantNames, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr,
+)]
 pub enum ImageDecoding {
     #[strum(to_string = "sync")]
     Sync,
@@ -132,7 +334,9 @@ pub enum ImageDecoding {
     Auto,
 }
 
-#[derive(EnumString, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr)]
+#[derive(
+    EnumString, EnumVariantNames, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr,
+)]
 pub enum InputType {
     #[strum(to_string = "button")]
     Button,
@@ -180,7 +384,9 @@ pub enum InputType {
     Week,
 }
 
-#[derive(EnumString, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr)]
+#[derive(
+    EnumString, EnumVariantNames, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr,
+)]
 pub enum LinkType {
     #[strum(to_string = "alternate")]
     Alternate,
@@ -228,7 +434,9 @@ pub enum LinkType {
     Tag,
 }
 
-#[derive(EnumString, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr)]
+#[derive(
+    EnumString, EnumVariantNames, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr,
+)]
 pub enum Metadata {
     #[strum(to_string = "application-name")]
     ApplicationName,
@@ -254,7 +462,9 @@ pub enum Metadata {
     Viewport,
 }
 
-#[derive(EnumString, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr)]
+#[derive(
+    EnumString, EnumVariantNames, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr,
+)]
 pub enum OnOff {
     #[strum(to_string = "on")]
     On,
@@ -262,7 +472,9 @@ pub enum OnOff {
     Off,
 }
 
-#[derive(EnumString, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr)]
+#[derive(
+    EnumString, EnumVariantNames, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr,
+)]
 pub enum OrderedListType {
     #[strum(to_string = "a")]
     LowerCaseLetters,
@@ -276,7 +488,9 @@ pub enum OrderedListType {
     Numbers,
 }
 
-#[derive(EnumString, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr)]
+#[derive(
+    EnumString, EnumVariantNames, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr,
+)]
 pub enum Preload {
     #[strum(to_string = "none")]
     None,
@@ -286,7 +500,9 @@ pub enum Preload {
     Auto,
 }
 
-#[derive(EnumString, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr)]
+#[derive(
+    EnumString, EnumVariantNames, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr,
+)]
 pub enum ReferrerPolicy {
     #[strum(to_string = "no-referrer")]
     NoReferrer,
@@ -300,7 +516,9 @@ pub enum ReferrerPolicy {
     UnsafeUrl,
 }
 
-#[derive(EnumString, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr)]
+#[derive(
+    EnumString, EnumVariantNames, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr,
+)]
 pub enum Role {
     #[strum(to_string = "any")]
     Any,
@@ -440,7 +658,9 @@ pub enum Role {
     TreeGrid,
 }
 
-#[derive(EnumString, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr)]
+#[derive(
+    EnumString, EnumVariantNames, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr,
+)]
 pub enum Sandbox {
     #[strum(to_string = "allow-forms")]
     AllowForms,
@@ -466,7 +686,9 @@ pub enum Sandbox {
     AllowTopNavigationByUserNavigation,
 }
 
-#[derive(EnumString, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr)]
+#[derive(
+    EnumString, EnumVariantNames, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr,
+)]
 pub enum TableHeaderScope {
     #[strum(to_string = "row")]
     Row,
@@ -480,7 +702,9 @@ pub enum TableHeaderScope {
     Auto,
 }
 
-#[derive(EnumString, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr)]
+#[derive(
+    EnumString, EnumVariantNames, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr,
+)]
 pub enum TextDirection {
     #[strum(to_string = "ltr")]
     LeftToRight,
@@ -488,7 +712,9 @@ pub enum TextDirection {
     RightToLeft,
 }
 
-#[derive(EnumString, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr)]
+#[derive(
+    EnumString, EnumVariantNames, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr,
+)]
 pub enum VideoKind {
     #[strum(to_string = "subtitles")]
     Subtitles,
@@ -502,7 +728,9 @@ pub enum VideoKind {
     Metadata,
 }
 
-#[derive(EnumString, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr)]
+#[derive(
+    EnumString, EnumVariantNames, Display, PartialEq, Eq, PartialOrd, Ord, AsRefStr, IntoStaticStr,
+)]
 pub enum Wrap {
     #[strum(to_string = "hard")]
     Hard,
@@ -63,6 +63,14 @@ impl<'a, A: 'a + Clone> FromIterator<&'a A> for SpacedList<A> {
     }
 }
 
+impl<A: FromStr> FromStr for SpacedList<A> {
+    type Err = <A as FromStr>::Err;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split_whitespace().map(FromStr::from_str).collect()
+    }
+}
+
 impl<'a, A> TryFrom<&'a str> for SpacedList<A>
 where
     A: FromStr,
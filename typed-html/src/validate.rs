@@ -0,0 +1,289 @@
+//! Validation of ID usage in DOM trees.
+//!
+//! The type system can't tell whether an `id` is unique in a document, or
+//! whether `<label for="name">` refers to an element which actually exists.
+//! [`validate()`][validate] checks this after the fact, by walking a rendered
+//! [`VNode`][VNode] tree once and keeping a table of the IDs it finds, which is
+//! cheap enough to do on every render in a debug build.
+//!
+//! # Examples
+//!
+//! ```
+//! # use typed_html::html;
+//! # use typed_html::dom::{DOMTree, Node};
+//! # use typed_html::validate::validate;
+//! # fn main() {
+//! let mut doc: DOMTree<String> = html!(
+//!     <form id="login">
+//!         <label for="username">"Username"</label>
+//!         <input id="user" name="username"/>
+//!     </form>
+//! );
+//! let errors = validate(&doc.vnode()).unwrap_err();
+//! assert_eq!(
+//!     "form > label:nth-child(1): for=\"username\" doesn't refer to any element",
+//!     errors[0].to_string()
+//! );
+//! # }
+//! ```
+//!
+//! [validate]: fn.validate.html
+//! [VNode]: ../dom/enum.VNode.html
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Error, Formatter};
+use std::str::FromStr;
+
+use crate::dom::{ElementPath, VElement, VNode};
+use crate::types::{Id, SpacedSet};
+use crate::OutputType;
+
+/// Attributes whose values are lists of element IDs.
+static ID_REFERENCES: &[&str] = &["contextmenu", "for", "form", "headers", "list"];
+
+/// A problem found by [`validate()`][validate].
+///
+/// [validate]: fn.validate.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidationError {
+    /// An ID is used by more than one element.
+    DuplicateId {
+        id: Id,
+        /// The first element using the ID.
+        first: ElementPath,
+        /// The element reusing it.
+        path: ElementPath,
+    },
+    /// An attribute refers to an ID (or, for `usemap`, a map name) which no
+    /// element in the tree has.
+    DanglingReference {
+        attribute: &'static str,
+        id: String,
+        path: ElementPath,
+    },
+    /// An `id` or reference attribute doesn't contain a valid ID.
+    InvalidId {
+        attribute: &'static str,
+        value: String,
+        path: ElementPath,
+    },
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            ValidationError::DuplicateId { id, first, path } => {
+                write!(f, "{}: id=\"{}\" is already used by {}", path, id, first)
+            }
+            ValidationError::DanglingReference {
+                attribute,
+                id,
+                path,
+            } => write!(
+                f,
+                "{}: {}=\"{}\" doesn't refer to any element",
+                path, attribute, id
+            ),
+            ValidationError::InvalidId {
+                attribute,
+                value,
+                path,
+            } => write!(f, "{}: {}={:?} is not a valid ID", path, attribute, value),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+#[derive(Default)]
+struct Validator {
+    ids: HashMap<Id, ElementPath>,
+    map_names: HashSet<String>,
+    references: Vec<(&'static str, Id, ElementPath)>,
+    map_references: Vec<(String, ElementPath)>,
+    errors: Vec<ValidationError>,
+}
+
+impl Validator {
+    fn element<T: OutputType + Send>(&mut self, element: &VElement<'_, T>, path: ElementPath) {
+        for (key, value) in &element.attributes {
+            match key.as_ref() {
+                "id" => match Id::from_str(value) {
+                    Ok(id) => {
+                        if let Some(first) = self.ids.get(&id) {
+                            self.errors.push(ValidationError::DuplicateId {
+                                id,
+                                first: first.clone(),
+                                path: path.clone(),
+                            });
+                        } else {
+                            self.ids.insert(id, path.clone());
+                        }
+                    }
+                    Err(_) => self.invalid("id", value, &path),
+                },
+                "name" if element.name == "map" => {
+                    self.map_names.insert(value.clone());
+                }
+                "usemap" => match value.strip_prefix('#') {
+                    Some(name) if !name.is_empty() => {
+                        self.map_references.push((name.to_string(), path.clone()))
+                    }
+                    _ => self.invalid("usemap", value, &path),
+                },
+                key => {
+                    let reference = ID_REFERENCES.iter().find(|reference| **reference == key);
+                    if let Some(&key) = reference {
+                        match SpacedSet::<Id>::from_str(value) {
+                            Ok(ids) => {
+                                for id in ids.iter() {
+                                    self.references.push((key, id.clone(), path.clone()));
+                                }
+                            }
+                            Err(_) => self.invalid(key, value, &path),
+                        }
+                    }
+                }
+            }
+        }
+        self.children(&element.children, &path);
+    }
+
+    fn children<T: OutputType + Send>(&mut self, children: &[VNode<'_, T>], path: &ElementPath) {
+        let elements = children.iter().filter_map(|child| match child {
+            VNode::Element(element) => Some(element),
+            _ => None,
+        });
+        for (index, element) in elements.enumerate() {
+            self.element(element, path.child(element.name, index + 1));
+        }
+    }
+
+    fn invalid(&mut self, attribute: &'static str, value: &str, path: &ElementPath) {
+        self.errors.push(ValidationError::InvalidId {
+            attribute,
+            value: value.to_string(),
+            path: path.clone(),
+        });
+    }
+
+    fn finish(mut self) -> Vec<ValidationError> {
+        for (attribute, id, path) in self.references {
+            if !self.ids.contains_key(&id) {
+                self.errors.push(ValidationError::DanglingReference {
+                    attribute,
+                    id: id.to_string(),
+                    path,
+                });
+            }
+        }
+        let ids = &self.ids;
+        for (name, path) in self.map_references {
+            let is_id = Id::from_str(&name)
+                .map(|id| ids.contains_key(&id))
+                .unwrap_or(false);
+            if !is_id && !self.map_names.contains(&name) {
+                self.errors.push(ValidationError::DanglingReference {
+                    attribute: "usemap",
+                    id: name,
+                    path,
+                });
+            }
+        }
+        self.errors
+    }
+}
+
+/// Check that every `id` in a tree is unique, and that every attribute
+/// referring to an element by its ID refers to one which exists in the tree.
+///
+/// The attributes checked are `for` (on `<label>` and `<output>`), `list`,
+/// `form`, `headers`, `contextmenu` and `usemap`, which should name a
+/// `<map>` element.
+///
+/// Returns every problem found, in document order for duplicate and invalid
+/// IDs, followed by dangling references.
+pub fn validate<T: OutputType + Send>(vnode: &VNode<'_, T>) -> Result<(), Vec<ValidationError>> {
+    let mut validator = Validator::default();
+    if let VNode::Element(element) = vnode {
+        validator.element(element, ElementPath::new().child(element.name, 1));
+    }
+    let errors = validator.finish();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate as typed_html;
+    use crate::dom::DOMTree;
+    use crate::html;
+
+    #[test]
+    fn test_valid_references() {
+        let mut doc: DOMTree<String> = html!(
+            <div>
+                <form id="search"/>
+                <label for="query">"Query"</label>
+                <input id="query" form="search" list="suggestions"/>
+                <datalist id="suggestions"/>
+                <img src="map.png" usemap="#places"/>
+                <map name="places"/>
+                <div data-id="search"/>
+            </div>
+        );
+        assert_eq!(Ok(()), validate(&doc.vnode()));
+    }
+
+    #[test]
+    fn test_duplicate_ids() {
+        let mut doc: DOMTree<String> = html!(
+            <div id="main">
+                <p id="intro">"Hello"</p>
+                <p id="intro">"Joe"</p>
+            </div>
+        );
+        assert_eq!(
+            Err(vec![ValidationError::DuplicateId {
+                id: Id::new("intro"),
+                first: ElementPath(vec![("div", 1), ("p", 1)]),
+                path: ElementPath(vec![("div", 1), ("p", 2)]),
+            }]),
+            validate(&doc.vnode())
+        );
+    }
+
+    #[test]
+    fn test_dangling_references() {
+        let mut doc: DOMTree<String> = html!(
+            <table>
+                <tr>
+                    <th id="name">"Name"</th>
+                    <td headers="name age">"Joe"</td>
+                </tr>
+            </table>
+        );
+        let errors = validate(&doc.vnode()).unwrap_err();
+        assert_eq!(1, errors.len());
+        assert_eq!(
+            "table > tr:nth-child(1) > td:nth-child(2): headers=\"age\" doesn't refer to any element",
+            errors[0].to_string()
+        );
+    }
+
+    #[test]
+    fn test_path_skips_text() {
+        let mut doc: DOMTree<String> = html!(
+            <div>"Name: " <label for="name">"Name"</label></div>
+        );
+        let errors = validate(&doc.vnode()).unwrap_err();
+        assert_eq!(
+            "div > label:nth-child(1): for=\"name\" doesn't refer to any element",
+            errors[0].to_string()
+        );
+    }
+}
@@ -0,0 +1,13 @@
+#![feature(proc_macro_hygiene)]
+
+extern crate typed_html;
+
+use typed_html::html;
+use typed_html::dom::DOMTree;
+
+fn main() {
+    let width = 100;
+    let _: DOMTree<String> = html!{
+        <img src="kitty.png" alt="Hello Kitty" width=f!"{width}px"/>
+    };
+}